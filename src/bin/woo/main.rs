@@ -0,0 +1,681 @@
+//! `woo`: day to day store operations without hand-built curl requests.
+//!
+//! Credentials come from a profile in the profiles file (see [`profile::Profile`]),
+//! with `WOO_URL`, `WOO_CONSUMER_KEY`, `WOO_CONSUMER_SECRET` and `WOO_AUTH` taking
+//! precedence. Output is a table by default, `--output json` prints the API objects.
+
+mod output;
+mod profile;
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::{Args, Parser, Subcommand};
+use serde::de::DeserializeOwned;
+use woocommerce_client::{
+    Coupon, CouponClient, CouponQuery, CreateCoupon, CreateOrderNote, CreateRefund, CreateWebhook,
+    CustomerId, DiscountType, Money, Order, OrderClient, OrderId, OrderQuery, OrderStatus, Product,
+    ProductClient, ProductId, StatusTransition, TransitionPolicy, UpdateProduct, Webhook,
+    WebhookClient, WebhookStatus, WebhookTopic, WooCommerceHttpError, WooDateTimeUtc,
+    WooHttpClient,
+};
+
+use output::{label, optional, print_json, print_table, OutputFormat, Table};
+
+#[derive(Parser)]
+#[command(name = "woo", version, about = "WooCommerce store operations")]
+struct Cli {
+    /// Profile to read from the profiles file.
+    #[arg(long, short, global = true, env = "WOO_PROFILE")]
+    #[arg(default_value = profile::DEFAULT_PROFILE)]
+    profile: String,
+
+    /// Profiles file [default: $WOO_CONFIG or ~/.config/woo/profiles.toml]
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    #[arg(long, short, global = true, value_enum, default_value_t = OutputFormat::Table)]
+    output: OutputFormat,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    #[command(subcommand)]
+    Orders(OrdersCommand),
+    #[command(subcommand)]
+    Coupons(CouponsCommand),
+    #[command(subcommand)]
+    Products(ProductsCommand),
+    #[command(subcommand)]
+    Webhooks(WebhooksCommand),
+}
+
+#[derive(Args)]
+struct PageArgs {
+    #[arg(long, default_value_t = 1)]
+    page: usize,
+
+    #[arg(long, default_value_t = 20, value_parser = clap::value_parser!(u8).range(1..=100))]
+    per_page: u8,
+}
+
+#[derive(Subcommand)]
+enum OrdersCommand {
+    List {
+        /// Repeat or comma-separate for several, e.g. `processing,on-hold`.
+        #[arg(long, value_delimiter = ',', value_parser = wire::<OrderStatus>)]
+        status: Vec<OrderStatus>,
+
+        #[arg(long)]
+        customer: Option<CustomerId>,
+
+        #[arg(long)]
+        search: Option<String>,
+
+        /// Created after, GMT, e.g. `2024-03-01T00:00:00`.
+        #[arg(long)]
+        after: Option<WooDateTimeUtc>,
+
+        /// Created before, GMT.
+        #[arg(long)]
+        before: Option<WooDateTimeUtc>,
+
+        #[command(flatten)]
+        paging: PageArgs,
+    },
+    Get {
+        id: OrderId,
+    },
+    /// Moves the order to a new status, refusing transitions a paid order can't make.
+    SetStatus {
+        id: OrderId,
+
+        #[arg(value_parser = wire::<OrderStatus>)]
+        status: OrderStatus,
+
+        /// Adds a note explaining the change.
+        #[arg(long)]
+        note: Option<String>,
+
+        /// Makes the note visible to the customer and emails it.
+        #[arg(long, requires = "note")]
+        notify_customer: bool,
+
+        /// Applies transitions outside the usual flow instead of refusing them.
+        #[arg(long)]
+        force: bool,
+    },
+    Note {
+        id: OrderId,
+
+        text: String,
+
+        /// Visible to the customer, who is emailed about it.
+        #[arg(long)]
+        customer: bool,
+    },
+    /// Records a refund. The money is only returned through the gateway with `--gateway`.
+    Refund {
+        id: OrderId,
+
+        #[arg(long)]
+        amount: Money,
+
+        #[arg(long, default_value = "")]
+        reason: String,
+
+        #[arg(long)]
+        gateway: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum CouponsCommand {
+    Create {
+        code: String,
+
+        /// `percent`, `fixed_cart` or `fixed_product`.
+        #[arg(long = "type", value_parser = wire::<DiscountType>)]
+        discount_type: DiscountType,
+
+        #[arg(long)]
+        amount: Money,
+
+        #[arg(long, default_value = "")]
+        description: String,
+
+        /// GMT, e.g. `2024-12-31T23:59:59`.
+        #[arg(long)]
+        expires: Option<WooDateTimeUtc>,
+
+        #[arg(long)]
+        usage_limit: Option<i32>,
+
+        #[arg(long)]
+        usage_limit_per_user: Option<i32>,
+
+        #[arg(long)]
+        minimum_amount: Option<Money>,
+
+        #[arg(long)]
+        individual_use: bool,
+
+        #[arg(long)]
+        free_shipping: bool,
+    },
+    List {
+        #[arg(long)]
+        search: Option<String>,
+
+        #[command(flatten)]
+        paging: PageArgs,
+    },
+    /// Deletes the coupon with this code for good.
+    Delete {
+        code: String,
+    },
+    Find {
+        code: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ProductsCommand {
+    List {
+        #[command(flatten)]
+        paging: PageArgs,
+    },
+    Get {
+        id: ProductId,
+    },
+    UpdatePrice {
+        id: ProductId,
+
+        #[arg(long, required_unless_present_any = ["sale", "clear_sale"])]
+        regular: Option<Money>,
+
+        #[arg(long)]
+        sale: Option<Money>,
+
+        /// Ends the sale, removing the sale price.
+        #[arg(long, conflicts_with = "sale")]
+        clear_sale: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum WebhooksCommand {
+    List {
+        #[command(flatten)]
+        paging: PageArgs,
+    },
+    Create {
+        /// e.g. `order.created` or `coupon.updated`.
+        #[arg(long)]
+        topic: WebhookTopic,
+
+        #[arg(long)]
+        url: String,
+
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Signs deliveries, see `X-WC-Webhook-Signature`.
+        #[arg(long)]
+        secret: Option<String>,
+
+        /// Creates the webhook paused.
+        #[arg(long)]
+        paused: bool,
+    },
+}
+
+/// Parses the API's name for a serde enum, e.g. `on-hold` or `fixed_cart`.
+fn wire<T: DeserializeOwned>(value: &str) -> Result<T, String> {
+    serde_json::from_value(serde_json::Value::String(value.to_string()))
+        .map_err(|_| format!("unknown value {:?}", value))
+}
+
+fn describe(err: WooCommerceHttpError) -> String {
+    match err {
+        WooCommerceHttpError::ErrorResponse(err) => {
+            format!("{} ({}): {}", err.code, err.data.status, err.message)
+        }
+        WooCommerceHttpError::SerdeError(err) => format!("unexpected response: {}", err),
+        WooCommerceHttpError::InvalidStatusTransition { order_id, from, to } => format!(
+            "order {} can't move from {} to {}, use --force to do it anyway",
+            order_id, from, to
+        ),
+        WooCommerceHttpError::NoteFailed { order, error } => format!(
+            "order {} is now {}, but adding the note failed: {}",
+            order.id,
+            order.status,
+            describe(*error)
+        ),
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(cli: Cli) -> Result<(), String> {
+    let path = cli.config.clone().or_else(profile::default_path);
+    let client = profile::resolve(path.as_deref(), &cli.profile)?.client()?;
+    let out = cli.output;
+    match cli.command {
+        Command::Orders(command) => orders(&client, command, out).await,
+        Command::Coupons(command) => coupons(&client, command, out).await,
+        Command::Products(command) => products(&client, command, out).await,
+        Command::Webhooks(command) => webhooks(&client, command, out).await,
+    }
+}
+
+fn show<T: serde::Serialize>(out: OutputFormat, value: &T, table: Table) -> Result<(), String> {
+    match out {
+        OutputFormat::Json => print_json(value),
+        OutputFormat::Table => print_table(&table),
+    }
+    .map_err(|err| err.to_string())
+}
+
+fn order_table(orders: &[Order]) -> Table {
+    let mut table = Table::new(&["ID", "NUMBER", "STATUS", "CREATED (GMT)", "EMAIL", "TOTAL"]);
+    for order in orders {
+        table.row(vec![
+            order.id.to_string(),
+            order.number.clone(),
+            order.status.to_string(),
+            order.date_created_gmt.to_string(),
+            order.billing.email.clone(),
+            format!("{} {}", order.total, order.currency),
+        ]);
+    }
+    table
+}
+
+async fn orders(
+    client: &WooHttpClient,
+    command: OrdersCommand,
+    out: OutputFormat,
+) -> Result<(), String> {
+    match command {
+        OrdersCommand::List {
+            status,
+            customer,
+            search,
+            after,
+            before,
+            paging,
+        } => {
+            let mut query = OrderQuery::new()
+                .page(paging.page, paging.per_page as usize)
+                .status(&status)
+                .created_between(after, before);
+            if let Some(customer) = customer {
+                query = query.customer(customer);
+            }
+            if let Some(search) = search {
+                query = query.search(&search);
+            }
+            let orders = client.list_orders(&query).await.map_err(describe)?;
+            show(out, &orders, order_table(&orders))
+        }
+        OrdersCommand::Get { id } => {
+            let order = client
+                .get_order(id)
+                .await
+                .map_err(describe)?
+                .ok_or(format!("order {} not found", id))?;
+            if out == OutputFormat::Json {
+                return show(out, &order, Table::new(&[]));
+            }
+            show(out, &order, order_table(std::slice::from_ref(&order)))?;
+            println!();
+            let mut items = Table::new(&["PRODUCT", "SKU", "NAME", "QTY", "TOTAL"]);
+            for item in &order.line_items {
+                items.row(vec![
+                    item.product_id.to_string(),
+                    item.sku.clone().unwrap_or_default(),
+                    item.name.clone(),
+                    item.quantity.to_string(),
+                    item.total.to_string(),
+                ]);
+            }
+            show(out, &order, items)
+        }
+        OrdersCommand::SetStatus {
+            id,
+            status,
+            note,
+            notify_customer,
+            force,
+        } => {
+            let mut transition = StatusTransition::new(status).notify_customer(notify_customer);
+            if let Some(note) = note {
+                transition = transition.note(&note);
+            }
+            if force {
+                transition = transition.policy(TransitionPolicy::Warn);
+            }
+            let (order, note_error) = match client.transition_order(id, &transition).await {
+                Ok(order) => (order.ok_or(format!("order {} not found", id))?, None),
+                // The status did change, so show the order and still fail the command.
+                Err(WooCommerceHttpError::NoteFailed { order, error }) => {
+                    (*order, Some(describe(*error)))
+                }
+                Err(err) => return Err(describe(err)),
+            };
+            show(out, &order, order_table(std::slice::from_ref(&order)))?;
+            match note_error {
+                Some(err) => Err(format!(
+                    "order {} is now {}, but adding the note failed: {}",
+                    order.id, order.status, err
+                )),
+                None => Ok(()),
+            }
+        }
+        OrdersCommand::Note { id, text, customer } => {
+            let note = CreateOrderNote {
+                note: text,
+                customer_note: customer,
+                added_by_user: true,
+            };
+            let note = client
+                .create_order_note(id, &note)
+                .await
+                .map_err(describe)?;
+            let mut table = Table::new(&["ID", "CREATED (GMT)", "CUSTOMER", "NOTE"]);
+            table.row(vec![
+                note.id.to_string(),
+                note.date_created_gmt.to_string(),
+                note.customer_note.to_string(),
+                note.note.clone(),
+            ]);
+            show(out, &note, table)
+        }
+        OrdersCommand::Refund {
+            id,
+            amount,
+            reason,
+            gateway,
+        } => {
+            let refund = CreateRefund::new(amount)
+                .reason(&reason)
+                .api_refund(gateway);
+            let refund = client.create_refund(id, &refund).await.map_err(describe)?;
+            let mut table = Table::new(&["ID", "ORDER", "AMOUNT", "VIA GATEWAY", "REASON"]);
+            table.row(vec![
+                refund.id.to_string(),
+                id.to_string(),
+                refund.amount.to_string(),
+                refund.refunded_payment.to_string(),
+                refund.reason.clone(),
+            ]);
+            show(out, &refund, table)
+        }
+    }
+}
+
+fn coupon_table(coupons: &[Coupon]) -> Table {
+    let mut table = Table::new(&[
+        "ID",
+        "CODE",
+        "TYPE",
+        "AMOUNT",
+        "USED",
+        "LIMIT",
+        "EXPIRES (GMT)",
+    ]);
+    for coupon in coupons {
+        table.row(vec![
+            coupon.id.to_string(),
+            coupon.code.clone(),
+            label(&coupon.discount_type),
+            coupon.amount.to_string(),
+            coupon.usage_count.to_string(),
+            optional(coupon.usage_limit),
+            optional(coupon.date_expires_gmt),
+        ]);
+    }
+    table
+}
+
+async fn coupons(
+    client: &WooHttpClient,
+    command: CouponsCommand,
+    out: OutputFormat,
+) -> Result<(), String> {
+    match command {
+        CouponsCommand::Create {
+            code,
+            discount_type,
+            amount,
+            description,
+            expires,
+            usage_limit,
+            usage_limit_per_user,
+            minimum_amount,
+            individual_use,
+            free_shipping,
+        } => {
+            let mut builder = CreateCoupon::builder(&code, discount_type, amount)
+                .description(&description)
+                .individual_use(individual_use)
+                .free_shipping(free_shipping);
+            if let Some(expires) = expires {
+                builder = builder.expires(expires);
+            }
+            if let Some(limit) = usage_limit {
+                builder = builder.usage_limit(limit);
+            }
+            if let Some(limit) = usage_limit_per_user {
+                builder = builder.usage_limit_per_user(limit);
+            }
+            if let Some(amount) = minimum_amount {
+                builder = builder.minimum_amount(amount);
+            }
+            let coupon = builder.build().map_err(|err| err.to_string())?;
+            let coupon = client.create_coupon(&coupon).await.map_err(describe)?;
+            show(out, &coupon, coupon_table(std::slice::from_ref(&coupon)))
+        }
+        CouponsCommand::List { search, paging } => {
+            let mut query = CouponQuery::new().page(paging.page, paging.per_page as usize);
+            if let Some(search) = search {
+                query = query.search(&search);
+            }
+            let coupons = client.list_coupons(&query).await.map_err(describe)?;
+            show(out, &coupons, coupon_table(&coupons))
+        }
+        CouponsCommand::Delete { code } => {
+            let coupon = client
+                .delete_coupon_by_code(&code)
+                .await
+                .map_err(describe)?
+                .ok_or(format!("no coupon {:?}", code))?;
+            show(out, &coupon, coupon_table(std::slice::from_ref(&coupon)))
+        }
+        CouponsCommand::Find { code } => {
+            let coupon = client
+                .find_coupon_by_code(&code)
+                .await
+                .map_err(describe)?
+                .ok_or(format!("no coupon {:?}", code))?;
+            show(out, &coupon, coupon_table(std::slice::from_ref(&coupon)))
+        }
+    }
+}
+
+fn product_table(products: &[Product]) -> Table {
+    let mut table = Table::new(&[
+        "ID", "SKU", "NAME", "STATUS", "PRICE", "REGULAR", "SALE", "STOCK",
+    ]);
+    for product in products {
+        table.row(vec![
+            product.id.to_string(),
+            product.sku.clone(),
+            product.name.clone(),
+            product.status.to_string(),
+            optional(product.price.as_ref()),
+            optional(product.regular_price.as_ref()),
+            optional(product.sale_price.as_ref()),
+            match product.stock_quantity {
+                Some(quantity) => format!("{} ({})", product.stock_status, quantity),
+                None => product.stock_status.to_string(),
+            },
+        ]);
+    }
+    table
+}
+
+async fn products(
+    client: &WooHttpClient,
+    command: ProductsCommand,
+    out: OutputFormat,
+) -> Result<(), String> {
+    match command {
+        ProductsCommand::List { paging } => {
+            let products = client
+                .get_products(paging.page, paging.per_page as usize)
+                .await
+                .map_err(describe)?;
+            show(out, &products, product_table(&products))
+        }
+        ProductsCommand::Get { id } => {
+            let product = client
+                .get_product(id)
+                .await
+                .map_err(describe)?
+                .ok_or(format!("product {} not found", id))?;
+            show(out, &product, product_table(std::slice::from_ref(&product)))
+        }
+        ProductsCommand::UpdatePrice {
+            id,
+            regular,
+            sale,
+            clear_sale,
+        } => {
+            let update = UpdateProduct {
+                regular_price: regular,
+                sale_price: if clear_sale {
+                    Some(None)
+                } else {
+                    sale.map(Some)
+                },
+                ..UpdateProduct::default()
+            };
+            let product = client.update_product(id, &update).await.map_err(describe)?;
+            show(out, &product, product_table(std::slice::from_ref(&product)))
+        }
+    }
+}
+
+fn webhook_table(webhooks: &[Webhook]) -> Table {
+    let mut table = Table::new(&["ID", "NAME", "STATUS", "TOPIC", "DELIVERY URL"]);
+    for webhook in webhooks {
+        table.row(vec![
+            webhook.id.to_string(),
+            webhook.name.clone(),
+            label(&webhook.status),
+            webhook.topic.to_string(),
+            webhook.delivery_url.clone(),
+        ]);
+    }
+    table
+}
+
+async fn webhooks(
+    client: &WooHttpClient,
+    command: WebhooksCommand,
+    out: OutputFormat,
+) -> Result<(), String> {
+    match command {
+        WebhooksCommand::List { paging } => {
+            let webhooks = client
+                .get_webhooks(paging.page, paging.per_page as usize)
+                .await
+                .map_err(describe)?;
+            show(out, &webhooks, webhook_table(&webhooks))
+        }
+        WebhooksCommand::Create {
+            topic,
+            url,
+            name,
+            secret,
+            paused,
+        } => {
+            let webhook = CreateWebhook {
+                name,
+                status: paused.then_some(WebhookStatus::Paused),
+                topic,
+                delivery_url: url,
+                secret,
+                api_version: None,
+            };
+            let webhook = client.create_webhook(&webhook).await.map_err(describe)?;
+            show(out, &webhook, webhook_table(std::slice::from_ref(&webhook)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+
+    #[test]
+    fn test_cli_args() {
+        Cli::command().debug_assert();
+
+        let cli = Cli::try_parse_from([
+            "woo",
+            "orders",
+            "list",
+            "--status",
+            "processing,on-hold",
+            "-o",
+            "json",
+        ])
+        .unwrap();
+        assert_eq!(cli.output, OutputFormat::Json);
+        let Command::Orders(OrdersCommand::List { status, .. }) = cli.command else {
+            panic!("expected orders list");
+        };
+        assert_eq!(status, vec![OrderStatus::Processing, OrderStatus::OnHold]);
+
+        assert!(Cli::try_parse_from(["woo", "orders", "set-status", "727", "shipped"]).is_err());
+        assert!(Cli::try_parse_from(["woo", "products", "update-price", "93"]).is_err());
+        let cli =
+            Cli::try_parse_from(["woo", "products", "update-price", "93", "--clear-sale"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Command::Products(ProductsCommand::UpdatePrice {
+                clear_sale: true,
+                sale: None,
+                ..
+            })
+        ));
+        assert!(Cli::try_parse_from([
+            "woo",
+            "products",
+            "update-price",
+            "93",
+            "--sale",
+            "5.00",
+            "--clear-sale"
+        ])
+        .is_err());
+    }
+}
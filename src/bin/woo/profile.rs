@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+use std::env;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use woocommerce_client::{Auth, OAuthSignatureMethod, WooHttpClient};
+
+pub const DEFAULT_PROFILE: &str = "default";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AuthMode {
+    Basic,
+    QueryString,
+    Oauth1,
+}
+
+/// One `[name]` table of the profiles file:
+///
+/// ```toml
+/// [default]
+/// url = "https://shop.example.com/wp-json"
+/// consumer_key = "ck_..."
+/// consumer_secret = "cs_..."
+/// # auth = "basic" | "query-string" | "oauth1", picked from the url scheme if unset
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    pub url: Option<String>,
+    pub consumer_key: Option<String>,
+    pub consumer_secret: Option<String>,
+    pub auth: Option<AuthMode>,
+}
+
+impl Profile {
+    /// `WOO_URL`, `WOO_CONSUMER_KEY`, `WOO_CONSUMER_SECRET` and `WOO_AUTH`.
+    pub fn from_env() -> Result<Self, String> {
+        let auth = match env::var("WOO_AUTH") {
+            Ok(auth) => Some(
+                toml::Value::String(auth.clone())
+                    .try_into()
+                    .map_err(|_| format!("unknown WOO_AUTH {:?}", auth))?,
+            ),
+            Err(_) => None,
+        };
+        Ok(Profile {
+            url: env::var("WOO_URL").ok(),
+            consumer_key: env::var("WOO_CONSUMER_KEY").ok(),
+            consumer_secret: env::var("WOO_CONSUMER_SECRET").ok(),
+            auth,
+        })
+    }
+
+    /// Fields set in `other` win.
+    pub fn merge(self, other: Profile) -> Profile {
+        Profile {
+            url: other.url.or(self.url),
+            consumer_key: other.consumer_key.or(self.consumer_key),
+            consumer_secret: other.consumer_secret.or(self.consumer_secret),
+            auth: other.auth.or(self.auth),
+        }
+    }
+
+    pub fn client(&self) -> Result<WooHttpClient, String> {
+        let url = self
+            .url
+            .as_deref()
+            .ok_or("no store url, set WOO_URL or `url`")?;
+        let url = url.trim_end_matches('/');
+        let key = self
+            .consumer_key
+            .as_deref()
+            .filter(|key| !key.is_empty())
+            .ok_or("no consumer key, set WOO_CONSUMER_KEY or `consumer_key`")?;
+        let secret = self
+            .consumer_secret
+            .as_deref()
+            .filter(|secret| !secret.is_empty())
+            .ok_or("no consumer secret, set WOO_CONSUMER_SECRET or `consumer_secret`")?;
+        let auth = match self.auth {
+            None => Auth::for_base_url(key, secret, url),
+            Some(AuthMode::Basic) => Auth::basic(key, secret),
+            Some(AuthMode::QueryString) => Auth::query_string(key, secret),
+            Some(AuthMode::Oauth1) => Auth::oauth1(key, secret, OAuthSignatureMethod::HmacSha256),
+        };
+        Ok(WooHttpClient::with_auth(auth, url))
+    }
+}
+
+/// `$WOO_CONFIG`, else `$XDG_CONFIG_HOME/woo/profiles.toml` or `~/.config/woo/profiles.toml`.
+pub fn default_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("WOO_CONFIG") {
+        return Some(PathBuf::from(path));
+    }
+    let config_dir = match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    Some(config_dir.join("woo").join("profiles.toml"))
+}
+
+pub fn parse(content: &str) -> Result<HashMap<String, Profile>, String> {
+    toml::from_str(content).map_err(|err| err.to_string())
+}
+
+/// Reads `name` from the profiles file and lays the environment over it. A missing file
+/// is fine for the default profile, so env-only setups keep working.
+pub fn resolve(path: Option<&Path>, name: &str) -> Result<Profile, String> {
+    let from_file = match path.map(std::fs::read_to_string) {
+        Some(Ok(content)) => {
+            let path = path.unwrap().display();
+            let mut profiles = parse(&content).map_err(|err| format!("{}: {}", path, err))?;
+            match profiles.remove(name) {
+                Some(profile) => profile,
+                None if name == DEFAULT_PROFILE => Profile::default(),
+                None => return Err(format!("{}: no profile {:?}", path, name)),
+            }
+        }
+        Some(Err(err)) if err.kind() != std::io::ErrorKind::NotFound => {
+            return Err(format!("{}: {}", path.unwrap().display(), err))
+        }
+        _ if name == DEFAULT_PROFILE => Profile::default(),
+        _ => return Err(format!("no profiles file for profile {:?}", name)),
+    };
+    Ok(from_file.merge(Profile::from_env()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_merge() {
+        let mut profiles = parse(
+            r#"
+            [default]
+            url = "https://shop.example.com/wp-json"
+            consumer_key = "ck_file"
+            consumer_secret = "cs_file"
+
+            [staging]
+            url = "http://staging.local/wp-json"
+            auth = "query-string"
+            "#,
+        )
+        .unwrap();
+        let staging = profiles.remove("staging").unwrap();
+        assert_eq!(staging.auth, Some(AuthMode::QueryString));
+
+        let merged = profiles.remove(DEFAULT_PROFILE).unwrap().merge(Profile {
+            consumer_key: Some("ck_env".to_string()),
+            ..Profile::default()
+        });
+        assert_eq!(merged.consumer_key.as_deref(), Some("ck_env"));
+        assert_eq!(merged.consumer_secret.as_deref(), Some("cs_file"));
+        assert!(merged.client().is_ok());
+        let blank_secret = Profile {
+            consumer_secret: Some(String::new()),
+            ..merged
+        };
+        assert!(blank_secret.client().is_err());
+
+        assert!(parse("[default]\nconsumer_keys = \"typo\"").is_err());
+        assert!(staging.client().is_err());
+    }
+}
@@ -0,0 +1,353 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use reqwest::Url;
+use sha2::{Digest, Sha256};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OAuthSignatureMethod {
+    HmacSha1,
+    HmacSha256,
+}
+
+impl OAuthSignatureMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OAuthSignatureMethod::HmacSha1 => "HMAC-SHA1",
+            OAuthSignatureMethod::HmacSha256 => "HMAC-SHA256",
+        }
+    }
+}
+
+/// How requests are authenticated against the store.
+///
+/// WooCommerce only accepts `Basic` and `QueryString` over HTTPS; plain HTTP
+/// stores (local docker setups) require one-legged OAuth 1.0a signing.
+#[derive(Debug, Clone)]
+pub enum Auth {
+    /// `Authorization: Basic base64(key:secret)` header.
+    Basic {
+        consumer_key: String,
+        consumer_secret: String,
+    },
+    /// `consumer_key` / `consumer_secret` query params, for hosts that strip the
+    /// `Authorization` header.
+    QueryString {
+        consumer_key: String,
+        consumer_secret: String,
+    },
+    OAuth1 {
+        consumer_key: String,
+        consumer_secret: String,
+        signature_method: OAuthSignatureMethod,
+    },
+}
+
+impl Auth {
+    pub fn basic(consumer_key: &str, consumer_secret: &str) -> Self {
+        Auth::Basic {
+            consumer_key: consumer_key.to_string(),
+            consumer_secret: consumer_secret.to_string(),
+        }
+    }
+
+    pub fn query_string(consumer_key: &str, consumer_secret: &str) -> Self {
+        Auth::QueryString {
+            consumer_key: consumer_key.to_string(),
+            consumer_secret: consumer_secret.to_string(),
+        }
+    }
+
+    pub fn oauth1(
+        consumer_key: &str,
+        consumer_secret: &str,
+        signature_method: OAuthSignatureMethod,
+    ) -> Self {
+        Auth::OAuth1 {
+            consumer_key: consumer_key.to_string(),
+            consumer_secret: consumer_secret.to_string(),
+            signature_method,
+        }
+    }
+
+    /// Basic auth for `https://` stores, OAuth 1.0a (HMAC-SHA256) otherwise.
+    pub fn for_base_url(consumer_key: &str, consumer_secret: &str, base_url: &str) -> Self {
+        if base_url.starts_with("https://") {
+            Auth::basic(consumer_key, consumer_secret)
+        } else {
+            Auth::oauth1(
+                consumer_key,
+                consumer_secret,
+                OAuthSignatureMethod::HmacSha256,
+            )
+        }
+    }
+
+    pub fn consumer_key(&self) -> &str {
+        match self {
+            Auth::Basic { consumer_key, .. }
+            | Auth::QueryString { consumer_key, .. }
+            | Auth::OAuth1 { consumer_key, .. } => consumer_key,
+        }
+    }
+
+    pub fn consumer_secret(&self) -> &str {
+        match self {
+            Auth::Basic {
+                consumer_secret, ..
+            }
+            | Auth::QueryString {
+                consumer_secret, ..
+            }
+            | Auth::OAuth1 {
+                consumer_secret, ..
+            } => consumer_secret,
+        }
+    }
+
+    pub(crate) fn authorization_header(&self) -> Option<String> {
+        match self {
+            Auth::Basic {
+                consumer_key,
+                consumer_secret,
+            } => {
+                let auth = format!("{}:{}", consumer_key, consumer_secret);
+                let auth_encoded = base64::engine::general_purpose::STANDARD.encode(auth);
+                Some(format!("Basic {}", auth_encoded))
+            }
+            Auth::QueryString { .. } | Auth::OAuth1 { .. } => None,
+        }
+    }
+
+    /// Adds the query params this strategy needs. `method` is only used for signing.
+    pub(crate) fn sign_url(&self, method: &str, url: &str) -> String {
+        match self {
+            Auth::Basic { .. } => url.to_string(),
+            Auth::QueryString {
+                consumer_key,
+                consumer_secret,
+            } => {
+                let Ok(mut url) = Url::parse(url) else {
+                    return url.to_string();
+                };
+                url.query_pairs_mut()
+                    .append_pair("consumer_key", consumer_key)
+                    .append_pair("consumer_secret", consumer_secret);
+                url.to_string()
+            }
+            Auth::OAuth1 {
+                consumer_key,
+                consumer_secret,
+                signature_method,
+            } => {
+                let timestamp = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or_default();
+                oauth1_sign_url(
+                    method,
+                    url,
+                    consumer_key,
+                    consumer_secret,
+                    *signature_method,
+                    &generate_nonce(),
+                    timestamp,
+                )
+            }
+        }
+    }
+}
+
+/// Appends the `oauth_*` params and the resulting `oauth_signature` to `url`.
+pub fn oauth1_sign_url(
+    method: &str,
+    url: &str,
+    consumer_key: &str,
+    consumer_secret: &str,
+    signature_method: OAuthSignatureMethod,
+    nonce: &str,
+    timestamp: u64,
+) -> String {
+    let Ok(mut url) = Url::parse(url) else {
+        return url.to_string();
+    };
+
+    let mut params: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+    params.push(("oauth_consumer_key".to_string(), consumer_key.to_string()));
+    params.push(("oauth_nonce".to_string(), nonce.to_string()));
+    params.push((
+        "oauth_signature_method".to_string(),
+        signature_method.as_str().to_string(),
+    ));
+    params.push(("oauth_timestamp".to_string(), timestamp.to_string()));
+
+    url.set_query(None);
+    url.set_fragment(None);
+    let base_string = oauth1_base_string(method, url.as_str(), &params);
+    let signature = oauth1_signature(&base_string, consumer_secret, signature_method);
+    params.push(("oauth_signature".to_string(), signature));
+
+    let query = params
+        .iter()
+        .map(|(key, value)| format!("{}={}", percent_encode(key), percent_encode(value)))
+        .collect::<Vec<_>>()
+        .join("&");
+    url.set_query(Some(&query));
+    url.to_string()
+}
+
+/// `METHOD&encoded(base url)&encoded(sorted, encoded params)` as per RFC 5849 3.4.1.
+pub fn oauth1_base_string(method: &str, base_url: &str, params: &[(String, String)]) -> String {
+    let mut encoded: Vec<(String, String)> = params
+        .iter()
+        .map(|(key, value)| (percent_encode(key), percent_encode(value)))
+        .collect();
+    encoded.sort();
+
+    let params = encoded
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<_>>()
+        .join("&");
+
+    format!(
+        "{}&{}&{}",
+        method.to_ascii_uppercase(),
+        percent_encode(base_url),
+        percent_encode(&params)
+    )
+}
+
+/// One-legged signature: the key is the consumer secret followed by an empty token secret.
+pub fn oauth1_signature(
+    base_string: &str,
+    consumer_secret: &str,
+    signature_method: OAuthSignatureMethod,
+) -> String {
+    let key = format!("{}&", consumer_secret);
+    let digest = match signature_method {
+        OAuthSignatureMethod::HmacSha1 => {
+            let mut mac = Hmac::<sha1::Sha1>::new_from_slice(key.as_bytes())
+                .expect("HMAC accepts any key size");
+            mac.update(base_string.as_bytes());
+            mac.finalize().into_bytes().to_vec()
+        }
+        OAuthSignatureMethod::HmacSha256 => {
+            let mut mac =
+                Hmac::<Sha256>::new_from_slice(key.as_bytes()).expect("HMAC accepts any key size");
+            mac.update(base_string.as_bytes());
+            mac.finalize().into_bytes().to_vec()
+        }
+    };
+    base64::engine::general_purpose::STANDARD.encode(digest)
+}
+
+/// RFC 3986 percent encoding: everything but unreserved characters is escaped.
+pub(crate) fn percent_encode(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                result.push(byte as char)
+            }
+            _ => result.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    result
+}
+
+fn generate_nonce() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    let mut hasher = Sha256::new();
+    hasher.update(nanos.to_le_bytes());
+    hasher.update(COUNTER.fetch_add(1, Ordering::Relaxed).to_le_bytes());
+    hasher.update(std::process::id().to_le_bytes());
+
+    hasher.finalize()[..16]
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_oauth1_hmac_sha1_vector() {
+        let url = oauth1_sign_url(
+            "GET",
+            "http://localhost:8080/wp-json/wc/v3/coupons?page=1&per_page=10",
+            "ck_test",
+            "cs_test",
+            OAuthSignatureMethod::HmacSha1,
+            "abc123",
+            1700000000,
+        );
+
+        assert_eq!(
+            url,
+            "http://localhost:8080/wp-json/wc/v3/coupons?page=1&per_page=10\
+             &oauth_consumer_key=ck_test&oauth_nonce=abc123\
+             &oauth_signature_method=HMAC-SHA1&oauth_timestamp=1700000000\
+             &oauth_signature=aKya%2F%2BYw%2FsBWC1LDvxxvZgfkcak%3D"
+        );
+    }
+
+    #[test]
+    fn test_oauth1_hmac_sha256_vector() {
+        let params = vec![
+            ("search".to_string(), "a b&c".to_string()),
+            ("oauth_consumer_key".to_string(), "ck_test".to_string()),
+            ("oauth_nonce".to_string(), "abc123".to_string()),
+            (
+                "oauth_signature_method".to_string(),
+                "HMAC-SHA256".to_string(),
+            ),
+            ("oauth_timestamp".to_string(), "1700000000".to_string()),
+        ];
+        let base_string = oauth1_base_string(
+            "post",
+            "http://localhost:8080/wp-json/wc/v3/orders",
+            &params,
+        );
+
+        assert_eq!(
+            base_string,
+            "POST&http%3A%2F%2Flocalhost%3A8080%2Fwp-json%2Fwc%2Fv3%2Forders\
+             &oauth_consumer_key%3Dck_test%26oauth_nonce%3Dabc123\
+             %26oauth_signature_method%3DHMAC-SHA256%26oauth_timestamp%3D1700000000\
+             %26search%3Da%2520b%2526c"
+        );
+        assert_eq!(
+            oauth1_signature(&base_string, "cs_test", OAuthSignatureMethod::HmacSha256),
+            "Vt/bgVgbHEjYA07Lv8/2W2HBNNXQSK42Eln2jkrQrJE="
+        );
+    }
+
+    #[test]
+    fn test_query_string_auth() {
+        let auth = Auth::query_string("ck_test", "cs_test");
+        assert_eq!(
+            auth.sign_url("GET", "https://example.com/wp-json/wc/v3/orders/1"),
+            "https://example.com/wp-json/wc/v3/orders/1?consumer_key=ck_test&consumer_secret=cs_test"
+        );
+        assert!(auth.authorization_header().is_none());
+    }
+
+    #[test]
+    fn test_basic_auth_header() {
+        let auth = Auth::basic("ck_test", "cs_test");
+        assert_eq!(
+            auth.authorization_header().unwrap(),
+            "Basic Y2tfdGVzdDpjc190ZXN0"
+        );
+    }
+}
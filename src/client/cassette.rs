@@ -0,0 +1,405 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    percent_encode, HttpRequest, HttpResponse, ReqwestTransport, Transport, TransportError,
+};
+
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Query params replaced with [`REDACTED`] before a request is stored or matched. The OAuth
+/// nonce and timestamp are not secret, but change on every request.
+const REDACTED_QUERY_PARAMS: &[&str] = &[
+    "consumer_key",
+    "consumer_secret",
+    "oauth_consumer_key",
+    "oauth_signature",
+    "oauth_nonce",
+    "oauth_timestamp",
+];
+
+const REDACTED_HEADERS: &[&str] = &["authorization", "cookie", "set-cookie"];
+
+/// Bodies are stored as the exact text that was sent or received, never re-encoded, so a
+/// replayed response parses to the same values (`"10.50"` stays `"10.50"`). Only the
+/// request headers and query strings carry credentials, and those are redacted.
+fn body_text(body: &[u8]) -> Option<String> {
+    if body.is_empty() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(body).into_owned())
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CassetteRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<String>,
+}
+
+impl CassetteRequest {
+    fn from_request(request: &HttpRequest) -> Self {
+        Self {
+            method: request.method.to_string(),
+            url: redact_url(&request.url),
+            headers: redact_headers(&request.headers),
+            body: request.body.as_deref().and_then(body_text),
+        }
+    }
+
+    fn matches(&self, other: &CassetteRequest) -> bool {
+        self.method == other.method && self.url == other.url && self.body == other.body
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CassetteResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<String>,
+}
+
+impl CassetteResponse {
+    pub fn to_response(&self) -> HttpResponse {
+        HttpResponse {
+            status: self.status,
+            headers: self.headers.clone(),
+            body: self
+                .body
+                .clone()
+                .map(String::into_bytes)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CassetteInteraction {
+    pub request: CassetteRequest,
+    pub response: CassetteResponse,
+}
+
+/// The recorded interactions, stored as pretty printed JSON.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Cassette {
+    pub interactions: Vec<CassetteInteraction>,
+}
+
+impl Cassette {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let content = std::fs::read(path)?;
+        serde_json::from_slice(&content).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        let content = serde_json::to_vec_pretty(self)?;
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, content)?;
+        std::fs::rename(tmp, path)
+    }
+}
+
+enum CassetteMode<T> {
+    Record(T),
+    Replay { used: Mutex<Vec<bool>> },
+}
+
+/// VCR style [`Transport`]. In record mode every request goes through the inner transport
+/// and the pair is appended to the cassette file, with credentials redacted. In replay mode
+/// responses come from the cassette and a request without a recorded match fails.
+///
+/// Requests match on method, redacted URL and body. Each recorded interaction is served
+/// once, in recording order.
+///
+/// ```ignore
+/// let transport = CassetteTransport::record("fixtures/cassettes/coupons.json", ReqwestTransport::new());
+/// let client = WooHttpClient::with_transport(auth, base_url, transport);
+/// ```
+pub struct CassetteTransport<T: Transport = ReqwestTransport> {
+    path: PathBuf,
+    mode: CassetteMode<T>,
+    cassette: Mutex<Cassette>,
+}
+
+impl<T: Transport> CassetteTransport<T> {
+    /// Starts an empty cassette at `path`, replacing any previous recording once the first
+    /// interaction is written.
+    pub fn record(path: impl AsRef<Path>, inner: T) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            mode: CassetteMode::Record(inner),
+            cassette: Mutex::new(Cassette::default()),
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        matches!(self.mode, CassetteMode::Record(_))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn cassette(&self) -> Cassette {
+        self.cassette.lock().unwrap().clone()
+    }
+}
+
+impl CassetteTransport {
+    pub fn replay(path: impl AsRef<Path>) -> io::Result<Self> {
+        let cassette = Cassette::load(&path)?;
+        let used = vec![false; cassette.interactions.len()];
+        Ok(Self {
+            path: path.as_ref().to_path_buf(),
+            mode: CassetteMode::Replay {
+                used: Mutex::new(used),
+            },
+            cassette: Mutex::new(cassette),
+        })
+    }
+}
+
+impl<T: Transport> Transport for CassetteTransport<T> {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, TransportError> {
+        let recorded = CassetteRequest::from_request(&request);
+
+        match &self.mode {
+            CassetteMode::Record(inner) => {
+                let response = inner.send(request).await?;
+
+                let mut cassette = self.cassette.lock().unwrap();
+                cassette.interactions.push(CassetteInteraction {
+                    request: recorded,
+                    response: CassetteResponse {
+                        status: response.status,
+                        headers: redact_headers(&response.headers),
+                        body: body_text(&response.body),
+                    },
+                });
+                cassette.save(&self.path).map_err(|e| {
+                    TransportError::Other(format!(
+                        "Can not write cassette {}: {}",
+                        self.path.display(),
+                        e
+                    ))
+                })?;
+
+                Ok(response)
+            }
+            CassetteMode::Replay { used } => {
+                let cassette = self.cassette.lock().unwrap();
+                let mut used = used.lock().unwrap();
+                let found =
+                    cassette
+                        .interactions
+                        .iter()
+                        .enumerate()
+                        .find(|(index, interaction)| {
+                            !used[*index] && interaction.request.matches(&recorded)
+                        });
+
+                match found {
+                    Some((index, interaction)) => {
+                        used[index] = true;
+                        Ok(interaction.response.to_response())
+                    }
+                    None => Err(TransportError::Other(format!(
+                        "Cassette {} has no unused interaction for {} {}",
+                        self.path.display(),
+                        recorded.method,
+                        recorded.url
+                    ))),
+                }
+            }
+        }
+    }
+}
+
+fn redact_url(url: &str) -> String {
+    let Some((path, _)) = url.split_once('?') else {
+        return url.to_string();
+    };
+    let Ok(parsed) = Url::parse(url) else {
+        return url.to_string();
+    };
+
+    let query: Vec<String> = parsed
+        .query_pairs()
+        .map(|(key, value)| {
+            let value = if REDACTED_QUERY_PARAMS.contains(&key.as_ref()) {
+                REDACTED.into()
+            } else {
+                value
+            };
+            format!("{}={}", percent_encode(&key), percent_encode(&value))
+        })
+        .collect();
+
+    format!("{}?{}", path, query.join("&"))
+}
+
+fn redact_headers(headers: &[(String, String)]) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            if REDACTED_HEADERS.contains(&name.to_ascii_lowercase().as_str()) {
+                (name.clone(), REDACTED.to_string())
+            } else {
+                (name.clone(), value.clone())
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Auth, CouponClient, CouponId, MockTransport, WooCommerceHttpError, WooHttpClient};
+
+    use reqwest::Method;
+
+    use super::*;
+
+    fn cassette_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("woo-cassette-{}-{}.json", name, std::process::id()))
+    }
+
+    #[tokio::test]
+    async fn test_record_then_replay() {
+        let path = cassette_path("record");
+
+        let mock = MockTransport::new();
+        mock.respond(
+            Method::GET,
+            "/wc/v3/coupons/719",
+            HttpResponse::new(200, include_str!("../../fixtures/coupon.json")),
+        );
+        let client = WooHttpClient::with_transport(
+            Auth::query_string("ck_live", "cs_live"),
+            "http://example.com/wp-json",
+            CassetteTransport::record(&path, mock),
+        );
+        assert_eq!(
+            client
+                .get_coupon(CouponId(719))
+                .await
+                .unwrap()
+                .unwrap()
+                .code,
+            "10off"
+        );
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(!content.contains("cs_live"));
+        assert!(!content.contains("ck_live"));
+
+        let client = WooHttpClient::with_transport(
+            Auth::basic("ck_other", "cs_other"),
+            "http://example.com/wp-json",
+            CassetteTransport::replay(&path).unwrap(),
+        );
+        // Basic auth sends no credentials in the query string, so the URL differs.
+        assert!(client.get_coupon(CouponId(719)).await.is_err());
+
+        let client = WooHttpClient::with_transport(
+            Auth::query_string("ck_other", "cs_other"),
+            "http://example.com/wp-json",
+            CassetteTransport::replay(&path).unwrap(),
+        );
+        let coupon = client.get_coupon(CouponId(719)).await.unwrap().unwrap();
+        assert_eq!(coupon.id, CouponId(719));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_bodies_replay_verbatim() {
+        let path = cassette_path("verbatim");
+        let body = r#"{"id":5,"secret":"wh-secret","amount":"10.50","rate":10.50}"#;
+
+        let mock = MockTransport::new();
+        mock.respond(
+            Method::POST,
+            "/wc/v3/webhooks",
+            HttpResponse::new(201, body),
+        );
+        let transport = CassetteTransport::record(&path, mock);
+        let request = HttpRequest::new(
+            Method::POST,
+            "https://example.com/wp-json/wc/v3/webhooks?consumer_key=ck_live",
+        )
+        .header("Authorization", "Basic Y2tfbGl2ZQ==")
+        .json(&serde_json::json!({"secret": "wh-secret", "total": "10.50"}))
+        .unwrap();
+        transport.send(request.clone()).await.unwrap();
+
+        let recorded = &transport.cassette().interactions[0];
+        assert_eq!(recorded.request.headers[0].1, REDACTED);
+        assert!(recorded
+            .request
+            .url
+            .ends_with("consumer_key=%5BREDACTED%5D"));
+        assert_eq!(
+            recorded.request.body.as_deref(),
+            Some(r#"{"secret":"wh-secret","total":"10.50"}"#)
+        );
+
+        let replayed = CassetteTransport::replay(&path)
+            .unwrap()
+            .send(request)
+            .await
+            .unwrap();
+        assert_eq!(replayed.body, body.as_bytes());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_replay_fails_on_unmatched_request() {
+        let path = cassette_path("unmatched");
+        let interaction = CassetteInteraction {
+            request: CassetteRequest {
+                method: "GET".to_string(),
+                url: "https://example.com/wp-json/wc/v3/coupons/1".to_string(),
+                headers: vec![("Authorization".to_string(), REDACTED.to_string())],
+                body: None,
+            },
+            response: CassetteResponse {
+                status: 200,
+                headers: vec![],
+                body: Some(include_str!("../../fixtures/coupon.json").to_string()),
+            },
+        };
+        Cassette {
+            interactions: vec![interaction],
+        }
+        .save(&path)
+        .unwrap();
+
+        let client = WooHttpClient::with_transport(
+            Auth::basic("ck_test", "cs_test"),
+            "https://example.com/wp-json",
+            CassetteTransport::replay(&path).unwrap(),
+        );
+        assert!(client.get_coupon(CouponId(1)).await.unwrap().is_some());
+
+        // Every interaction is served once.
+        match client.get_coupon(CouponId(1)).await {
+            Err(WooCommerceHttpError::ErrorResponse(err)) => {
+                assert_eq!(err.code, "transport_error");
+                assert!(err.message.contains("no unused interaction"));
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,16 +1,33 @@
 use std::env;
 
-use base64::Engine;
-use reqwest::{header, Response};
-use serde::{Deserialize, Serialize};
+use reqwest::{Method, Url};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use service_sdk::my_logger::{LogEventCtx, LOGGER};
 
+use crate::{
+    Auth, HttpRequest, HttpResponse, Order, OrderId, OrderStatus, ReqwestTransport, Transport,
+    TransportError,
+};
+
+use super::lenient::from_slice_lenient;
+
 #[derive(Debug)]
 pub enum WooCommerceHttpError {
     //ReqwestError(reqwest::Error),
     //FlurlError(FlUrlError),
     SerdeError(serde_json::Error),
     ErrorResponse(ErrorResponse),
+    /// Refused locally, the order was not changed.
+    InvalidStatusTransition {
+        order_id: OrderId,
+        from: OrderStatus,
+        to: OrderStatus,
+    },
+    /// The order status was changed, but adding the transition's note failed.
+    NoteFailed {
+        order: Box<Order>,
+        error: Box<WooCommerceHttpError>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -27,59 +44,97 @@ pub struct ErrorData {
 
 #[derive(Debug)]
 pub enum ResponseStatusCheck<T> {
-    Ok(Response),
+    Ok(HttpResponse),
     Err(Result<T, WooCommerceHttpError>),
 }
 
 #[allow(dead_code)]
-pub struct WooHttpClient {
+pub struct WooHttpClient<T: Transport = ReqwestTransport> {
     pub(crate) base_url: String,
-    pub(crate) client: reqwest::Client,
+    pub(crate) transport: T,
     pub(crate) debug: bool,
-    pub(crate) auth_header: String,
+    pub(crate) auth: Auth,
+    pub(crate) lenient: bool,
 }
 
-impl WooHttpClient {
+impl WooHttpClient<ReqwestTransport> {
     pub fn new(consumer_key: &str, consumer_secret: &str, base_url: &str) -> Self {
-        assert!(consumer_key.len() > 0);
-        assert!(consumer_secret.len() > 0);
-        assert!(base_url.len() > 0);
+        Self::with_auth(Auth::basic(consumer_key, consumer_secret), base_url)
+    }
+
+    pub fn with_auth(auth: Auth, base_url: &str) -> Self {
+        Self::with_transport(auth, base_url, ReqwestTransport::new())
+    }
+}
 
-        let auth: String = format!("{}:{}", consumer_key, consumer_secret);
-        let auth_encoded = base64::engine::general_purpose::STANDARD.encode(auth);
-        let auth_header = format!("Basic {}", auth_encoded);
-        let mut headers = header::HeaderMap::new();
-        headers.insert(header::CONTENT_TYPE, "application/json".parse().unwrap());
-        headers.insert("Authorization", auth_header.parse().unwrap());
-       
-        let client = reqwest::Client::builder()
-            .default_headers(headers)
-            //TODO: REMOVE
-            //.danger_accept_invalid_certs(true)
-            .build()
-            .unwrap();
+impl<T: Transport> WooHttpClient<T> {
+    pub fn with_transport(auth: Auth, base_url: &str, transport: T) -> Self {
+        assert!(!auth.consumer_key().is_empty());
+        assert!(!auth.consumer_secret().is_empty());
+        assert!(base_url.len() > 0);
 
         let debug = env::var("DEBUG").unwrap_or("0".to_string()) == "1";
 
         WooHttpClient {
-            client,
+            transport,
             base_url: base_url.to_string(),
             debug,
-            auth_header,
+            auth,
+            lenient: false,
         }
     }
 
-    pub(crate) async fn check_for_failed_status_code<T>(
+    /// In lenient mode response values that don't match the model's types are logged and
+    /// dropped instead of failing the call. Optional fields then keep their defaults.
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    /// `base_url` + `path` with the query params url-encoded.
+    pub(crate) fn url_with_query(&self, path: &str, pairs: &[(&str, String)]) -> String {
+        let url = format!("{}{}", self.base_url, path);
+        match Url::parse(&url) {
+            Ok(mut parsed) if !pairs.is_empty() => {
+                parsed.query_pairs_mut().extend_pairs(pairs);
+                parsed.to_string()
+            }
+            _ => url,
+        }
+    }
+
+    /// Starts a request with the headers and query params required by the auth strategy.
+    pub(crate) fn request(&self, method: Method, url: &str) -> HttpRequest {
+        let url = self.auth.sign_url(method.as_str(), url);
+        let request = HttpRequest::new(method, &url).header("Content-Type", "application/json");
+        match self.auth.authorization_header() {
+            Some(auth_header) => request.header("Authorization", &auth_header),
+            None => request,
+        }
+    }
+
+    pub(crate) fn decode<R: DeserializeOwned>(
         &self,
-        res: reqwest::Response,
-    ) -> ResponseStatusCheck<T> {
+        res: &HttpResponse,
+    ) -> Result<R, serde_json::Error> {
+        if self.lenient {
+            return from_slice_lenient(&res.body, "WooHttpClient::decode");
+        }
+        res.json()
+    }
+
+    pub(crate) fn check_for_failed_status_code<R>(
+        &self,
+        res: HttpResponse,
+    ) -> ResponseStatusCheck<R> {
         // Process unsuccessful HTTP codes here.
-        if !res.status().is_success() {
-            let status = res.status();
-            let body = res
-                .text()
-                .await
-                .unwrap_or_else(|_| "Failed to get response body".to_string());
+        if !res.is_success() {
+            let status = res.status;
+            let body = res.text();
             // Log the unsuccessful response
             if self.debug {
                 LOGGER.write_error(
@@ -147,6 +202,19 @@ impl From<reqwest::Error> for WooCommerceHttpError {
     }
 }
 
+impl From<TransportError> for WooCommerceHttpError {
+    fn from(err: TransportError) -> Self {
+        match err {
+            TransportError::Reqwest(err) => err.into(),
+            TransportError::Other(message) => WooCommerceHttpError::ErrorResponse(ErrorResponse {
+                code: "transport_error".to_string(),
+                message,
+                data: ErrorData { status: 0 },
+            }),
+        }
+    }
+}
+
 /* impl From<FlUrlError> for WooCommerceHttpError {
     fn from(err: FlUrlError) -> Self {
         WooCommerceHttpError::FlurlError(err)
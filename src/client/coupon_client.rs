@@ -1,6 +1,11 @@
+use reqwest::Method;
 use service_sdk::my_logger::{LogEventCtx, LOGGER};
 
-use crate::{Coupon, CreateCoupon, UpdateCoupon, WooCommerceHttpError, WooHttpClient};
+use crate::{
+    normalize_coupon_code, BatchRequest, BatchResponse, Coupon, CouponId, CouponQuery,
+    CreateCoupon, OrderBy, SortOrder, Transport, UpdateCoupon, WooCommerceHttpError,
+    WooHttpClient,
+};
 
 #[allow(async_fn_in_trait)]
 pub trait CouponClient {
@@ -12,17 +17,48 @@ pub trait CouponClient {
         per_page: usize,
     ) -> Result<Vec<Coupon>, WooCommerceHttpError>;
 
-    async fn update_coupon(&self, coupon: &UpdateCoupon) -> Result<Coupon, WooCommerceHttpError>;
+    /// Changes only the fields set in `coupon`.
+    async fn update_coupon(
+        &self,
+        id: CouponId,
+        coupon: &UpdateCoupon,
+    ) -> Result<Coupon, WooCommerceHttpError>;
+
+    async fn delete_coupon(&self, id: CouponId) -> Result<Coupon, WooCommerceHttpError>;
+
+    async fn get_coupon(&self, id: CouponId) -> Result<Option<Coupon>, WooCommerceHttpError>;
+
+    async fn list_coupons(&self, query: &CouponQuery) -> Result<Vec<Coupon>, WooCommerceHttpError>;
 
-    async fn delete_coupon(&self, id: i32) -> Result<Coupon, WooCommerceHttpError>;
+    /// Case-insensitive. If several coupons share the code the oldest one is returned.
+    async fn find_coupon_by_code(&self, code: &str)
+        -> Result<Option<Coupon>, WooCommerceHttpError>;
+
+    /// `None` if no coupon has this code.
+    async fn update_coupon_by_code(
+        &self,
+        code: &str,
+        coupon: &UpdateCoupon,
+    ) -> Result<Option<Coupon>, WooCommerceHttpError>;
+
+    /// `None` if no coupon has this code.
+    async fn delete_coupon_by_code(
+        &self,
+        code: &str,
+    ) -> Result<Option<Coupon>, WooCommerceHttpError>;
 
-    async fn get_coupon(&self, id: i32) -> Result<Option<Coupon>, WooCommerceHttpError>;
+    /// Up to 100 items in total per request.
+    async fn batch_coupons(
+        &self,
+        batch: &BatchRequest<CreateCoupon, UpdateCoupon, CouponId>,
+    ) -> Result<BatchResponse<Coupon>, WooCommerceHttpError>;
 }
 
-impl CouponClient for WooHttpClient {
+impl<T: Transport> CouponClient for WooHttpClient<T> {
     async fn create_coupon(&self, coupon: &CreateCoupon) -> Result<Coupon, WooCommerceHttpError> {
         let url = format!("{}/wc/v3/coupons", self.base_url);
-        let res = self.client.post(&url).json(coupon).send().await;
+        let request = self.request(Method::POST, &url).json(coupon)?;
+        let res = self.transport.send(request).await;
         println!("Response: {:?}", res);
         match res {
             Ok(res) => {
@@ -34,12 +70,12 @@ impl CouponClient for WooHttpClient {
                     );
                 }
 
-                let res = match self.check_for_failed_status_code(res).await {
+                let res = match self.check_for_failed_status_code(res) {
                     crate::ResponseStatusCheck::Ok(res) => res,
                     crate::ResponseStatusCheck::Err(err) => return err,
                 };
 
-                let coupon: Result<Coupon, reqwest::Error> = res.json().await;
+                let coupon: Result<Coupon, serde_json::Error> = self.decode(&res);
 
                 return Ok(coupon?);
             }
@@ -65,7 +101,7 @@ impl CouponClient for WooHttpClient {
             "{}/wc/v3/coupons?page={}&per_page={}",
             self.base_url, page, per_page
         );
-        let res = self.client.get(&url).send().await;
+        let res = self.transport.send(self.request(Method::GET, &url)).await;
         match res {
             Ok(res) => {
                 if self.debug {
@@ -76,12 +112,12 @@ impl CouponClient for WooHttpClient {
                     );
                 }
 
-                let res = match self.check_for_failed_status_code(res).await {
+                let res = match self.check_for_failed_status_code(res) {
                     crate::ResponseStatusCheck::Ok(res) => res,
                     crate::ResponseStatusCheck::Err(err) => return err,
                 };
 
-                let coupons = res.json().await;
+                let coupons = self.decode(&res);
                 return Ok(coupons?);
             }
             Err(e) => {
@@ -97,9 +133,14 @@ impl CouponClient for WooHttpClient {
         }
     }
 
-    async fn update_coupon(&self, coupon: &UpdateCoupon) -> Result<Coupon, WooCommerceHttpError> {
-        let url = format!("{}/wc/v3/coupons/{}", self.base_url, coupon.id);
-        let res = self.client.put(&url).json(coupon).send().await;
+    async fn update_coupon(
+        &self,
+        id: CouponId,
+        coupon: &UpdateCoupon,
+    ) -> Result<Coupon, WooCommerceHttpError> {
+        let url = format!("{}/wc/v3/coupons/{}", self.base_url, id);
+        let request = self.request(Method::PUT, &url).json(coupon)?;
+        let res = self.transport.send(request).await;
         match res {
             Ok(res) => {
                 if self.debug {
@@ -110,12 +151,12 @@ impl CouponClient for WooHttpClient {
                     );
                 }
 
-                let res = match self.check_for_failed_status_code(res).await {
+                let res = match self.check_for_failed_status_code(res) {
                     crate::ResponseStatusCheck::Ok(res) => res,
                     crate::ResponseStatusCheck::Err(err) => return err,
                 };
 
-                let coupon = res.json().await;
+                let coupon = self.decode(&res);
 
                 return Ok(coupon?);
             }
@@ -132,9 +173,9 @@ impl CouponClient for WooHttpClient {
         }
     }
 
-    async fn delete_coupon(&self, id: i32) -> Result<Coupon, WooCommerceHttpError> {
+    async fn delete_coupon(&self, id: CouponId) -> Result<Coupon, WooCommerceHttpError> {
         let url = format!("{}/wc/v3/coupons/{}?force=true", self.base_url, id);
-        let res = self.client.delete(&url).send().await;
+        let res = self.transport.send(self.request(Method::DELETE, &url)).await;
         match res {
             Ok(res) => {
                 if self.debug {
@@ -145,12 +186,12 @@ impl CouponClient for WooHttpClient {
                     );
                 }
 
-                let res = match self.check_for_failed_status_code(res).await {
+                let res = match self.check_for_failed_status_code(res) {
                     crate::ResponseStatusCheck::Ok(res) => res,
                     crate::ResponseStatusCheck::Err(err) => return err,
                 };
 
-                let coupon = res.json().await;
+                let coupon = self.decode(&res);
 
                 return Ok(coupon?);
             }
@@ -167,9 +208,9 @@ impl CouponClient for WooHttpClient {
         }
     }
 
-    async fn get_coupon(&self, id: i32) -> Result<Option<Coupon>, WooCommerceHttpError> {
+    async fn get_coupon(&self, id: CouponId) -> Result<Option<Coupon>, WooCommerceHttpError> {
         let url = format!("{}/wc/v3/coupons/{}", self.base_url, id);
-        let res = self.client.get(&url).send().await;
+        let res = self.transport.send(self.request(Method::GET, &url)).await;
         match res {
             Ok(res) => {
                 if self.debug {
@@ -180,12 +221,12 @@ impl CouponClient for WooHttpClient {
                     );
                 }
 
-                let res = match self.check_for_failed_status_code(res).await {
+                let res = match self.check_for_failed_status_code(res) {
                     crate::ResponseStatusCheck::Ok(res) => res,
                     crate::ResponseStatusCheck::Err(err) => return err,
                 };
 
-                let coupon = res.json().await;
+                let coupon = self.decode(&res);
 
                 return Ok(coupon?);
             }
@@ -201,17 +242,320 @@ impl CouponClient for WooHttpClient {
             }
         }
     }
+
+    async fn list_coupons(&self, query: &CouponQuery) -> Result<Vec<Coupon>, WooCommerceHttpError> {
+        let url = self.url_with_query("/wc/v3/coupons", &query.query_pairs());
+        let res = self.transport.send(self.request(Method::GET, &url)).await;
+        match res {
+            Ok(res) => {
+                if self.debug {
+                    LOGGER.write_info(
+                        "WooHttpClient::list_coupons",
+                        format!("Response: {:?}", res),
+                        LogEventCtx::new(),
+                    );
+                }
+
+                let res = match self.check_for_failed_status_code(res) {
+                    crate::ResponseStatusCheck::Ok(res) => res,
+                    crate::ResponseStatusCheck::Err(err) => return err,
+                };
+
+                let coupons = self.decode(&res);
+                return Ok(coupons?);
+            }
+            Err(e) => {
+                if self.debug {
+                    LOGGER.write_error(
+                        "WooHttpClient::list_coupons",
+                        format!("Error: {:?}", e),
+                        LogEventCtx::new(),
+                    );
+                }
+                return Err(e.into());
+            }
+        }
+    }
+
+    async fn find_coupon_by_code(
+        &self,
+        code: &str,
+    ) -> Result<Option<Coupon>, WooCommerceHttpError> {
+        let code = normalize_coupon_code(code);
+        if code.is_empty() {
+            return Ok(None);
+        }
+        let query = CouponQuery::new()
+            .code(&code)
+            .order_by(OrderBy::Id, SortOrder::Asc);
+        let coupons = self.list_coupons(&query).await?;
+        Ok(coupons.into_iter().find(|coupon| coupon.matches_code(&code)))
+    }
+
+    async fn update_coupon_by_code(
+        &self,
+        code: &str,
+        coupon: &UpdateCoupon,
+    ) -> Result<Option<Coupon>, WooCommerceHttpError> {
+        match self.find_coupon_by_code(code).await? {
+            Some(existing) => Ok(Some(self.update_coupon(existing.id, coupon).await?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn delete_coupon_by_code(
+        &self,
+        code: &str,
+    ) -> Result<Option<Coupon>, WooCommerceHttpError> {
+        match self.find_coupon_by_code(code).await? {
+            Some(existing) => Ok(Some(self.delete_coupon(existing.id).await?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn batch_coupons(
+        &self,
+        batch: &BatchRequest<CreateCoupon, UpdateCoupon, CouponId>,
+    ) -> Result<BatchResponse<Coupon>, WooCommerceHttpError> {
+        let url = format!("{}/wc/v3/coupons/batch", self.base_url);
+        let request = self.request(Method::POST, &url).json(batch)?;
+        let res = self.transport.send(request).await;
+        match res {
+            Ok(res) => {
+                if self.debug {
+                    LOGGER.write_info(
+                        "WooHttpClient::batch_coupons",
+                        format!("Response: {:?}", res),
+                        LogEventCtx::new(),
+                    );
+                }
+
+                let res = match self.check_for_failed_status_code(res) {
+                    crate::ResponseStatusCheck::Ok(res) => res,
+                    crate::ResponseStatusCheck::Err(err) => return err,
+                };
+
+                let response = self.decode(&res);
+                return Ok(response?);
+            }
+            Err(e) => {
+                if self.debug {
+                    LOGGER.write_error(
+                        "WooHttpClient::batch_coupons",
+                        format!("Error: {:?}", e),
+                        LogEventCtx::new(),
+                    );
+                }
+                return Err(e.into());
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    #[cfg(feature = "chrono")]
     use std::time::Duration;
 
     use service_sdk::{flurl::my_tls::tokio_rustls::rustls::client::AlwaysResolvesClientRawPublicKeys, rust_extensions::date_time::DateTimeAsMicroseconds};
     use tokio::time::sleep;
 
+    use crate::{Auth, DiscountType, HttpResponse, MockTransport};
+    #[cfg(feature = "chrono")]
+    use crate::ProductId;
+
     use super::*;
 
+    fn mock_client() -> WooHttpClient<MockTransport> {
+        WooHttpClient::with_transport(
+            Auth::basic("ck_test", "cs_test"),
+            "https://example.com/wp-json",
+            MockTransport::new(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_get_coupon_mock() {
+        let client = mock_client();
+        client.transport().respond(
+            Method::GET,
+            "/wc/v3/coupons/719",
+            HttpResponse::new(200, include_str!("../../fixtures/coupon.json")),
+        );
+
+        let coupon = client.get_coupon(CouponId(719)).await.unwrap().unwrap();
+        assert_eq!(coupon.id, CouponId(719));
+        assert_eq!(coupon.code, "10off");
+
+        let request = client.transport().last_request().unwrap();
+        assert_eq!(request.method, Method::GET);
+        assert_eq!(request.url, "https://example.com/wp-json/wc/v3/coupons/719");
+        assert_eq!(
+            request.get_header("Authorization"),
+            Some("Basic Y2tfdGVzdDpjc190ZXN0")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_update_coupon_sends_only_changes_mock() {
+        let client = mock_client();
+        client.transport().respond(
+            Method::PUT,
+            "/wc/v3/coupons/719",
+            HttpResponse::new(200, include_str!("../../fixtures/coupon.json")),
+        );
+
+        let update = UpdateCoupon {
+            description: Some("Support refund".to_string()),
+            date_expires_gmt: Some(None),
+            ..Default::default()
+        };
+        client.update_coupon(CouponId(719), &update).await.unwrap();
+
+        let request = client.transport().last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&request.body.unwrap()).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({"description": "Support refund", "date_expires_gmt": ""})
+        );
+
+        let update = UpdateCoupon {
+            usage_limit: Some(None),
+            usage_limit_per_user: Some(Some(2)),
+            limit_usage_to_x_items: Some(None),
+            minimum_amount: Some(None),
+            maximum_amount: Some(Some("250.00".parse().unwrap())),
+            ..Default::default()
+        };
+        client.update_coupon(CouponId(719), &update).await.unwrap();
+
+        let request = client.transport().last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&request.body.unwrap()).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "usage_limit": 0,
+                "usage_limit_per_user": 2,
+                "limit_usage_to_x_items": 0,
+                "minimum_amount": "",
+                "maximum_amount": "250.00"
+            })
+        );
+        let parsed: UpdateCoupon = serde_json::from_value(body).unwrap();
+        assert_eq!(parsed.usage_limit, Some(None));
+        assert_eq!(parsed.limit_usage_to_x_items, Some(None));
+        assert_eq!(parsed.minimum_amount, Some(None));
+        assert!(parsed.amount.is_none());
+    }
+
+    #[test]
+    fn test_create_coupon_builder() {
+        let coupon = CreateCoupon::builder("vip20", DiscountType::Percent, "20".parse().unwrap())
+            .exclude_sale_items(true)
+            .minimum_amount("50.00".parse().unwrap())
+            .email_restriction("*@example.com")
+            .usage_limit_per_user(1)
+            .build()
+            .unwrap();
+        let body = serde_json::to_value(&coupon).unwrap();
+        assert_eq!(body["minimum_amount"], "50.00");
+        assert_eq!(body["email_restrictions"][0], "*@example.com");
+        assert!(body.get("usage_limit").is_none());
+
+        let invalid = [
+            CreateCoupon::builder("", DiscountType::Percent, "5".parse().unwrap()),
+            CreateCoupon::builder("cart", DiscountType::FixedCart, "5".parse().unwrap())
+                .limit_usage_to_x_items(2),
+        ];
+        for builder in invalid {
+            assert!(builder.build().is_err());
+        }
+
+        #[cfg(feature = "rust_decimal")]
+        {
+            let invalid = [
+                CreateCoupon::builder("big", DiscountType::Percent, "150".parse().unwrap()),
+                CreateCoupon::builder("range", DiscountType::FixedCart, "5".parse().unwrap())
+                    .minimum_amount("100".parse().unwrap())
+                    .maximum_amount("10".parse().unwrap()),
+            ];
+            for builder in invalid {
+                assert!(builder.build().is_err());
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_coupon_by_code_mock() {
+        let client = mock_client();
+        let coupons = format!("[{}]", include_str!("../../fixtures/coupon.json"));
+        client.transport().respond(
+            Method::GET,
+            "/wc/v3/coupons?code=10off&orderby=id&order=asc",
+            HttpResponse::new(200, coupons),
+        );
+        client.transport().respond(
+            Method::GET,
+            "/wc/v3/coupons?code=missing&orderby=id&order=asc",
+            HttpResponse::new(200, "[]"),
+        );
+        client.transport().respond(
+            Method::DELETE,
+            "/wc/v3/coupons/719?force=true",
+            HttpResponse::new(200, include_str!("../../fixtures/coupon.json")),
+        );
+
+        let coupon = client.find_coupon_by_code(" 10OFF ").await.unwrap().unwrap();
+        assert_eq!(coupon.id, CouponId(719));
+        assert!(client.find_coupon_by_code("missing").await.unwrap().is_none());
+        let update = UpdateCoupon::default();
+        assert!(client
+            .update_coupon_by_code("Missing", &update)
+            .await
+            .unwrap()
+            .is_none());
+
+        let deleted = client.delete_coupon_by_code("10Off").await.unwrap().unwrap();
+        assert_eq!(deleted.id, CouponId(719));
+    }
+
+    #[test]
+    fn test_coupon_query_pairs() {
+        let query = CouponQuery::new()
+            .page(2, 50)
+            .search("summer sale")
+            .exclude(&[CouponId(1), CouponId(2)])
+            .modified_after("2024-05-01T00:00:00".parse().unwrap());
+        let client = mock_client();
+        assert_eq!(
+            client.url_with_query("/wc/v3/coupons", &query.query_pairs()),
+            "https://example.com/wp-json/wc/v3/coupons?page=2&per_page=50&search=summer+sale\
+             &exclude=1%2C2&modified_after=2024-05-01T00%3A00%3A00&dates_are_gmt=true"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_delete_coupon_error_mock() {
+        let client = mock_client();
+        client.transport().respond(
+            Method::DELETE,
+            "/wc/v3/coupons/1?force=true",
+            HttpResponse::new(
+                404,
+                r#"{"code":"woocommerce_rest_shop_coupon_invalid_id","message":"Invalid ID.","data":{"status":404}}"#,
+            ),
+        );
+
+        match client.delete_coupon(CouponId(1)).await {
+            Err(WooCommerceHttpError::ErrorResponse(err)) => {
+                assert_eq!(err.code, "woocommerce_rest_shop_coupon_invalid_id");
+                assert_eq!(err.data.status, 404);
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[cfg(feature = "chrono")]
     #[tokio::test]
     async fn test_coupon() {
         let client = WooHttpClient::new(
@@ -222,17 +566,18 @@ mod tests {
         let now = DateTimeAsMicroseconds::now().add(Duration::from_secs(60 * 60 * 24 * 30));
         let resp = client
             .create_coupon(&CreateCoupon {
-                amount: "10.0".to_string(),
+                amount: "10.0".parse().unwrap(),
                 code: "test1234".to_string(),
                 description: "test1".to_string(),
                 discount_type: crate::DiscountType::Percent,
-                date_expires_gmt: Some(now.to_rfc3339()),
+                date_expires_gmt: Some(now.into()),
                 individual_use: false,
                 //Ensure it exists!
-                product_ids: vec![335],
+                product_ids: vec![ProductId(335)],
                 usage_limit: None,
                 usage_limit_per_user: None,
                 limit_usage_to_x_items: None,
+                ..Default::default()
             })
             .await
             .unwrap();
@@ -242,16 +587,19 @@ mod tests {
         println!("Coupons: {:?}", coupons);
 
         for item in coupons {
-            let mut item: UpdateCoupon = item.into();
-            item.description = format!("{}-updated", item.description);
-            println!("Coupon: {:?}", item);
-            client.update_coupon(&item).await.unwrap();
+            let update = UpdateCoupon {
+                description: Some(format!("{}-updated", item.description)),
+                ..Default::default()
+            };
+            println!("Coupon: {:?}", update);
+            client.update_coupon(item.id, &update).await.unwrap();
             let res: Option<Coupon> = client.get_coupon(item.id).await.unwrap();
             println!("Coupon: {:?}", res);
             client.delete_coupon(res.unwrap().id).await.unwrap();
         }
     }
 
+    #[cfg(feature = "chrono")]
     #[tokio::test]
     async fn test_existing_coupon() {
         env_logger::init();
@@ -270,10 +618,8 @@ mod tests {
         println!("Coupons: {:?}", coupons); 
 
         for item in coupons {
-            //let mut item: UpdateCoupon = item.into();
-            //item.description = format!("{}-updated", item.description);
             println!("Coupon: {:?}", item);
-            //client.update_coupon(&item).await.unwrap();
+            //client.update_coupon(item.id, &UpdateCoupon::default()).await.unwrap();
             //let res: Option<Coupon> = client.get_coupon(item.id).await.unwrap();
             //println!("Coupon: {:?}", res);
             //client.delete_coupon(res.unwrap().id).await.unwrap();
@@ -282,17 +628,18 @@ mod tests {
         let now = DateTimeAsMicroseconds::now().add(Duration::from_secs(60 * 60 * 24 * 30));
         let resp = client
             .create_coupon(&CreateCoupon {
-                amount: "10.0".to_string(),
+                amount: "10.0".parse().unwrap(),
                 code: "top1".to_string(),
                 description: "test1".to_string(),
                 discount_type: crate::DiscountType::Percent,
-                date_expires_gmt: Some(now.to_rfc3339()),
+                date_expires_gmt: Some(now.into()),
                 individual_use: false,
                 //Ensure it exists!
-                product_ids: vec![2636],
+                product_ids: vec![ProductId(2636)],
                 usage_limit: None,
                 usage_limit_per_user: None,
                 limit_usage_to_x_items: None,
+                ..Default::default()
             })
             .await;
 
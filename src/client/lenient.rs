@@ -0,0 +1,111 @@
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use serde_path_to_error::{Path, Segment};
+use service_sdk::my_logger::{LogEventCtx, LOGGER};
+
+/// Stops a pathological response from looping forever.
+const MAX_DROPPED_VALUES: usize = 64;
+
+/// Deserializes `body`, dropping every value whose type doesn't match the model instead of
+/// failing. Dropped values are logged; optional fields fall back to their defaults, while a
+/// dropped required field still fails with "missing field".
+pub(crate) fn from_slice_lenient<T: DeserializeOwned>(
+    body: &[u8],
+    context: &str,
+) -> Result<T, serde_json::Error> {
+    let mut value: Value = serde_json::from_slice(body)?;
+
+    for _ in 0..MAX_DROPPED_VALUES {
+        let err = match serde_path_to_error::deserialize::<_, T>(&value) {
+            Ok(result) => return Ok(result),
+            Err(err) => err,
+        };
+
+        let path = err.path().clone();
+        let inner = err.into_inner();
+        if inner.to_string().starts_with("missing field") || !remove_at(&mut value, &path) {
+            return Err(inner);
+        }
+
+        LOGGER.write_warning(
+            context,
+            format!("Dropped `{}` from the response: {}", path, inner),
+            LogEventCtx::new(),
+        );
+    }
+
+    serde_json::from_value(value)
+}
+
+fn remove_at(value: &mut Value, path: &Path) -> bool {
+    let segments: Vec<&Segment> = path.iter().collect();
+    let Some((last, parents)) = segments.split_last() else {
+        return false;
+    };
+
+    let mut current = value;
+    for segment in parents {
+        let next = match segment {
+            Segment::Seq { index } => current.get_mut(*index),
+            Segment::Map { key } => current.get_mut(key.as_str()),
+            _ => None,
+        };
+        match next {
+            Some(next) => current = next,
+            None => return false,
+        }
+    }
+
+    match (last, current) {
+        (Segment::Map { key }, Value::Object(map)) => map.remove(key.as_str()).is_some(),
+        (Segment::Seq { index }, Value::Array(items)) if *index < items.len() => {
+            items.remove(*index);
+            true
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Order;
+
+    use super::*;
+
+    fn order_with(patch: impl FnOnce(&mut Value)) -> Vec<u8> {
+        let mut order: Value =
+            serde_json::from_str(include_str!("../../fixtures/order.json")).unwrap();
+        patch(&mut order);
+        serde_json::to_vec(&order).unwrap()
+    }
+
+    #[test]
+    fn test_lenient_drops_mismatched_optional_values() {
+        let body = order_with(|order| {
+            order["needs_payment"] = Value::String("yes".to_string());
+            order["line_items"][0]["sku"] = Value::from(93);
+            order["related_ids"] = Value::from(vec!["x"]);
+        });
+
+        assert!(serde_json::from_slice::<Order>(&body).is_err());
+
+        let order: Order = from_slice_lenient(&body, "test").unwrap();
+        assert!(!order.needs_payment);
+        assert_eq!(order.line_items[0].sku, None);
+        assert_eq!(order.line_items[1].sku.as_deref(), Some("Bar3"));
+        assert_eq!(order.extra["related_ids"], Value::from(vec!["x"]));
+
+        let round_trip = serde_json::to_value(&order).unwrap();
+        assert_eq!(round_trip["related_ids"], Value::from(vec!["x"]));
+    }
+
+    #[test]
+    fn test_lenient_still_requires_required_fields() {
+        let body = order_with(|order| {
+            order["total"] = Value::Bool(true);
+        });
+
+        let err = from_slice_lenient::<Order>(&body, "test").unwrap_err();
+        assert!(err.to_string().contains("missing field `total`"));
+    }
+}
@@ -0,0 +1,111 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use reqwest::Method;
+use serde::Serialize;
+
+use super::{HttpRequest, HttpResponse, Transport, TransportError};
+
+struct MockRoute {
+    method: Method,
+    path: String,
+    responses: VecDeque<HttpResponse>,
+}
+
+/// In-memory [`Transport`] for unit tests: answers with canned responses and records
+/// every request it receives.
+///
+/// Routes match on the method and the end of the request path (the query string is
+/// only compared when the route includes one). Queued responses for a route are served
+/// in order, the last one is repeated. Unmatched requests get WooCommerce's 404 body.
+#[derive(Default)]
+pub struct MockTransport {
+    routes: Mutex<Vec<MockRoute>>,
+    requests: Mutex<Vec<HttpRequest>>,
+}
+
+impl MockTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn respond(&self, method: Method, path: &str, response: HttpResponse) {
+        let mut routes = self.routes.lock().unwrap();
+        if let Some(route) = routes
+            .iter_mut()
+            .find(|route| route.method == method && route.path == path)
+        {
+            route.responses.push_back(response);
+            return;
+        }
+
+        routes.push(MockRoute {
+            method,
+            path: path.to_string(),
+            responses: VecDeque::from([response]),
+        });
+    }
+
+    pub fn respond_json<B: Serialize + ?Sized>(
+        &self,
+        method: Method,
+        path: &str,
+        status: u16,
+        body: &B,
+    ) {
+        let body = serde_json::to_vec(body).unwrap();
+        let mut response = HttpResponse::new(status, body);
+        response
+            .headers
+            .push(("Content-Type".to_string(), "application/json".to_string()));
+        self.respond(method, path, response);
+    }
+
+    pub fn requests(&self) -> Vec<HttpRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    pub fn last_request(&self) -> Option<HttpRequest> {
+        self.requests.lock().unwrap().last().cloned()
+    }
+}
+
+impl Transport for MockTransport {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, TransportError> {
+        self.requests.lock().unwrap().push(request.clone());
+
+        let (path, query) = match request.url.split_once('?') {
+            Some((path, query)) => (path, Some(query)),
+            None => (request.url.as_str(), None),
+        };
+
+        let mut routes = self.routes.lock().unwrap();
+        let route = routes.iter_mut().find(|route| {
+            if route.method != request.method {
+                return false;
+            }
+            match route.path.split_once('?') {
+                Some((route_path, route_query)) => {
+                    path.ends_with(route_path) && query == Some(route_query)
+                }
+                None => path.ends_with(&route.path),
+            }
+        });
+
+        let Some(route) = route else {
+            let body = serde_json::json!({
+                "code": "rest_no_route",
+                "message": "No route was found matching the URL and request method.",
+                "data": { "status": 404 }
+            });
+            return Ok(HttpResponse::new(404, body.to_string()));
+        };
+
+        let response = if route.responses.len() > 1 {
+            route.responses.pop_front().unwrap()
+        } else {
+            route.responses.front().cloned().unwrap()
+        };
+        Ok(response)
+    }
+}
@@ -8,4 +8,20 @@ mod product_client;
 pub use product_client::*;
 
 mod coupon_client;
-pub use coupon_client::*;
\ No newline at end of file
+pub use coupon_client::*;
+mod webhook_client;
+pub use webhook_client::*;
+
+mod auth;
+pub use auth::*;
+
+mod transport;
+pub use transport::*;
+
+mod lenient;
+
+mod mock_transport;
+pub use mock_transport::*;
+
+mod cassette;
+pub use cassette::*;
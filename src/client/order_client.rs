@@ -1,5 +1,10 @@
+use reqwest::Method;
 use service_sdk::my_logger::{LogEventCtx, LOGGER};
-use crate::{CreateOrder, Order, WooCommerceHttpError, WooHttpClient};
+use crate::{
+    CreateOrder, CreateOrderNote, CreateRefund, Order, OrderId, OrderNote, OrderQuery,
+    OrderRefund, StatusTransition, TransitionPolicy, Transport, WooCommerceHttpError,
+    WooHttpClient,
+};
 
 #[allow(async_fn_in_trait)]
 pub trait OrderClient {
@@ -7,13 +12,43 @@ pub trait OrderClient {
 
     async fn update_order(&self, order: &Order) -> Result<Order, WooCommerceHttpError>;
 
-    async fn get_order(&self, order_id: i32) -> Result<Option<Order>, WooCommerceHttpError>;
+    async fn get_order(&self, order_id: OrderId) -> Result<Option<Order>, WooCommerceHttpError>;
+
+    /// One page of orders, see [`OrderQuery::page`].
+    async fn list_orders(&self, query: &OrderQuery) -> Result<Vec<Order>, WooCommerceHttpError>;
+
+    async fn create_order_note(
+        &self,
+        order_id: OrderId,
+        note: &CreateOrderNote,
+    ) -> Result<OrderNote, WooCommerceHttpError>;
+
+    async fn create_refund(
+        &self,
+        order_id: OrderId,
+        refund: &CreateRefund,
+    ) -> Result<OrderRefund, WooCommerceHttpError>;
+
+    /// Moves the order to `transition.to` if [`OrderStatus::can_transition_to`] allows it,
+    /// see [`TransitionPolicy`] otherwise. Only the status is sent, the rest of the order
+    /// is left alone. `None` if the order doesn't exist.
+    ///
+    /// If the note can't be added after the status changed, the error is
+    /// [`WooCommerceHttpError::NoteFailed`] with the updated order.
+    ///
+    /// [`OrderStatus::can_transition_to`]: crate::OrderStatus::can_transition_to
+    async fn transition_order(
+        &self,
+        order_id: OrderId,
+        transition: &StatusTransition,
+    ) -> Result<Option<Order>, WooCommerceHttpError>;
 }
 
-impl OrderClient for WooHttpClient {
+impl<T: Transport> OrderClient for WooHttpClient<T> {
     async fn create_order(&self, order: &CreateOrder) -> Result<Order, WooCommerceHttpError> {
         let url = format!("{}/wc/v3/orders", self.base_url);
-        let res = self.client.post(&url).json(order).send().await;
+        let request = self.request(Method::POST, &url).json(order)?;
+        let res = self.transport.send(request).await;
         match res {
             Ok(res) => {
                 if self.debug {
@@ -24,12 +59,12 @@ impl OrderClient for WooHttpClient {
                     );
                 }
 
-                let res = match self.check_for_failed_status_code(res).await {
+                let res = match self.check_for_failed_status_code(res) {
                     crate::ResponseStatusCheck::Ok(res) => res,
                     crate::ResponseStatusCheck::Err(err) => return err,
                 };
 
-                let order: Result<Order, reqwest::Error> = res.json().await;
+                let order: Result<Order, serde_json::Error> = self.decode(&res);
 
                 return Ok(order?);
             }
@@ -48,7 +83,8 @@ impl OrderClient for WooHttpClient {
 
     async fn update_order(&self, order: &Order) -> Result<Order, WooCommerceHttpError> {
         let url = format!("{}/wc/v3/orders/{}", self.base_url, order.id);
-        let res = self.client.put(&url).json(order).send().await;
+        let request = self.request(Method::PUT, &url).json(order)?;
+        let res = self.transport.send(request).await;
         match res {
             Ok(res) => {
                 if self.debug {
@@ -59,12 +95,12 @@ impl OrderClient for WooHttpClient {
                     );
                 }
 
-                let res = match self.check_for_failed_status_code(res).await {
+                let res = match self.check_for_failed_status_code(res) {
                     crate::ResponseStatusCheck::Ok(res) => res,
                     crate::ResponseStatusCheck::Err(err) => return err,
                 };
 
-                let order: Result<Order, reqwest::Error> = res.json().await;
+                let order: Result<Order, serde_json::Error> = self.decode(&res);
 
                 return Ok(order?);
             }
@@ -81,9 +117,9 @@ impl OrderClient for WooHttpClient {
         }
     }
 
-    async fn get_order(&self, order_id: i32) -> Result<Option<Order>, WooCommerceHttpError> {
+    async fn get_order(&self, order_id: OrderId) -> Result<Option<Order>, WooCommerceHttpError> {
         let url = format!("{}/wc/v3/orders/{}", self.base_url, order_id);
-        let res = self.client.get(&url).send().await;
+        let res = self.transport.send(self.request(Method::GET, &url)).await;
         match res {
             Ok(res) => {
                 if self.debug {
@@ -94,12 +130,12 @@ impl OrderClient for WooHttpClient {
                     );
                 }
 
-                let res = match self.check_for_failed_status_code(res).await {
+                let res = match self.check_for_failed_status_code(res) {
                     crate::ResponseStatusCheck::Ok(res) => res,
                     crate::ResponseStatusCheck::Err(err) => return err,
                 };
 
-                let order = res.json().await;
+                let order = self.decode(&res);
                 return Ok(order?);
             }
             Err(e) => {
@@ -114,16 +150,464 @@ impl OrderClient for WooHttpClient {
             }
         }
     }
+
+    async fn list_orders(&self, query: &OrderQuery) -> Result<Vec<Order>, WooCommerceHttpError> {
+        let url = self.url_with_query("/wc/v3/orders", &query.query_pairs());
+        let res = self.transport.send(self.request(Method::GET, &url)).await;
+        match res {
+            Ok(res) => {
+                if self.debug {
+                    LOGGER.write_info(
+                        "WooHttpClient::list_orders",
+                        format!("Response: {:?}", res),
+                        LogEventCtx::new(),
+                    );
+                }
+
+                let res = match self.check_for_failed_status_code(res) {
+                    crate::ResponseStatusCheck::Ok(res) => res,
+                    crate::ResponseStatusCheck::Err(err) => return err,
+                };
+
+                let orders = self.decode(&res);
+                return Ok(orders?);
+            }
+            Err(e) => {
+                if self.debug {
+                    LOGGER.write_error(
+                        "WooHttpClient::list_orders",
+                        format!("Error: {:?}", e),
+                        LogEventCtx::new(),
+                    );
+                }
+                return Err(e.into());
+            }
+        }
+    }
+
+    async fn create_order_note(
+        &self,
+        order_id: OrderId,
+        note: &CreateOrderNote,
+    ) -> Result<OrderNote, WooCommerceHttpError> {
+        let url = format!("{}/wc/v3/orders/{}/notes", self.base_url, order_id);
+        let request = self.request(Method::POST, &url).json(note)?;
+        let res = self.transport.send(request).await;
+        match res {
+            Ok(res) => {
+                if self.debug {
+                    LOGGER.write_info(
+                        "WooHttpClient::create_order_note",
+                        format!("Response: {:?}", res),
+                        LogEventCtx::new(),
+                    );
+                }
+
+                let res = match self.check_for_failed_status_code(res) {
+                    crate::ResponseStatusCheck::Ok(res) => res,
+                    crate::ResponseStatusCheck::Err(err) => return err,
+                };
+
+                let note = self.decode(&res);
+                return Ok(note?);
+            }
+            Err(e) => {
+                if self.debug {
+                    LOGGER.write_error(
+                        "WooHttpClient::create_order_note",
+                        format!("Error: {:?}", e),
+                        LogEventCtx::new(),
+                    );
+                }
+                return Err(e.into());
+            }
+        }
+    }
+
+    async fn create_refund(
+        &self,
+        order_id: OrderId,
+        refund: &CreateRefund,
+    ) -> Result<OrderRefund, WooCommerceHttpError> {
+        let url = format!("{}/wc/v3/orders/{}/refunds", self.base_url, order_id);
+        let request = self.request(Method::POST, &url).json(refund)?;
+        let res = self.transport.send(request).await;
+        match res {
+            Ok(res) => {
+                if self.debug {
+                    LOGGER.write_info(
+                        "WooHttpClient::create_refund",
+                        format!("Response: {:?}", res),
+                        LogEventCtx::new(),
+                    );
+                }
+
+                let res = match self.check_for_failed_status_code(res) {
+                    crate::ResponseStatusCheck::Ok(res) => res,
+                    crate::ResponseStatusCheck::Err(err) => return err,
+                };
+
+                let refund = self.decode(&res);
+                return Ok(refund?);
+            }
+            Err(e) => {
+                if self.debug {
+                    LOGGER.write_error(
+                        "WooHttpClient::create_refund",
+                        format!("Error: {:?}", e),
+                        LogEventCtx::new(),
+                    );
+                }
+                return Err(e.into());
+            }
+        }
+    }
+
+    async fn transition_order(
+        &self,
+        order_id: OrderId,
+        transition: &StatusTransition,
+    ) -> Result<Option<Order>, WooCommerceHttpError> {
+        let Some(order) = self.get_order(order_id).await? else {
+            return Ok(None);
+        };
+        let from = order.status;
+        if from == transition.to {
+            return Ok(Some(order));
+        }
+        if !from.can_transition_to(transition.to) {
+            match transition.policy {
+                TransitionPolicy::Reject => {
+                    return Err(WooCommerceHttpError::InvalidStatusTransition {
+                        order_id,
+                        from,
+                        to: transition.to,
+                    });
+                }
+                TransitionPolicy::Warn => {
+                    LOGGER.write_warning(
+                        "WooHttpClient::transition_order",
+                        format!(
+                            "Order {} moved from {} to {}, which WooCommerce flows don't do",
+                            order_id, from, transition.to
+                        ),
+                        LogEventCtx::new(),
+                    );
+                }
+            }
+        }
+
+        let url = format!("{}/wc/v3/orders/{}", self.base_url, order_id);
+        let body = serde_json::json!({ "status": transition.to });
+        let request = self.request(Method::PUT, &url).json(&body)?;
+        let res = self.transport.send(request).await;
+        let order: Order = match res {
+            Ok(res) => {
+                if self.debug {
+                    LOGGER.write_info(
+                        "WooHttpClient::transition_order",
+                        format!("Response: {:?}", res),
+                        LogEventCtx::new(),
+                    );
+                }
+
+                let res = match self.check_for_failed_status_code(res) {
+                    crate::ResponseStatusCheck::Ok(res) => res,
+                    crate::ResponseStatusCheck::Err(err) => return err,
+                };
+
+                self.decode(&res)?
+            }
+            Err(e) => {
+                if self.debug {
+                    LOGGER.write_error(
+                        "WooHttpClient::transition_order",
+                        format!("Error: {:?}", e),
+                        LogEventCtx::new(),
+                    );
+                }
+                return Err(e.into());
+            }
+        };
+
+        if let Some(note) = &transition.note {
+            let note = CreateOrderNote {
+                note: note.clone(),
+                customer_note: transition.notify_customer,
+                added_by_user: false,
+            };
+            if let Err(error) = self.create_order_note(order_id, &note).await {
+                return Err(WooCommerceHttpError::NoteFailed {
+                    order: Box::new(order),
+                    error: Box::new(error),
+                });
+            }
+        }
+        Ok(Some(order))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use serde_json::Value;
 
-    use crate::{CreateLineItem, MetaData, OrderStatus};
+    use crate::{CreateLineItem, OrderStatus};
+
+    use crate::{Auth, CustomerId, HttpResponse, MockTransport, ProductId};
 
     use super::*;
 
+    #[tokio::test]
+    async fn test_create_order_mock() {
+        let client = WooHttpClient::with_transport(
+            Auth::basic("ck_test", "cs_test"),
+            "https://example.com/wp-json",
+            MockTransport::new(),
+        );
+        client.transport().respond(
+            Method::POST,
+            "/wc/v3/orders",
+            HttpResponse::new(201, include_str!("../../fixtures/order.json")),
+        );
+
+        let order = client
+            .create_order(&CreateOrder {
+                set_paid: true,
+                billing: crate::ContactDetails {
+                    first_name: "John".to_string(),
+                    last_name: "Doe".to_string(),
+                    company: "".to_string(),
+                    address_1: "969 Market".to_string(),
+                    address_2: "".to_string(),
+                    city: "San Francisco".to_string(),
+                    state: "CA".to_string(),
+                    postcode: "94103".to_string(),
+                    country: "US".to_string(),
+                    email: "john.doe@example.com".to_string(),
+                    phone: "(555) 555-5555".to_string(),
+                    ..Default::default()
+                },
+                meta_data: vec![],
+                line_items: vec![CreateLineItem {
+                    product_id: Some(ProductId(93)),
+                    quantity: 2,
+                    ..Default::default()
+                }],
+                customer_id: CustomerId(0),
+                coupon_lines: vec![],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(order.id, OrderId(727));
+        assert_eq!(order.status, OrderStatus::Processing);
+        assert_eq!(order.line_items.len(), 2);
+
+        let request = client.transport().last_request().unwrap();
+        let body: Value = serde_json::from_slice(&request.body.unwrap()).unwrap();
+        assert_eq!(body["line_items"][0]["product_id"], 93);
+        assert_eq!(body["set_paid"], true);
+        assert!(body["line_items"][0].get("total").is_none());
+    }
+
+    #[test]
+    fn test_create_order_builder() {
+        assert_eq!(
+            CreateOrder::builder().build().unwrap_err(),
+            crate::BuilderError::Missing("line_items")
+        );
+        assert!(CreateOrder::builder().sku("", 0).build().is_err());
+        assert!(CreateOrder::builder()
+            .product(ProductId(93), 1)
+            .transaction_id("crm-1042")
+            .build()
+            .is_err());
+
+        let order = CreateOrder::builder()
+            .currency("EUR")
+            .payment_method("bacs", "Bank transfer")
+            .transaction_id("crm-1042")
+            .set_paid(true)
+            .variation(ProductId(22), crate::VariationId(23), 1)
+            .sku("challenge-50k", 2)
+            .shipping_line("flat_rate", "Flat Rate", "10.00".parse().unwrap())
+            .fee("Setup", "5.00".parse().unwrap())
+            .meta("platform", "mt5")
+            .build()
+            .unwrap();
+
+        let body = serde_json::to_value(&order).unwrap();
+        assert_eq!(body["payment_method"], "bacs");
+        assert_eq!(body["transaction_id"], "crm-1042");
+        assert_eq!(body["line_items"][0]["variation_id"], 23);
+        assert_eq!(body["line_items"][1]["sku"], "challenge-50k");
+        assert!(body["line_items"][1].get("product_id").is_none());
+        assert_eq!(body["shipping_lines"][0]["total"], "10.00");
+        assert_eq!(body["fee_lines"][0]["name"], "Setup");
+        assert!(body.get("shipping").is_none());
+        assert!(body.get("status").is_none());
+    }
+
+    #[test]
+    fn test_order_query_pairs() {
+        let client = WooHttpClient::with_transport(
+            Auth::basic("ck_test", "cs_test"),
+            "https://example.com/wp-json",
+            MockTransport::new(),
+        );
+        let query = OrderQuery::new()
+            .page(3, 100)
+            .status(&[OrderStatus::Processing, OrderStatus::OnHold])
+            .created_between(Some("2024-03-01T00:00:00".parse().unwrap()), None)
+            .order_by(crate::OrderBy::Id, crate::SortOrder::Asc);
+        assert_eq!(
+            client.url_with_query("/wc/v3/orders", &query.query_pairs()),
+            "https://example.com/wp-json/wc/v3/orders?page=3&per_page=100\
+             &status=processing%2Con-hold&after=2024-03-01T00%3A00%3A00&dates_are_gmt=true\
+             &orderby=id&order=asc"
+        );
+    }
+
+    #[test]
+    fn test_status_transitions() {
+        assert!(OrderStatus::Pending.can_transition_to(OrderStatus::Processing));
+        assert!(OrderStatus::Processing.can_transition_to(OrderStatus::Completed));
+        assert!(OrderStatus::Completed.can_transition_to(OrderStatus::Refunded));
+        assert!(OrderStatus::Completed.can_transition_to(OrderStatus::Completed));
+        assert!(!OrderStatus::Completed.can_transition_to(OrderStatus::Pending));
+        assert!(!OrderStatus::Refunded.can_transition_to(OrderStatus::Processing));
+        assert!(!OrderStatus::Processing.can_transition_to(OrderStatus::CheckoutDraft));
+    }
+
+    #[tokio::test]
+    async fn test_transition_order() {
+        let client = WooHttpClient::with_transport(
+            Auth::basic("ck_test", "cs_test"),
+            "https://example.com/wp-json",
+            MockTransport::new(),
+        );
+        let transport = client.transport();
+        let mut order: Value =
+            serde_json::from_str(include_str!("../../fixtures/order.json")).unwrap();
+        order["status"] = "completed".into();
+        transport.respond_json(Method::GET, "/wc/v3/orders/727", 200, &order);
+
+        let err = client
+            .transition_order(OrderId(727), &StatusTransition::new(OrderStatus::Pending))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            WooCommerceHttpError::InvalidStatusTransition {
+                from: OrderStatus::Completed,
+                to: OrderStatus::Pending,
+                ..
+            }
+        ));
+        assert_eq!(transport.requests().len(), 1);
+
+        let mut refunded = order.clone();
+        refunded["status"] = "refunded".into();
+        transport.respond_json(Method::PUT, "/wc/v3/orders/727", 200, &refunded);
+        let note = serde_json::json!({
+            "id": 281,
+            "author": "system",
+            "date_created": "2024-03-01T10:00:00",
+            "date_created_gmt": "2024-03-01T13:00:00",
+            "note": "Chargeback",
+            "customer_note": false
+        });
+        transport.respond_json(Method::POST, "/wc/v3/orders/727/notes", 201, &note);
+
+        let updated = client
+            .transition_order(
+                OrderId(727),
+                &StatusTransition::new(OrderStatus::Refunded).note("Chargeback"),
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(updated.status, OrderStatus::Refunded);
+
+        let requests = transport.requests();
+        let update: Value = serde_json::from_slice(requests[2].body.as_ref().unwrap()).unwrap();
+        assert_eq!(update, serde_json::json!({ "status": "refunded" }));
+        let note: Value = serde_json::from_slice(requests[3].body.as_ref().unwrap()).unwrap();
+        assert_eq!(note["note"], "Chargeback");
+
+        // The status changed even though the note couldn't be added.
+        let client = WooHttpClient::with_transport(
+            Auth::basic("ck_test", "cs_test"),
+            "https://example.com/wp-json",
+            MockTransport::new(),
+        );
+        let transport = client.transport();
+        transport.respond_json(Method::GET, "/wc/v3/orders/727", 200, &order);
+        transport.respond_json(Method::PUT, "/wc/v3/orders/727", 200, &refunded);
+        let failure = serde_json::json!({
+            "code": "internal_error",
+            "message": "Database error",
+            "data": { "status": 500 }
+        });
+        transport.respond_json(Method::POST, "/wc/v3/orders/727/notes", 500, &failure);
+        let err = client
+            .transition_order(
+                OrderId(727),
+                &StatusTransition::new(OrderStatus::Refunded).note("Chargeback"),
+            )
+            .await
+            .unwrap_err();
+        match err {
+            WooCommerceHttpError::NoteFailed { order, error } => {
+                assert_eq!(order.status, OrderStatus::Refunded);
+                assert!(matches!(
+                    *error,
+                    WooCommerceHttpError::ErrorResponse(err) if err.data.status == 500
+                ));
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_refund() {
+        let client = WooHttpClient::with_transport(
+            Auth::basic("ck_test", "cs_test"),
+            "https://example.com/wp-json",
+            MockTransport::new(),
+        );
+        let transport = client.transport();
+        let refund = serde_json::json!({
+            "id": 726,
+            "date_created": "2024-03-02T10:00:00",
+            "date_created_gmt": "2024-03-02T13:00:00",
+            "amount": "10.00",
+            "reason": "Damaged",
+            "refunded_by": 1,
+            "refunded_payment": false,
+            "meta_data": []
+        });
+        transport.respond_json(Method::POST, "/wc/v3/orders/727/refunds", 201, &refund);
+
+        let refund = client
+            .create_refund(
+                OrderId(727),
+                &CreateRefund::new("10.00".parse().unwrap()).reason("Damaged"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(refund.id, 726);
+        assert_eq!(refund.amount.to_string(), "10.00");
+
+        let request = transport.last_request().unwrap();
+        let body: Value = serde_json::from_slice(request.body.as_ref().unwrap()).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({ "amount": "10.00", "reason": "Damaged", "api_refund": false })
+        );
+    }
+
     // This test requires a valid consumer key and secret
     // cargo test test_post_order -- --nocapture
     #[tokio::test]
@@ -150,6 +634,7 @@ mod tests {
                     country: "US".to_string(),
                     email: "john.doe@example.com".to_string(),
                     phone: "(555) 555-5555".to_string(),
+                    ..Default::default()
                 },
                 meta_data: vec![
                     /* MetaData {
@@ -165,13 +650,13 @@ mod tests {
                 ],
                 line_items: vec![CreateLineItem {
                     //product_id: 60,
-                    product_id: 1915,
+                    product_id: Some(ProductId(1915)),
                     quantity: 1,
-                    total: None,
-                    subtotal: None,
+                    ..Default::default()
                 }],
-                customer_id: 0,
+                customer_id: CustomerId(0),
                 coupon_lines: vec![],
+                ..Default::default()
             })
             .await
             .unwrap();
@@ -1,6 +1,10 @@
+use reqwest::Method;
 use service_sdk::my_logger::{LogEventCtx, LOGGER};
 
-use crate::{CreateProduct, Product, WooCommerceHttpError, WooHttpClient};
+use crate::{
+    CreateProduct, Product, ProductId, ProductQuery, Transport, UpdateProduct,
+    WooCommerceHttpError, WooHttpClient,
+};
 
 #[allow(async_fn_in_trait)]
 pub trait ProductClient {
@@ -15,16 +19,33 @@ pub trait ProductClient {
         per_page: usize,
     ) -> Result<Vec<Product>, WooCommerceHttpError>;
 
-    async fn update_product(&self, product: &Product) -> Result<(), WooCommerceHttpError>;
+    /// One page of products, see [`ProductQuery::page`].
+    async fn list_products(
+        &self,
+        query: &ProductQuery,
+    ) -> Result<Vec<Product>, WooCommerceHttpError>;
+
+    async fn get_product(
+        &self,
+        product_id: ProductId,
+    ) -> Result<Option<Product>, WooCommerceHttpError>;
+
+    /// Changes only the fields set in `product` and returns the updated product.
+    async fn update_product(
+        &self,
+        product_id: ProductId,
+        product: &UpdateProduct,
+    ) -> Result<Product, WooCommerceHttpError>;
 }
 
-impl ProductClient for WooHttpClient {
+impl<T: Transport> ProductClient for WooHttpClient<T> {
     async fn create_product(
         &self,
         product: &CreateProduct,
     ) -> Result<Product, WooCommerceHttpError> {
         let url = format!("{}/wc/v3/products", self.base_url);
-        let res = self.client.post(&url).json(product).send().await;
+        let request = self.request(Method::POST, &url).json(product)?;
+        let res = self.transport.send(request).await;
         match res {
             Ok(res) => {
                 if self.debug {
@@ -35,12 +56,12 @@ impl ProductClient for WooHttpClient {
                     );
                 }
 
-                let res = match self.check_for_failed_status_code(res).await {
+                let res = match self.check_for_failed_status_code(res) {
                     crate::ResponseStatusCheck::Ok(res) => res,
                     crate::ResponseStatusCheck::Err(err) => return err,
                 };
 
-                let product: Result<Product, reqwest::Error> = res.json().await;
+                let product: Result<Product, serde_json::Error> = self.decode(&res);
 
                 return Ok(product?);
             }
@@ -66,7 +87,7 @@ impl ProductClient for WooHttpClient {
             "{}/wc/v3/products?page={}&per_page={}",
             self.base_url, page, per_page
         );
-        let res = self.client.get(&url).send().await;
+        let res = self.transport.send(self.request(Method::GET, &url)).await;
         match res {
             Ok(res) => {
                 if self.debug {
@@ -77,12 +98,12 @@ impl ProductClient for WooHttpClient {
                     );
                 }
 
-                let res = match self.check_for_failed_status_code(res).await {
+                let res = match self.check_for_failed_status_code(res) {
                     crate::ResponseStatusCheck::Ok(res) => res,
                     crate::ResponseStatusCheck::Err(err) => return err,
                 };
 
-                let products = res.json().await;
+                let products = self.decode(&res);
                 return Ok(products?);
             }
             Err(e) => {
@@ -98,9 +119,89 @@ impl ProductClient for WooHttpClient {
         }
     }
 
-    async fn update_product(&self, product: &Product) -> Result<(), WooCommerceHttpError> {
-        let url = format!("{}/wc/v3/products/{}", self.base_url, product.id);
-        let res = self.client.put(&url).json(product).send().await;
+    async fn list_products(
+        &self,
+        query: &ProductQuery,
+    ) -> Result<Vec<Product>, WooCommerceHttpError> {
+        let url = self.url_with_query("/wc/v3/products", &query.query_pairs());
+        let res = self.transport.send(self.request(Method::GET, &url)).await;
+        match res {
+            Ok(res) => {
+                if self.debug {
+                    LOGGER.write_info(
+                        "WooHttpClient::list_products",
+                        format!("Response: {:?}", res),
+                        LogEventCtx::new(),
+                    );
+                }
+
+                let res = match self.check_for_failed_status_code(res) {
+                    crate::ResponseStatusCheck::Ok(res) => res,
+                    crate::ResponseStatusCheck::Err(err) => return err,
+                };
+
+                let products = self.decode(&res);
+                return Ok(products?);
+            }
+            Err(e) => {
+                if self.debug {
+                    LOGGER.write_error(
+                        "WooHttpClient::list_products",
+                        format!("Error: {:?}", e),
+                        LogEventCtx::new(),
+                    );
+                }
+                return Err(e.into());
+            }
+        }
+    }
+
+    async fn get_product(
+        &self,
+        product_id: ProductId,
+    ) -> Result<Option<Product>, WooCommerceHttpError> {
+        let url = format!("{}/wc/v3/products/{}", self.base_url, product_id);
+        let res = self.transport.send(self.request(Method::GET, &url)).await;
+        match res {
+            Ok(res) => {
+                if self.debug {
+                    LOGGER.write_info(
+                        "WooHttpClient::get_product",
+                        format!("Response: {:?}", res),
+                        LogEventCtx::new(),
+                    );
+                }
+
+                let res = match self.check_for_failed_status_code(res) {
+                    crate::ResponseStatusCheck::Ok(res) => res,
+                    crate::ResponseStatusCheck::Err(err) => return err,
+                };
+
+                let product = self.decode(&res);
+
+                return Ok(product?);
+            }
+            Err(e) => {
+                if self.debug {
+                    LOGGER.write_error(
+                        "WooHttpClient::get_product",
+                        format!("Error: {:?}", e),
+                        LogEventCtx::new(),
+                    );
+                }
+                return Err(e.into());
+            }
+        }
+    }
+
+    async fn update_product(
+        &self,
+        product_id: ProductId,
+        product: &UpdateProduct,
+    ) -> Result<Product, WooCommerceHttpError> {
+        let url = format!("{}/wc/v3/products/{}", self.base_url, product_id);
+        let request = self.request(Method::PUT, &url).json(product)?;
+        let res = self.transport.send(request).await;
         match res {
             Ok(res) => {
                 if self.debug {
@@ -111,12 +212,14 @@ impl ProductClient for WooHttpClient {
                     );
                 }
 
-                let _ = match self.check_for_failed_status_code(res).await {
+                let res = match self.check_for_failed_status_code(res) {
                     crate::ResponseStatusCheck::Ok(res) => res,
                     crate::ResponseStatusCheck::Err(err) => return err,
                 };
 
-                return Ok(());
+                let product: Result<Product, serde_json::Error> = self.decode(&res);
+
+                return Ok(product?);
             }
             Err(e) => {
                 if self.debug {
@@ -136,48 +239,163 @@ impl ProductClient for WooHttpClient {
 mod tests {
     use serde_json::Value;
 
-    use crate::MetaData;
+    use crate::{
+        Auth, HttpResponse, ImageRef, MockTransport, NewAttribute, ProductType, StockStatus,
+    };
 
     use super::*;
 
+    #[tokio::test]
+    async fn test_create_and_update_product_mock() {
+        let client = WooHttpClient::with_transport(
+            Auth::basic("ck_test", "cs_test"),
+            "https://example.com/wp-json",
+            MockTransport::new(),
+        );
+        let product = r#"{
+            "id": 794,
+            "name": "Premium Quality",
+            "date_created": "2017-03-23T17:01:14",
+            "date_created_gmt": "2017-03-23T20:01:14",
+            "date_modified": "2017-03-23T17:01:14",
+            "date_modified_gmt": "2017-03-23T20:01:14",
+            "type": "bundle",
+            "status": "publish",
+            "stock_status": "onbackorder",
+            "regular_price": "21.99",
+            "sale_price": ""
+        }"#;
+        client
+            .transport()
+            .respond(Method::POST, "/wc/v3/products", HttpResponse::new(201, product));
+        client
+            .transport()
+            .respond(Method::PUT, "/wc/v3/products/794", HttpResponse::new(200, product));
+
+        let create = CreateProduct::builder("Premium Quality")
+            .regular_price("21.99".parse().unwrap())
+            .sale_price("19.99".parse().unwrap())
+            .sku("premium-quality")
+            .stock(10)
+            .image(ImageRef::from_src("https://example.com/premium.jpg"))
+            .attribute(NewAttribute::custom("Size", &["S", "M"]).for_variations(true))
+            .upsell(ProductId(31))
+            .build()
+            .unwrap();
+        let created = client.create_product(&create).await.unwrap();
+        assert_eq!(created.product_type, ProductType::Other("bundle".to_string()));
+        assert_eq!(created.stock_status, StockStatus::OnBackorder);
+        assert_eq!(created.sale_price, None);
+
+        let body: Value =
+            serde_json::from_slice(&client.transport().last_request().unwrap().body.unwrap())
+                .unwrap();
+        assert_eq!(body["sale_price"], "19.99");
+        assert_eq!(body["manage_stock"], true);
+        assert_eq!(body["images"][0]["src"], "https://example.com/premium.jpg");
+        assert!(body["images"][0].get("id").is_none());
+        assert_eq!(body["attributes"][0]["variation"], true);
+        assert_eq!(body["upsell_ids"][0], 31);
+        assert!(body.get("type").is_none());
+
+        let updated = client
+            .update_product(
+                created.id,
+                &UpdateProduct {
+                    stock_status: Some(StockStatus::OutOfStock),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(updated.id, ProductId(794));
+        let body: Value =
+            serde_json::from_slice(&client.transport().last_request().unwrap().body.unwrap())
+                .unwrap();
+        assert_eq!(body, serde_json::json!({"stock_status": "outofstock"}));
+    }
+
+    #[test]
+    fn test_update_product_clears_sale() {
+        let update = UpdateProduct {
+            regular_price: Some("21.99".parse().unwrap()),
+            sale_price: Some(None),
+            date_on_sale_from_gmt: Some(None),
+            date_on_sale_to_gmt: Some(None),
+            ..Default::default()
+        };
+        let body = serde_json::to_value(&update).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "regular_price": "21.99",
+                "sale_price": "",
+                "date_on_sale_from_gmt": "",
+                "date_on_sale_to_gmt": ""
+            })
+        );
+
+        let update = UpdateProduct {
+            sale_price: Some(Some("19.99".parse().unwrap())),
+            ..Default::default()
+        };
+        let body = serde_json::to_value(&update).unwrap();
+        assert_eq!(body, serde_json::json!({"sale_price": "19.99"}));
+        let parsed: UpdateProduct = serde_json::from_value(body).unwrap();
+        assert_eq!(parsed.sale_price, Some(Some("19.99".parse().unwrap())));
+        assert!(parsed.date_on_sale_to_gmt.is_none());
+    }
+
+    #[test]
+    fn test_create_product_builder_validation() {
+        #[cfg(feature = "rust_decimal")]
+        assert!(CreateProduct::builder("Sale")
+            .regular_price("10.00".parse().unwrap())
+            .sale_price("12.00".parse().unwrap())
+            .build()
+            .is_err());
+        assert_eq!(
+            CreateProduct::builder("Affiliate")
+                .product_type(ProductType::External)
+                .build()
+                .unwrap_err(),
+            crate::BuilderError::Missing("external_url")
+        );
+        assert!(CreateProduct::builder("Open sale")
+            .sale_period(Some("2024-01-01T00:00:00".parse().unwrap()), None)
+            .build()
+            .is_err());
+    }
+
     // This test requires a valid consumer key and secret
     //#[tokio::test]
     async fn _test_post_product() {
         let client = WooHttpClient::new("ck_*", "cs_*", "https://checkout*/wp-json");
         let resp = client
-            .create_product(&CreateProduct {
-                name: "test_post_product".to_string(),
-                product_type: "simple".to_string(),
-                regular_price: "100.00".to_string(),
-                price: "100.00".to_string(),
-                virtual_type: true,
-                description: "test_post_product".to_string(),
-                short_description: "test_post_product".to_string(),
-                categories: vec![],
-                images: vec![],
-                meta_data: vec![
-                    MetaData {
-                        id: 379,
-                        key: "minimum_allowed_quantity".to_string(),
-                        value: Value::String("1".to_string()),
-                    },
-                    MetaData {
-                        id: 380,
-                        key: "maximum_allowed_quantity".to_string(),
-                        value: Value::String("1".to_string()),
-                    },
-                ],
-            })
+            .create_product(
+                &CreateProduct::builder("test_post_product")
+                    .regular_price("100.00".parse().unwrap())
+                    .virtual_product(true)
+                    .description("test_post_product")
+                    .short_description("test_post_product")
+                    .meta("minimum_allowed_quantity", Value::String("1".to_string()))
+                    .meta("maximum_allowed_quantity", Value::String("1".to_string()))
+                    .build()
+                    .unwrap(),
+            )
             .await
             .unwrap();
 
         println!("Response: {:?}", resp);
         let products = client.get_products(1, 10).await.unwrap();
         println!("Products: {:?}", products);
-        for mut item in products {
-            item.name = format!("{}-updated", item.name);
+        for item in products {
+            let update = UpdateProduct {
+                name: Some(format!("{}-updated", item.name)),
+                ..Default::default()
+            };
             println!("Product: {:?}", item);
-            client.update_product(&item).await.unwrap();
+            client.update_product(item.id, &update).await.unwrap();
         }
     }
 }
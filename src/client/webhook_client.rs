@@ -0,0 +1,392 @@
+use reqwest::Method;
+use service_sdk::my_logger::{LogEventCtx, LOGGER};
+
+use crate::{
+    BatchRequest, BatchResponse, CreateWebhook, Transport, UpdateWebhook, Webhook, WebhookId,
+    WooCommerceHttpError, WooHttpClient,
+};
+
+const WEBHOOKS_PAGE_SIZE: usize = 100;
+
+#[allow(async_fn_in_trait)]
+pub trait WebhookClient {
+    async fn create_webhook(
+        &self,
+        webhook: &CreateWebhook,
+    ) -> Result<Webhook, WooCommerceHttpError>;
+
+    async fn get_webhooks(
+        &self,
+        page: usize,
+        per_page: usize,
+    ) -> Result<Vec<Webhook>, WooCommerceHttpError>;
+
+    async fn get_webhook(&self, id: WebhookId) -> Result<Option<Webhook>, WooCommerceHttpError>;
+
+    async fn update_webhook(
+        &self,
+        id: WebhookId,
+        webhook: &UpdateWebhook,
+    ) -> Result<Webhook, WooCommerceHttpError>;
+
+    async fn delete_webhook(&self, id: WebhookId) -> Result<Webhook, WooCommerceHttpError>;
+
+    async fn batch_webhooks(
+        &self,
+        batch: &BatchRequest<CreateWebhook, UpdateWebhook, WebhookId>,
+    ) -> Result<BatchResponse<Webhook>, WooCommerceHttpError>;
+
+    /// Idempotently registers a webhook: an existing webhook with the same topic and
+    /// delivery url is reused (and patched if needed), duplicates of it are deleted and
+    /// a new webhook is only created when there is none. Safe to call on every startup.
+    async fn ensure_webhook(
+        &self,
+        webhook: &CreateWebhook,
+    ) -> Result<Webhook, WooCommerceHttpError>;
+}
+
+impl<T: Transport> WebhookClient for WooHttpClient<T> {
+    async fn create_webhook(
+        &self,
+        webhook: &CreateWebhook,
+    ) -> Result<Webhook, WooCommerceHttpError> {
+        let url = format!("{}/wc/v3/webhooks", self.base_url);
+        let request = self.request(Method::POST, &url).json(webhook)?;
+        let res = self.transport.send(request).await;
+        match res {
+            Ok(res) => {
+                if self.debug {
+                    LOGGER.write_info(
+                        "WooHttpClient::create_webhook",
+                        format!("Response: {:?}", res),
+                        LogEventCtx::new(),
+                    );
+                }
+
+                let res = match self.check_for_failed_status_code(res) {
+                    crate::ResponseStatusCheck::Ok(res) => res,
+                    crate::ResponseStatusCheck::Err(err) => return err,
+                };
+
+                let webhook: Result<Webhook, serde_json::Error> = self.decode(&res);
+
+                return Ok(webhook?);
+            }
+            Err(e) => {
+                if self.debug {
+                    LOGGER.write_error(
+                        "WooHttpClient::create_webhook",
+                        format!("Error: {:?}", e),
+                        LogEventCtx::new(),
+                    );
+                }
+                return Err(e.into());
+            }
+        }
+    }
+
+    async fn get_webhooks(
+        &self,
+        page: usize,
+        per_page: usize,
+    ) -> Result<Vec<Webhook>, WooCommerceHttpError> {
+        let url = format!(
+            "{}/wc/v3/webhooks?page={}&per_page={}",
+            self.base_url, page, per_page
+        );
+        let res = self.transport.send(self.request(Method::GET, &url)).await;
+        match res {
+            Ok(res) => {
+                if self.debug {
+                    LOGGER.write_info(
+                        "WooHttpClient::get_webhooks",
+                        format!("Response: {:?}", res),
+                        LogEventCtx::new(),
+                    );
+                }
+
+                let res = match self.check_for_failed_status_code(res) {
+                    crate::ResponseStatusCheck::Ok(res) => res,
+                    crate::ResponseStatusCheck::Err(err) => return err,
+                };
+
+                let webhooks = self.decode(&res);
+                return Ok(webhooks?);
+            }
+            Err(e) => {
+                if self.debug {
+                    LOGGER.write_error(
+                        "WooHttpClient::get_webhooks",
+                        format!("Error: {:?}", e),
+                        LogEventCtx::new(),
+                    );
+                }
+                return Err(e.into());
+            }
+        }
+    }
+
+    async fn get_webhook(&self, id: WebhookId) -> Result<Option<Webhook>, WooCommerceHttpError> {
+        let url = format!("{}/wc/v3/webhooks/{}", self.base_url, id);
+        let res = self.transport.send(self.request(Method::GET, &url)).await;
+        match res {
+            Ok(res) => {
+                if self.debug {
+                    LOGGER.write_info(
+                        "WooHttpClient::get_webhook",
+                        format!("Response: {:?}", res),
+                        LogEventCtx::new(),
+                    );
+                }
+
+                let res = match self.check_for_failed_status_code(res) {
+                    crate::ResponseStatusCheck::Ok(res) => res,
+                    crate::ResponseStatusCheck::Err(err) => return err,
+                };
+
+                let webhook = self.decode(&res);
+                return Ok(webhook?);
+            }
+            Err(e) => {
+                if self.debug {
+                    LOGGER.write_error(
+                        "WooHttpClient::get_webhook",
+                        format!("Error: {:?}", e),
+                        LogEventCtx::new(),
+                    );
+                }
+                return Err(e.into());
+            }
+        }
+    }
+
+    async fn update_webhook(
+        &self,
+        id: WebhookId,
+        webhook: &UpdateWebhook,
+    ) -> Result<Webhook, WooCommerceHttpError> {
+        let url = format!("{}/wc/v3/webhooks/{}", self.base_url, id);
+        let request = self.request(Method::PUT, &url).json(webhook)?;
+        let res = self.transport.send(request).await;
+        match res {
+            Ok(res) => {
+                if self.debug {
+                    LOGGER.write_info(
+                        "WooHttpClient::update_webhook",
+                        format!("Response: {:?}", res),
+                        LogEventCtx::new(),
+                    );
+                }
+
+                let res = match self.check_for_failed_status_code(res) {
+                    crate::ResponseStatusCheck::Ok(res) => res,
+                    crate::ResponseStatusCheck::Err(err) => return err,
+                };
+
+                let webhook = self.decode(&res);
+                return Ok(webhook?);
+            }
+            Err(e) => {
+                if self.debug {
+                    LOGGER.write_error(
+                        "WooHttpClient::update_webhook",
+                        format!("Error: {:?}", e),
+                        LogEventCtx::new(),
+                    );
+                }
+                return Err(e.into());
+            }
+        }
+    }
+
+    async fn delete_webhook(&self, id: WebhookId) -> Result<Webhook, WooCommerceHttpError> {
+        let url = format!("{}/wc/v3/webhooks/{}?force=true", self.base_url, id);
+        let res = self
+            .transport
+            .send(self.request(Method::DELETE, &url))
+            .await;
+        match res {
+            Ok(res) => {
+                if self.debug {
+                    LOGGER.write_info(
+                        "WooHttpClient::delete_webhook",
+                        format!("Response: {:?}", res),
+                        LogEventCtx::new(),
+                    );
+                }
+
+                let res = match self.check_for_failed_status_code(res) {
+                    crate::ResponseStatusCheck::Ok(res) => res,
+                    crate::ResponseStatusCheck::Err(err) => return err,
+                };
+
+                let webhook = self.decode(&res);
+                return Ok(webhook?);
+            }
+            Err(e) => {
+                if self.debug {
+                    LOGGER.write_error(
+                        "WooHttpClient::delete_webhook",
+                        format!("Error: {:?}", e),
+                        LogEventCtx::new(),
+                    );
+                }
+                return Err(e.into());
+            }
+        }
+    }
+
+    async fn batch_webhooks(
+        &self,
+        batch: &BatchRequest<CreateWebhook, UpdateWebhook, WebhookId>,
+    ) -> Result<BatchResponse<Webhook>, WooCommerceHttpError> {
+        let url = format!("{}/wc/v3/webhooks/batch", self.base_url);
+        let request = self.request(Method::POST, &url).json(batch)?;
+        let res = self.transport.send(request).await;
+        match res {
+            Ok(res) => {
+                if self.debug {
+                    LOGGER.write_info(
+                        "WooHttpClient::batch_webhooks",
+                        format!("Response: {:?}", res),
+                        LogEventCtx::new(),
+                    );
+                }
+
+                let res = match self.check_for_failed_status_code(res) {
+                    crate::ResponseStatusCheck::Ok(res) => res,
+                    crate::ResponseStatusCheck::Err(err) => return err,
+                };
+
+                let response = self.decode(&res);
+                return Ok(response?);
+            }
+            Err(e) => {
+                if self.debug {
+                    LOGGER.write_error(
+                        "WooHttpClient::batch_webhooks",
+                        format!("Error: {:?}", e),
+                        LogEventCtx::new(),
+                    );
+                }
+                return Err(e.into());
+            }
+        }
+    }
+
+    async fn ensure_webhook(
+        &self,
+        webhook: &CreateWebhook,
+    ) -> Result<Webhook, WooCommerceHttpError> {
+        let mut matching = Vec::new();
+        let mut page = 1;
+        loop {
+            let webhooks = self.get_webhooks(page, WEBHOOKS_PAGE_SIZE).await?;
+            let last_page = webhooks.len() < WEBHOOKS_PAGE_SIZE;
+            matching.extend(webhooks.into_iter().filter(|item| {
+                item.topic == webhook.topic && item.delivery_url == webhook.delivery_url
+            }));
+            if last_page {
+                break;
+            }
+            page += 1;
+        }
+
+        if matching.is_empty() {
+            return self.create_webhook(webhook).await;
+        }
+
+        // Oldest one wins, everything else is left over from previous registrations.
+        matching.sort_by_key(|item| item.id);
+        let existing = matching.remove(0);
+        for duplicate in matching {
+            if self.debug {
+                LOGGER.write_info(
+                    "WooHttpClient::ensure_webhook",
+                    format!(
+                        "Deleting duplicate webhook {} for {} -> {}",
+                        duplicate.id, duplicate.topic, duplicate.delivery_url
+                    ),
+                    LogEventCtx::new(),
+                );
+            }
+            self.delete_webhook(duplicate.id).await?;
+        }
+
+        match existing.diff(webhook) {
+            Some(update) => self.update_webhook(existing.id, &update).await,
+            None => Ok(existing),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use crate::{Auth, MockTransport, WebhookTopic};
+
+    use super::*;
+
+    fn webhook_json(id: u64, delivery_url: &str) -> Value {
+        json!({
+            "id": id,
+            "name": "Order sync",
+            "status": "active",
+            "topic": "order.created",
+            "delivery_url": delivery_url,
+            "date_created": "2024-03-01T10:00:00",
+            "date_created_gmt": "2024-03-01T13:00:00"
+        })
+    }
+
+    #[tokio::test]
+    async fn test_ensure_webhook_mock() {
+        let client = WooHttpClient::with_transport(
+            Auth::basic("ck_test", "cs_test"),
+            "https://example.com/wp-json",
+            MockTransport::new(),
+        );
+        let transport = client.transport();
+        let url = "https://example.com/hooks";
+        let listed = json!([
+            webhook_json(12, url),
+            webhook_json(11, url),
+            webhook_json(13, "https://example.com/other"),
+        ]);
+        transport.respond_json(Method::GET, "/wc/v3/webhooks", 200, &listed);
+        transport.respond_json(Method::DELETE, "/wc/v3/webhooks/12", 200, &listed[0]);
+        transport.respond_json(Method::PUT, "/wc/v3/webhooks/11", 200, &listed[1]);
+
+        let desired = CreateWebhook {
+            name: Some("Order sync".to_string()),
+            status: None,
+            topic: WebhookTopic::OrderCreated,
+            delivery_url: url.to_string(),
+            secret: Some("rotated".to_string()),
+            api_version: None,
+        };
+        let webhook = client.ensure_webhook(&desired).await.unwrap();
+        assert_eq!(webhook.id, WebhookId(11));
+
+        let requests = transport.requests();
+        let methods: Vec<&Method> = requests.iter().map(|request| &request.method).collect();
+        assert_eq!(methods, [&Method::GET, &Method::DELETE, &Method::PUT]);
+        assert!(requests[1].url.contains("/wc/v3/webhooks/12?force=true"));
+        let update: Value = serde_json::from_slice(requests[2].body.as_ref().unwrap()).unwrap();
+        assert_eq!(update, json!({ "secret": "rotated" }));
+
+        // Nothing registered yet: the webhook is created.
+        let transport = MockTransport::new();
+        transport.respond_json(Method::GET, "/wc/v3/webhooks", 200, &json!([]));
+        transport.respond_json(Method::POST, "/wc/v3/webhooks", 201, &webhook_json(20, url));
+        let client = WooHttpClient::with_transport(
+            Auth::basic("ck_test", "cs_test"),
+            "https://example.com/wp-json",
+            transport,
+        );
+        let webhook = client.ensure_webhook(&desired).await.unwrap();
+        assert_eq!(webhook.id, WebhookId(20));
+        assert_eq!(client.transport().requests().len(), 2);
+    }
+}
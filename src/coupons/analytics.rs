@@ -0,0 +1,377 @@
+use std::collections::{BTreeMap, HashSet};
+use std::io;
+
+use serde::Serialize;
+
+use crate::{
+    normalize_coupon_code, round_for, Money, Order, OrderBy, OrderClient, OrderId, OrderQuery,
+    OrderStatus, SortOrder, WooCommerceHttpError, WooDateTimeUtc,
+};
+
+/// The statuses WooCommerce Analytics counts as sales.
+pub const REPORTED_ORDER_STATUSES: [OrderStatus; 3] = [
+    OrderStatus::Processing,
+    OrderStatus::Completed,
+    OrderStatus::OnHold,
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DailyCouponUsage {
+    /// `YYYY-MM-DD` of the order creation in UTC.
+    pub date: String,
+    pub redemptions: usize,
+    pub discount: Money,
+    pub revenue: Money,
+}
+
+/// Usage of one coupon code in one currency.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CouponUsage {
+    /// Lowercase, as WooCommerce stores it.
+    pub code: String,
+    pub currency: String,
+    /// Orders that used the code.
+    pub redemptions: usize,
+    pub discount: Money,
+    pub discount_tax: Money,
+    /// Totals of those orders after discounts, less refunds.
+    pub revenue: Money,
+    /// Registered customers by id, guests by billing email.
+    pub unique_customers: usize,
+    pub first_used: WooDateTimeUtc,
+    pub last_used: WooDateTimeUtc,
+    pub daily: Vec<DailyCouponUsage>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct CouponUsageReport {
+    pub after: Option<WooDateTimeUtc>,
+    pub before: Option<WooDateTimeUtc>,
+    pub orders_scanned: usize,
+    /// Sorted by code, then currency.
+    pub coupons: Vec<CouponUsage>,
+}
+
+impl CouponUsageReport {
+    pub fn coupon(&self, code: &str) -> impl Iterator<Item = &CouponUsage> {
+        let code = normalize_coupon_code(code);
+        self.coupons.iter().filter(move |usage| usage.code == code)
+    }
+
+    /// One row per coupon and currency.
+    pub fn write_csv<W: io::Write>(&self, writer: W) -> Result<(), csv::Error> {
+        let mut writer = csv::Writer::from_writer(writer);
+        writer.write_record([
+            "code",
+            "currency",
+            "redemptions",
+            "discount",
+            "discount_tax",
+            "revenue",
+            "unique_customers",
+            "first_used",
+            "last_used",
+        ])?;
+        for usage in &self.coupons {
+            writer.write_record([
+                usage.code.clone(),
+                usage.currency.clone(),
+                usage.redemptions.to_string(),
+                usage.discount.to_string(),
+                usage.discount_tax.to_string(),
+                usage.revenue.to_string(),
+                usage.unique_customers.to_string(),
+                usage.first_used.to_string(),
+                usage.last_used.to_string(),
+            ])?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// One row per coupon, currency and day with usage.
+    pub fn write_daily_csv<W: io::Write>(&self, writer: W) -> Result<(), csv::Error> {
+        let mut writer = csv::Writer::from_writer(writer);
+        writer.write_record([
+            "date",
+            "code",
+            "currency",
+            "redemptions",
+            "discount",
+            "revenue",
+        ])?;
+        for usage in &self.coupons {
+            for day in &usage.daily {
+                writer.write_record([
+                    day.date.clone(),
+                    usage.code.clone(),
+                    usage.currency.clone(),
+                    day.redemptions.to_string(),
+                    day.discount.to_string(),
+                    day.revenue.to_string(),
+                ])?;
+            }
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    pub fn write_json<W: io::Write>(&self, writer: W) -> Result<(), serde_json::Error> {
+        serde_json::to_writer_pretty(writer, self)
+    }
+}
+
+struct UsageTotals {
+    redemptions: usize,
+    discount: Money,
+    discount_tax: Money,
+    revenue: Money,
+    customers: HashSet<String>,
+    first_used: WooDateTimeUtc,
+    last_used: WooDateTimeUtc,
+    daily: BTreeMap<String, DailyCouponUsage>,
+}
+
+impl UsageTotals {
+    fn new(used: WooDateTimeUtc) -> Self {
+        UsageTotals {
+            redemptions: 0,
+            discount: Money::ZERO,
+            discount_tax: Money::ZERO,
+            revenue: Money::ZERO,
+            customers: HashSet::new(),
+            first_used: used,
+            last_used: used,
+            daily: BTreeMap::new(),
+        }
+    }
+}
+
+/// Folds orders into a [`CouponUsageReport`]. Orders seen twice are counted once.
+#[derive(Default)]
+pub struct CouponUsageAggregator {
+    after: Option<WooDateTimeUtc>,
+    before: Option<WooDateTimeUtc>,
+    seen: HashSet<OrderId>,
+    totals: BTreeMap<(String, String), UsageTotals>,
+}
+
+impl CouponUsageAggregator {
+    pub fn new(after: Option<WooDateTimeUtc>, before: Option<WooDateTimeUtc>) -> Self {
+        CouponUsageAggregator {
+            after,
+            before,
+            ..Default::default()
+        }
+    }
+
+    pub fn add_order(&mut self, order: &Order) {
+        if !self.seen.insert(order.id) {
+            return;
+        }
+
+        // An order using the same code on several lines is still one redemption.
+        let mut discounts: BTreeMap<String, (Money, Money)> = BTreeMap::new();
+        for line in order.coupon_lines.iter().flatten() {
+            let entry = discounts
+                .entry(normalize_coupon_code(&line.code))
+                .or_default();
+            entry.0 += line.discount;
+            entry.1 += line.discount_tax;
+        }
+        if discounts.is_empty() {
+            return;
+        }
+
+        let refunded: Money = order.refunds.iter().map(|refund| refund.total.abs()).sum();
+        let revenue = order.total - refunded;
+        let customer = if order.customer_id.0 > 0 {
+            order.customer_id.to_string()
+        } else {
+            order.billing.email.trim().to_lowercase()
+        };
+        let created = order.date_created_gmt;
+        // `YYYY-MM-DD`, which also sorts by day.
+        let day = created.to_string()[..10].to_string();
+
+        for (code, (discount, discount_tax)) in discounts {
+            let totals = self
+                .totals
+                .entry((code, order.currency.clone()))
+                .or_insert_with(|| UsageTotals::new(created));
+            totals.redemptions += 1;
+            totals.discount += discount;
+            totals.discount_tax += discount_tax;
+            totals.revenue += revenue;
+            if !customer.is_empty() {
+                totals.customers.insert(customer.clone());
+            }
+            totals.first_used = totals.first_used.min(created);
+            totals.last_used = totals.last_used.max(created);
+
+            let daily = totals
+                .daily
+                .entry(day.clone())
+                .or_insert_with(|| DailyCouponUsage {
+                    date: day.clone(),
+                    redemptions: 0,
+                    discount: Money::ZERO,
+                    revenue: Money::ZERO,
+                });
+            daily.redemptions += 1;
+            daily.discount += discount;
+            daily.revenue += revenue;
+        }
+    }
+
+    pub fn finish(self) -> CouponUsageReport {
+        let coupons = self
+            .totals
+            .into_iter()
+            .map(|((code, currency), totals)| CouponUsage {
+                code,
+                currency: currency.clone(),
+                redemptions: totals.redemptions,
+                discount: round_for(totals.discount, &currency),
+                discount_tax: round_for(totals.discount_tax, &currency),
+                revenue: round_for(totals.revenue, &currency),
+                unique_customers: totals.customers.len(),
+                first_used: totals.first_used,
+                last_used: totals.last_used,
+                daily: totals.daily.into_values().collect(),
+            })
+            .collect();
+        CouponUsageReport {
+            after: self.after,
+            before: self.before,
+            orders_scanned: self.seen.len(),
+            coupons,
+        }
+    }
+}
+
+/// Walks the orders created in a period and reports coupon usage.
+pub struct CouponUsageAnalyzer<'a, C: OrderClient> {
+    client: &'a C,
+    after: Option<WooDateTimeUtc>,
+    before: Option<WooDateTimeUtc>,
+    statuses: Vec<OrderStatus>,
+    per_page: usize,
+}
+
+impl<'a, C: OrderClient> CouponUsageAnalyzer<'a, C> {
+    pub fn new(client: &'a C) -> Self {
+        CouponUsageAnalyzer {
+            client,
+            after: None,
+            before: None,
+            statuses: REPORTED_ORDER_STATUSES.to_vec(),
+            per_page: 100,
+        }
+    }
+
+    /// Orders created in `[after, before)`, either bound may be open.
+    pub fn period(mut self, after: Option<WooDateTimeUtc>, before: Option<WooDateTimeUtc>) -> Self {
+        self.after = after;
+        self.before = before;
+        self
+    }
+
+    /// An empty list counts orders in any status.
+    pub fn statuses(mut self, statuses: &[OrderStatus]) -> Self {
+        self.statuses = statuses.to_vec();
+        self
+    }
+
+    pub fn per_page(mut self, per_page: usize) -> Self {
+        self.per_page = per_page.clamp(1, 100);
+        self
+    }
+
+    pub async fn run(&self) -> Result<CouponUsageReport, WooCommerceHttpError> {
+        let mut aggregator = CouponUsageAggregator::new(self.after, self.before);
+        let mut page = 1;
+        loop {
+            // Oldest first, so orders placed while paging end up on later pages.
+            let query = OrderQuery::new()
+                .page(page, self.per_page)
+                .status(&self.statuses)
+                .created_between(self.after, self.before)
+                .order_by(OrderBy::Id, SortOrder::Asc);
+            let orders = self.client.list_orders(&query).await?;
+            for order in &orders {
+                aggregator.add_order(order);
+            }
+            if orders.len() < self.per_page {
+                break;
+            }
+            page += 1;
+        }
+        Ok(aggregator.finish())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CustomerId, Refund};
+
+    use super::*;
+
+    fn order(id: u64, created: &str, customer_id: u64, email: &str) -> Order {
+        let mut order: Order =
+            serde_json::from_str(include_str!("../../fixtures/order.json")).unwrap();
+        order.id = OrderId(id);
+        order.date_created_gmt = created.parse().unwrap();
+        order.customer_id = CustomerId(customer_id);
+        order.billing.email = email.to_string();
+        order
+    }
+
+    #[test]
+    fn test_aggregate_coupon_usage() {
+        let first = order(1, "2024-03-01T10:00:00", 0, "Guest@Example.com");
+        let mut second = order(2, "2024-03-01T18:00:00", 0, "guest@example.com");
+        second.refunds.push(Refund {
+            id: 9,
+            reason: String::new(),
+            total: "-6.00".parse().unwrap(),
+            extra: Default::default(),
+        });
+        let mut third = order(3, "2024-03-02T09:00:00", 7, "");
+        third.coupon_lines.as_mut().unwrap()[0].code = "10OFF".to_string();
+        let mut plain = order(4, "2024-03-02T09:00:00", 7, "");
+        plain.coupon_lines = None;
+
+        let mut aggregator = CouponUsageAggregator::default();
+        for order in [&first, &second, &third, &first, &plain] {
+            aggregator.add_order(order);
+        }
+        let report = aggregator.finish();
+
+        assert_eq!(report.orders_scanned, 4);
+        assert_eq!(report.coupons.len(), 1);
+        let usage = report.coupon("10OFF").next().unwrap();
+        assert_eq!(usage.redemptions, 3);
+        assert_eq!(usage.discount, "15.00".parse().unwrap());
+        assert_eq!(usage.discount_tax, "1.50".parse().unwrap());
+        assert_eq!(usage.revenue, "192.00".parse().unwrap());
+        assert_eq!(usage.unique_customers, 2);
+        assert_eq!(usage.daily.len(), 2);
+        assert_eq!(usage.daily[0].redemptions, 2);
+        assert_eq!(usage.daily[0].revenue, "126.00".parse().unwrap());
+
+        let mut csv = Vec::new();
+        report.write_daily_csv(&mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "date,code,currency,redemptions,discount,revenue\n\
+             2024-03-01,10off,USD,2,10.00,126.00\n\
+             2024-03-02,10off,USD,1,5.00,66.00\n"
+        );
+
+        let mut json = Vec::new();
+        report.write_json(&mut json).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(json["coupons"][0]["revenue"], "192.00");
+    }
+}
@@ -0,0 +1,570 @@
+use std::fmt;
+
+use crate::{
+    normalize_coupon_code, round_for, Coupon, CustomerId, DiscountType, Money, Product, ProductId,
+    VariationId, WooDateTimeUtc,
+};
+
+/// One product in a prospective cart. `price` is the unit price the customer pays.
+#[derive(Debug, Clone)]
+pub struct CartLine {
+    pub product_id: ProductId,
+    pub variation_id: Option<VariationId>,
+    pub quantity: u32,
+    pub price: Money,
+    pub category_ids: Vec<i32>,
+    pub on_sale: bool,
+}
+
+impl CartLine {
+    pub fn new(product_id: ProductId, quantity: u32, price: Money) -> Self {
+        CartLine {
+            product_id,
+            variation_id: None,
+            quantity,
+            price,
+            category_ids: Vec::new(),
+            on_sale: false,
+        }
+    }
+
+    /// Price, categories and sale flag taken from the product.
+    pub fn from_product(product: &Product, quantity: u32) -> Self {
+        CartLine {
+            product_id: product.id,
+            variation_id: None,
+            quantity,
+            price: product.price.unwrap_or_default(),
+            category_ids: product
+                .categories
+                .iter()
+                .map(|category| category.id)
+                .collect(),
+            on_sale: product.on_sale,
+        }
+    }
+
+    pub fn variation(mut self, variation_id: VariationId) -> Self {
+        self.variation_id = Some(variation_id);
+        self
+    }
+
+    pub fn categories(mut self, category_ids: &[i32]) -> Self {
+        self.category_ids = category_ids.to_vec();
+        self
+    }
+
+    pub fn on_sale(mut self, on_sale: bool) -> Self {
+        self.on_sale = on_sale;
+        self
+    }
+
+    fn subtotal(&self) -> Option<Money> {
+        self.price.checked_mul(Money::from(self.quantity))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Cart {
+    pub lines: Vec<CartLine>,
+    pub currency: String,
+    pub customer_id: Option<CustomerId>,
+    pub customer_email: Option<String>,
+    /// Codes of coupons already applied to the cart.
+    pub applied_coupons: Vec<String>,
+    pub now: WooDateTimeUtc,
+}
+
+impl Cart {
+    pub fn new(currency: &str, now: WooDateTimeUtc) -> Self {
+        Cart {
+            lines: Vec::new(),
+            currency: currency.to_string(),
+            customer_id: None,
+            customer_email: None,
+            applied_coupons: Vec::new(),
+            now,
+        }
+    }
+
+    pub fn line(mut self, line: CartLine) -> Self {
+        self.lines.push(line);
+        self
+    }
+
+    pub fn customer(mut self, customer_id: Option<CustomerId>, email: &str) -> Self {
+        self.customer_id = customer_id.filter(|id| id.0 > 0);
+        self.customer_email = Some(email.to_string());
+        self
+    }
+
+    pub fn applied_coupon(mut self, code: &str) -> Self {
+        self.applied_coupons.push(code.to_string());
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineDiscount {
+    pub product_id: ProductId,
+    pub variation_id: Option<VariationId>,
+    pub amount: Money,
+}
+
+/// What the coupon takes off, rounded to the cart currency.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Discount {
+    pub total: Money,
+    pub lines: Vec<LineDiscount>,
+    pub free_shipping: bool,
+}
+
+/// Why WooCommerce would refuse the coupon, checked in the order WooCommerce does.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Rejection {
+    EmptyCart,
+    Expired {
+        expired_at: WooDateTimeUtc,
+    },
+    UsageLimitReached {
+        limit: i32,
+    },
+    UserUsageLimitReached {
+        limit: i32,
+    },
+    /// The coupon can't be combined with the coupons already in the cart.
+    IndividualUse {
+        applied: Vec<String>,
+    },
+    MinimumSpendNotMet {
+        minimum: Money,
+        subtotal: Money,
+    },
+    MaximumSpendExceeded {
+        maximum: Money,
+        subtotal: Money,
+    },
+    /// None of the cart's products are covered by the coupon's product or category list.
+    NotApplicableToProducts,
+    /// Cart coupons refuse carts holding any excluded product.
+    ExcludedProducts {
+        product_ids: Vec<ProductId>,
+    },
+    /// Cart coupons with `exclude_sale_items` refuse carts holding sale items.
+    SaleItemsInCart {
+        product_ids: Vec<ProductId>,
+    },
+    /// The coupon has email restrictions and the cart has no email.
+    EmailRequired,
+    EmailNotAllowed,
+    AmountOverflow,
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rejection::EmptyCart => write!(f, "the cart is empty"),
+            Rejection::Expired { expired_at } => write!(f, "coupon expired at {}Z", expired_at),
+            Rejection::UsageLimitReached { limit } => {
+                write!(f, "coupon usage limit of {} reached", limit)
+            }
+            Rejection::UserUsageLimitReached { limit } => {
+                write!(f, "coupon usage limit of {} per customer reached", limit)
+            }
+            Rejection::IndividualUse { applied } => write!(
+                f,
+                "coupon can't be used together with {}",
+                applied.join(", ")
+            ),
+            Rejection::MinimumSpendNotMet { minimum, subtotal } => {
+                write!(f, "minimum spend is {}, cart has {}", minimum, subtotal)
+            }
+            Rejection::MaximumSpendExceeded { maximum, subtotal } => {
+                write!(f, "maximum spend is {}, cart has {}", maximum, subtotal)
+            }
+            Rejection::NotApplicableToProducts => {
+                write!(f, "coupon is not applicable to the selected products")
+            }
+            Rejection::ExcludedProducts { product_ids } => {
+                write!(f, "coupon excludes products {:?}", product_ids)
+            }
+            Rejection::SaleItemsInCart { product_ids } => {
+                write!(f, "coupon is not valid for sale items {:?}", product_ids)
+            }
+            Rejection::EmailRequired => write!(f, "coupon needs a billing email"),
+            Rejection::EmailNotAllowed => write!(f, "coupon is not valid for this email"),
+            Rejection::AmountOverflow => write!(f, "cart amounts overflow"),
+        }
+    }
+}
+
+impl std::error::Error for Rejection {}
+
+/// Predicts whether WooCommerce applies `coupon` to `cart` and what it takes off.
+///
+/// Mirrors `WC_Discounts` for the built-in discount types. Taxes and other coupons'
+/// discounts are not taken into account.
+pub fn evaluate(coupon: &Coupon, cart: &Cart) -> Result<Discount, Rejection> {
+    if cart.lines.iter().all(|line| line.quantity == 0) {
+        return Err(Rejection::EmptyCart);
+    }
+    if let Some(expired_at) = coupon.date_expires_gmt {
+        if cart.now >= expired_at {
+            return Err(Rejection::Expired { expired_at });
+        }
+    }
+    if let Some(limit) = coupon.usage_limit.filter(|limit| *limit > 0) {
+        if coupon.usage_count >= limit {
+            return Err(Rejection::UsageLimitReached { limit });
+        }
+    }
+    if let Some(limit) = coupon.usage_limit_per_user.filter(|limit| *limit > 0) {
+        if uses_by_customer(coupon, cart) >= limit as usize {
+            return Err(Rejection::UserUsageLimitReached { limit });
+        }
+    }
+    if coupon.individual_use {
+        let applied: Vec<String> = cart
+            .applied_coupons
+            .iter()
+            .filter(|code| !coupon.matches_code(code))
+            .cloned()
+            .collect();
+        if !applied.is_empty() {
+            return Err(Rejection::IndividualUse { applied });
+        }
+    }
+
+    let subtotal = sum(cart.lines.iter().map(CartLine::subtotal))?;
+    if let Some(minimum) = coupon.minimum_amount.filter(|amount| !amount.is_zero()) {
+        if subtotal < minimum {
+            return Err(Rejection::MinimumSpendNotMet { minimum, subtotal });
+        }
+    }
+    if let Some(maximum) = coupon.maximum_amount.filter(|amount| !amount.is_zero()) {
+        if subtotal > maximum {
+            return Err(Rejection::MaximumSpendExceeded { maximum, subtotal });
+        }
+    }
+
+    let included: Vec<&CartLine> = cart
+        .lines
+        .iter()
+        .filter(|line| is_included(coupon, line))
+        .collect();
+    if included.is_empty() {
+        return Err(Rejection::NotApplicableToProducts);
+    }
+    let eligible: Vec<&CartLine> = match coupon.discount_type {
+        DiscountType::FixedCart => {
+            let excluded = product_ids(cart.lines.iter().filter(|line| is_excluded(coupon, line)));
+            if !excluded.is_empty() {
+                return Err(Rejection::ExcludedProducts {
+                    product_ids: excluded,
+                });
+            }
+            if coupon.exclude_sale_items {
+                let on_sale = product_ids(cart.lines.iter().filter(|line| line.on_sale));
+                if !on_sale.is_empty() {
+                    return Err(Rejection::SaleItemsInCart {
+                        product_ids: on_sale,
+                    });
+                }
+            }
+            cart.lines.iter().filter(|line| line.quantity > 0).collect()
+        }
+        DiscountType::Percent | DiscountType::FixedProduct => {
+            let eligible: Vec<&CartLine> = included
+                .into_iter()
+                .filter(|line| line.quantity > 0 && !is_excluded(coupon, line))
+                .filter(|line| !(coupon.exclude_sale_items && line.on_sale))
+                .collect();
+            if eligible.is_empty() {
+                return Err(Rejection::NotApplicableToProducts);
+            }
+            eligible
+        }
+    };
+
+    if !coupon.email_restrictions.is_empty() {
+        let Some(email) = cart.customer_email.as_deref() else {
+            return Err(Rejection::EmailRequired);
+        };
+        let allowed = coupon
+            .email_restrictions
+            .iter()
+            .any(|pattern| email_matches(pattern, email));
+        if !allowed {
+            return Err(Rejection::EmailNotAllowed);
+        }
+    }
+
+    let lines = discount_lines(coupon, cart, eligible)?;
+    let total = sum(lines.iter().map(|line| Some(line.amount)))?;
+    Ok(Discount {
+        total,
+        lines,
+        free_shipping: coupon.free_shipping,
+    })
+}
+
+fn discount_lines(
+    coupon: &Coupon,
+    cart: &Cart,
+    mut eligible: Vec<&CartLine>,
+) -> Result<Vec<LineDiscount>, Rejection> {
+    let amount = coupon.amount;
+    let line_discount = |line: &CartLine, amount: Money| LineDiscount {
+        product_id: line.product_id,
+        variation_id: line.variation_id,
+        amount: round_for(amount, &cart.currency),
+    };
+
+    if coupon.discount_type == DiscountType::FixedCart {
+        let subtotal = sum(eligible.iter().map(|line| line.subtotal()))?;
+        if subtotal.is_zero() {
+            return Ok(Vec::new());
+        }
+        let total = round_for(amount.min(subtotal), &cart.currency);
+        // Spread by line subtotal, the last line takes the rounding remainder.
+        let mut lines = Vec::new();
+        let mut remaining = total;
+        for (index, line) in eligible.iter().enumerate() {
+            let share = if index + 1 == eligible.len() {
+                remaining
+            } else {
+                let line_subtotal = line.subtotal().ok_or(Rejection::AmountOverflow)?;
+                let share = line_subtotal
+                    .checked_mul(total)
+                    .and_then(|value| value.checked_div(subtotal))
+                    .ok_or(Rejection::AmountOverflow)?;
+                round_for(share, &cart.currency)
+            };
+            remaining = remaining
+                .checked_sub(share)
+                .ok_or(Rejection::AmountOverflow)?;
+            lines.push(line_discount(line, share));
+        }
+        return Ok(lines);
+    }
+
+    // Item limits go to the most expensive items first, like WooCommerce.
+    eligible.sort_by_key(|line| std::cmp::Reverse(line.price));
+    let mut items_left = coupon
+        .limit_usage_to_x_items
+        .filter(|limit| *limit > 0)
+        .map(|limit| limit as u32)
+        .unwrap_or(u32::MAX);
+
+    let mut lines = Vec::new();
+    for line in eligible {
+        let quantity = line.quantity.min(items_left);
+        if quantity == 0 {
+            break;
+        }
+        items_left -= quantity;
+        let per_item = match coupon.discount_type {
+            DiscountType::Percent => line
+                .price
+                .checked_mul(amount.min(Money::from(100)))
+                .and_then(|value| value.checked_div(Money::from(100)))
+                .ok_or(Rejection::AmountOverflow)?,
+            _ => amount.min(line.price),
+        };
+        let discount = per_item
+            .checked_mul(Money::from(quantity))
+            .ok_or(Rejection::AmountOverflow)?;
+        lines.push(line_discount(line, discount));
+    }
+    Ok(lines)
+}
+
+fn is_included(coupon: &Coupon, line: &CartLine) -> bool {
+    let restricted = !coupon.product_ids.is_empty() || !coupon.product_categories.is_empty();
+    !restricted
+        || coupon.product_ids.contains(&line.product_id)
+        || line
+            .variation_id
+            .is_some_and(|id| coupon.product_ids.contains(&ProductId(id.0)))
+        || line
+            .category_ids
+            .iter()
+            .any(|id| coupon.product_categories.contains(id))
+}
+
+fn is_excluded(coupon: &Coupon, line: &CartLine) -> bool {
+    coupon.excluded_product_ids.contains(&line.product_id)
+        || line
+            .variation_id
+            .is_some_and(|id| coupon.excluded_product_ids.contains(&ProductId(id.0)))
+        || line
+            .category_ids
+            .iter()
+            .any(|id| coupon.excluded_product_categories.contains(id))
+}
+
+/// `used_by` holds user ids for registered customers and emails for guests.
+fn uses_by_customer(coupon: &Coupon, cart: &Cart) -> usize {
+    let id = cart.customer_id.map(|id| id.to_string());
+    let email = cart.customer_email.as_deref().map(normalize_coupon_code);
+    coupon
+        .used_by
+        .iter()
+        .filter(|used_by| {
+            Some(used_by.as_str()) == id.as_deref() || Some(normalize_coupon_code(used_by)) == email
+        })
+        .count()
+}
+
+/// Case-insensitive, `*` matches any run of characters.
+fn email_matches(pattern: &str, email: &str) -> bool {
+    let pattern = pattern.trim().to_lowercase();
+    let email = email.trim().to_lowercase();
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return pattern == email;
+    }
+
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if email.len() < first.len() + last.len() || !email.starts_with(first) || !email.ends_with(last)
+    {
+        return false;
+    }
+    // Both ends matched, so both indices fall on char boundaries.
+    let mut rest = &email[first.len()..email.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(position) => rest = &rest[position + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+fn product_ids<'a>(lines: impl Iterator<Item = &'a CartLine>) -> Vec<ProductId> {
+    let mut ids: Vec<ProductId> = lines.map(|line| line.product_id).collect();
+    ids.dedup();
+    ids
+}
+
+fn sum(mut amounts: impl Iterator<Item = Option<Money>>) -> Result<Money, Rejection> {
+    amounts.try_fold(Money::ZERO, |total, amount| {
+        amount
+            .and_then(|amount| total.checked_add(amount))
+            .ok_or(Rejection::AmountOverflow)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coupon() -> Coupon {
+        let mut coupon: Coupon =
+            serde_json::from_str(include_str!("../../fixtures/coupon.json")).unwrap();
+        coupon.individual_use = false;
+        coupon.exclude_sale_items = false;
+        coupon.minimum_amount = None;
+        coupon
+    }
+
+    fn money(value: &str) -> Money {
+        value.parse().unwrap()
+    }
+
+    fn cart() -> Cart {
+        Cart::new("USD", "2024-01-01T00:00:00".parse().unwrap())
+            .line(CartLine::new(ProductId(10), 2, money("30.00")).categories(&[5]))
+            .line(CartLine::new(ProductId(11), 1, money("15.50")).on_sale(true))
+            .customer(None, "Trader@Example.com")
+    }
+
+    #[test]
+    fn test_discount_per_type() {
+        let mut coupon = coupon();
+        coupon.discount_type = DiscountType::Percent;
+        coupon.amount = money("10");
+        let discount = evaluate(&coupon, &cart()).unwrap();
+        assert_eq!(discount.total, money("7.55"));
+
+        coupon.limit_usage_to_x_items = Some(1);
+        assert_eq!(evaluate(&coupon, &cart()).unwrap().total, money("3.00"));
+
+        coupon.discount_type = DiscountType::FixedProduct;
+        coupon.amount = money("20");
+        coupon.limit_usage_to_x_items = None;
+        assert_eq!(evaluate(&coupon, &cart()).unwrap().total, money("55.50"));
+
+        coupon.discount_type = DiscountType::FixedCart;
+        coupon.amount = money("10");
+        let discount = evaluate(&coupon, &cart()).unwrap();
+        assert_eq!(discount.total, money("10.00"));
+        assert_eq!(discount.lines[0].amount, money("7.95"));
+        assert_eq!(discount.lines[1].amount, money("2.05"));
+    }
+
+    #[test]
+    fn test_email_patterns() {
+        assert!(email_matches("*@example.com", "Ana@Example.com"));
+        assert!(email_matches("j*@*.org", "jörg@verein.org"));
+        assert!(!email_matches("*a", "bé"));
+        assert!(!email_matches("é*", "eé"));
+        assert!(email_matches("*é", "josé"));
+        assert!(!email_matches("a*b*c", "abc-b"));
+    }
+
+    #[test]
+    fn test_rejections() {
+        let mut expired = coupon();
+        expired.date_expires_gmt = Some("2023-12-31T23:59:59".parse().unwrap());
+        assert!(matches!(
+            evaluate(&expired, &cart()),
+            Err(Rejection::Expired { .. })
+        ));
+
+        let mut coupon = coupon();
+
+        coupon.usage_limit_per_user = Some(1);
+        coupon.used_by = vec!["trader@example.com".to_string()];
+        assert_eq!(
+            evaluate(&coupon, &cart()),
+            Err(Rejection::UserUsageLimitReached { limit: 1 })
+        );
+        coupon.used_by.clear();
+
+        coupon.minimum_amount = Some(money("100"));
+        assert!(matches!(
+            evaluate(&coupon, &cart()),
+            Err(Rejection::MinimumSpendNotMet { .. })
+        ));
+        coupon.minimum_amount = None;
+
+        coupon.discount_type = DiscountType::FixedCart;
+        coupon.exclude_sale_items = true;
+        assert_eq!(
+            evaluate(&coupon, &cart()),
+            Err(Rejection::SaleItemsInCart {
+                product_ids: vec![ProductId(11)]
+            })
+        );
+        coupon.exclude_sale_items = false;
+
+        coupon.product_categories = vec![7];
+        assert_eq!(
+            evaluate(&coupon, &cart()),
+            Err(Rejection::NotApplicableToProducts)
+        );
+        coupon.product_categories.clear();
+
+        coupon.email_restrictions = vec!["*@firm.com".to_string()];
+        assert_eq!(evaluate(&coupon, &cart()), Err(Rejection::EmailNotAllowed));
+        coupon.email_restrictions = vec!["*@EXAMPLE.com".to_string()];
+        assert!(evaluate(&coupon, &cart()).is_ok());
+
+        coupon.individual_use = true;
+        assert!(matches!(
+            evaluate(&coupon, &cart().applied_coupon("other")),
+            Err(Rejection::IndividualUse { .. })
+        ));
+    }
+}
@@ -0,0 +1,546 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use futures::stream::{self, StreamExt};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    normalize_coupon_code, BatchItem, BatchRequest, BuilderError, CouponClient, CouponId,
+    CouponQuery, CreateCoupon, HasMetaData, NewMetaData, OrderBy, SortOrder, WooCommerceHttpError,
+};
+
+/// Upper case letters and digits without `0`/`O` and `1`/`I`/`L`.
+pub const UNAMBIGUOUS_ALPHABET: &str = "ABCDEFGHJKMNPQRSTUVWXYZ23456789";
+
+/// Meta key holding the campaign on every issued coupon, used to recognise them on reruns.
+pub const ISSUANCE_META_KEY: &str = "_issuance_campaign";
+
+/// WooCommerce rejects batches with more than 100 items.
+const MAX_BATCH_SIZE: usize = 100;
+
+/// Shape of generated codes: `prefix` followed by `length` random characters and an
+/// optional Luhn mod N check character over them.
+///
+/// WooCommerce lowercases codes, so the alphabet must not rely on case.
+#[derive(Debug, Clone)]
+pub struct CodePattern {
+    pub prefix: String,
+    pub alphabet: Vec<char>,
+    pub length: usize,
+    pub check_digit: bool,
+}
+
+impl CodePattern {
+    pub fn new(length: usize) -> Self {
+        CodePattern {
+            prefix: String::new(),
+            alphabet: UNAMBIGUOUS_ALPHABET.chars().collect(),
+            length,
+            check_digit: false,
+        }
+    }
+
+    pub fn prefix(mut self, prefix: &str) -> Self {
+        self.prefix = prefix.to_string();
+        self
+    }
+
+    pub fn alphabet(mut self, alphabet: &str) -> Self {
+        self.alphabet = alphabet.chars().collect();
+        self
+    }
+
+    pub fn check_digit(mut self, check_digit: bool) -> Self {
+        self.check_digit = check_digit;
+        self
+    }
+
+    pub fn validate(&self) -> Result<(), BuilderError> {
+        if self.length == 0 {
+            return Err(BuilderError::invalid("length", "must be positive"));
+        }
+        if self.alphabet.len() < 2 {
+            return Err(BuilderError::invalid(
+                "alphabet",
+                "needs at least two characters",
+            ));
+        }
+        let mut seen = HashSet::new();
+        for c in &self.alphabet {
+            if !c.is_ascii_alphanumeric() {
+                return Err(BuilderError::invalid(
+                    "alphabet",
+                    format!("{:?} is not allowed", c),
+                ));
+            }
+            if !seen.insert(c.to_ascii_lowercase()) {
+                return Err(BuilderError::invalid(
+                    "alphabet",
+                    format!("{:?} appears twice, codes are case-insensitive", c),
+                ));
+            }
+        }
+        if let Some(c) = self
+            .prefix
+            .chars()
+            .find(|c| !c.is_ascii_alphanumeric() && *c != '-' && *c != '_')
+        {
+            return Err(BuilderError::invalid(
+                "prefix",
+                format!("{:?} is not allowed", c),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Number of distinct codes, saturating at `u64::MAX`.
+    pub fn capacity(&self) -> u64 {
+        (self.alphabet.len() as u64).saturating_pow(self.length.min(u32::MAX as usize) as u32)
+    }
+
+    pub fn generate<R: Rng>(&self, rng: &mut R) -> String {
+        let body: String = (0..self.length)
+            .map(|_| self.alphabet[rng.gen_range(0..self.alphabet.len())])
+            .collect();
+        let mut code = format!("{}{}", self.prefix, body);
+        if self.check_digit {
+            code.push(self.check_char(&body));
+        }
+        code
+    }
+
+    /// Checks prefix, length, alphabet and check character, ignoring case.
+    pub fn is_valid(&self, code: &str) -> bool {
+        let code = code.trim();
+        if code.len() < self.prefix.len()
+            || !code.is_char_boundary(self.prefix.len())
+            || !code[..self.prefix.len()].eq_ignore_ascii_case(&self.prefix)
+        {
+            return false;
+        }
+        let body = &code[self.prefix.len()..];
+        let expected = self.length + usize::from(self.check_digit);
+        if body.chars().count() != expected || body.chars().any(|c| self.index_of(c).is_none()) {
+            return false;
+        }
+        if !self.check_digit {
+            return true;
+        }
+        let Some((split, check)) = body.char_indices().last() else {
+            return false;
+        };
+        self.index_of(check) == self.index_of(self.check_char(&body[..split]))
+    }
+
+    fn index_of(&self, c: char) -> Option<usize> {
+        self.alphabet
+            .iter()
+            .position(|candidate| candidate.eq_ignore_ascii_case(&c))
+    }
+
+    /// Luhn mod N, catches every single character typo and most adjacent swaps.
+    fn check_char(&self, body: &str) -> char {
+        let n = self.alphabet.len();
+        let mut factor = 2;
+        let mut sum = 0;
+        for c in body.chars().rev() {
+            let addend = factor * self.index_of(c).unwrap_or_default();
+            sum += addend / n + addend % n;
+            factor = if factor == 2 { 1 } else { 2 };
+        }
+        self.alphabet[(n - sum % n) % n]
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum IssueStatus {
+    Pending,
+    Issued { id: CouponId },
+    Failed { error: String },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct IssuedCode {
+    pub code: String,
+    #[serde(flatten)]
+    pub status: IssueStatus,
+}
+
+/// Outcome of an issuance run. Saved after every batch, so a rerun with the same file
+/// only retries what is still pending or failed.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct IssuanceManifest {
+    pub campaign: String,
+    pub entries: Vec<IssuedCode>,
+}
+
+impl IssuanceManifest {
+    pub fn new(campaign: &str) -> Self {
+        IssuanceManifest {
+            campaign: campaign.to_string(),
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let content = std::fs::read(path)?;
+        serde_json::from_slice(&content).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        let content = serde_json::to_vec_pretty(self)?;
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, content)?;
+        std::fs::rename(tmp, path)
+    }
+
+    pub fn issued(&self) -> impl Iterator<Item = (&str, CouponId)> {
+        self.entries.iter().filter_map(|entry| match entry.status {
+            IssueStatus::Issued { id } => Some((entry.code.as_str(), id)),
+            _ => None,
+        })
+    }
+
+    pub fn failed(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries.iter().filter_map(|entry| match &entry.status {
+            IssueStatus::Failed { error } => Some((entry.code.as_str(), error.as_str())),
+            _ => None,
+        })
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.entries
+            .iter()
+            .all(|entry| matches!(entry.status, IssueStatus::Issued { .. }))
+    }
+}
+
+#[derive(Debug)]
+pub enum IssuanceError {
+    Pattern(BuilderError),
+    /// The pattern can't produce enough unused codes.
+    PatternExhausted,
+    /// The manifest file belongs to another campaign.
+    CampaignMismatch {
+        expected: String,
+        found: String,
+    },
+    Http(WooCommerceHttpError),
+    Io(io::Error),
+}
+
+impl fmt::Display for IssuanceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IssuanceError::Pattern(err) => write!(f, "invalid code pattern: {}", err),
+            IssuanceError::PatternExhausted => write!(f, "code pattern has too few unused codes"),
+            IssuanceError::CampaignMismatch { expected, found } => write!(
+                f,
+                "manifest is for campaign {:?}, not {:?}",
+                found, expected
+            ),
+            IssuanceError::Http(err) => write!(f, "request failed: {:?}", err),
+            IssuanceError::Io(err) => write!(f, "manifest: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for IssuanceError {}
+
+impl From<BuilderError> for IssuanceError {
+    fn from(err: BuilderError) -> Self {
+        IssuanceError::Pattern(err)
+    }
+}
+
+impl From<WooCommerceHttpError> for IssuanceError {
+    fn from(err: WooCommerceHttpError) -> Self {
+        IssuanceError::Http(err)
+    }
+}
+
+impl From<io::Error> for IssuanceError {
+    fn from(err: io::Error) -> Self {
+        IssuanceError::Io(err)
+    }
+}
+
+/// Creates single-use coupons with generated codes through the batch endpoint.
+///
+/// Every coupon is a copy of `template` with its own code and [`ISSUANCE_META_KEY`] set
+/// to the campaign. Before issuing, codes are checked against the store: a code taken by
+/// a coupon of the same campaign counts as issued (the response of an earlier run was
+/// lost), any other taken code is replaced.
+pub struct CouponIssuer<'a, C> {
+    client: &'a C,
+    pattern: CodePattern,
+    template: CreateCoupon,
+    batch_size: usize,
+    concurrency: usize,
+    manifest_path: Option<PathBuf>,
+}
+
+impl<'a, C: CouponClient> CouponIssuer<'a, C> {
+    pub fn new(client: &'a C, pattern: CodePattern, template: CreateCoupon) -> Self {
+        CouponIssuer {
+            client,
+            pattern,
+            template,
+            batch_size: MAX_BATCH_SIZE,
+            concurrency: 4,
+            manifest_path: None,
+        }
+    }
+
+    /// Coupons per batch request, at most 100.
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.clamp(1, MAX_BATCH_SIZE);
+        self
+    }
+
+    /// Batch requests in flight at the same time.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Resumes from this file if it exists and keeps it up to date.
+    pub fn manifest_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.manifest_path = Some(path.into());
+        self
+    }
+
+    /// Makes sure `count` coupons exist for `campaign`. Per coupon failures end up in
+    /// the manifest, only setup errors are returned as `Err`.
+    pub async fn issue(
+        &self,
+        campaign: &str,
+        count: usize,
+    ) -> Result<IssuanceManifest, IssuanceError> {
+        self.pattern.validate()?;
+        let mut manifest = match &self.manifest_path {
+            Some(path) if path.exists() => IssuanceManifest::load(path)?,
+            _ => IssuanceManifest::new(campaign),
+        };
+        if manifest.campaign != campaign {
+            return Err(IssuanceError::CampaignMismatch {
+                expected: campaign.to_string(),
+                found: manifest.campaign,
+            });
+        }
+
+        let existing = self.existing_codes(campaign).await?;
+        let mut taken: HashSet<String> = existing.keys().cloned().collect();
+        taken.extend(
+            manifest
+                .entries
+                .iter()
+                .map(|entry| normalize_coupon_code(&entry.code)),
+        );
+        let unused = self.pattern.capacity().saturating_sub(taken.len() as u64);
+        let needed = count.saturating_sub(manifest.entries.len()) as u64;
+        // Leave room so random draws keep finding free codes quickly.
+        if needed.saturating_mul(2) > unused {
+            return Err(IssuanceError::PatternExhausted);
+        }
+
+        let mut rng = rand::thread_rng();
+        let mut fresh_code = |taken: &mut HashSet<String>| loop {
+            let code = self.pattern.generate(&mut rng);
+            if taken.insert(normalize_coupon_code(&code)) {
+                return code;
+            }
+        };
+
+        for entry in manifest.entries.iter_mut() {
+            if matches!(entry.status, IssueStatus::Issued { .. }) {
+                continue;
+            }
+            match existing.get(&normalize_coupon_code(&entry.code)) {
+                Some((id, true)) => entry.status = IssueStatus::Issued { id: *id },
+                Some((_, false)) => {
+                    entry.code = fresh_code(&mut taken);
+                    entry.status = IssueStatus::Pending;
+                }
+                None => entry.status = IssueStatus::Pending,
+            }
+        }
+        while manifest.entries.len() < count {
+            manifest.entries.push(IssuedCode {
+                code: fresh_code(&mut taken),
+                status: IssueStatus::Pending,
+            });
+        }
+        self.save(&manifest)?;
+
+        let pending: Vec<(usize, String)> = manifest
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.status == IssueStatus::Pending)
+            .map(|(index, entry)| (index, entry.code.clone()))
+            .collect();
+        let batches: Vec<Vec<(usize, String)>> = pending
+            .chunks(self.batch_size)
+            .map(|chunk| chunk.to_vec())
+            .collect();
+
+        let mut responses = stream::iter(batches)
+            .map(|batch| async move {
+                let request = BatchRequest {
+                    create: batch
+                        .iter()
+                        .map(|(_, code)| self.coupon(campaign, code))
+                        .collect(),
+                    ..Default::default()
+                };
+                let response = self.client.batch_coupons(&request).await;
+                (batch, response)
+            })
+            .buffer_unordered(self.concurrency);
+
+        while let Some((batch, response)) = responses.next().await {
+            match response {
+                Ok(response) => {
+                    let mut items = response.create.into_iter();
+                    for (index, _) in batch {
+                        manifest.entries[index].status = match items.next() {
+                            Some(BatchItem::Ok(coupon)) => IssueStatus::Issued { id: coupon.id },
+                            Some(BatchItem::Err(err)) => IssueStatus::Failed {
+                                error: format!("{}: {}", err.error.code, err.error.message),
+                            },
+                            None => IssueStatus::Failed {
+                                error: "missing from the batch response".to_string(),
+                            },
+                        };
+                    }
+                }
+                Err(err) => {
+                    let error = describe(&err);
+                    for (index, _) in batch {
+                        manifest.entries[index].status = IssueStatus::Failed {
+                            error: error.clone(),
+                        };
+                    }
+                }
+            }
+            self.save(&manifest)?;
+        }
+
+        Ok(manifest)
+    }
+
+    fn coupon(&self, campaign: &str, code: &str) -> CreateCoupon {
+        let mut coupon = self.template.clone();
+        coupon.code = code.to_string();
+        coupon
+            .meta_data
+            .retain(|meta| meta.key != ISSUANCE_META_KEY);
+        coupon
+            .meta_data
+            .push(NewMetaData::new(ISSUANCE_META_KEY, campaign));
+        coupon
+    }
+
+    /// Every code in the store, with the coupon id and whether it belongs to `campaign`.
+    async fn existing_codes(
+        &self,
+        campaign: &str,
+    ) -> Result<HashMap<String, (CouponId, bool)>, WooCommerceHttpError> {
+        let mut codes = HashMap::new();
+        let mut page = 1;
+        loop {
+            let query = CouponQuery::new()
+                .page(page, MAX_BATCH_SIZE)
+                .order_by(OrderBy::Id, SortOrder::Asc);
+            let coupons = self.client.list_coupons(&query).await?;
+            let last_page = coupons.len() < MAX_BATCH_SIZE;
+            for coupon in coupons {
+                let ours = coupon.get_meta::<String>(ISSUANCE_META_KEY).ok().flatten()
+                    == Some(campaign.to_string());
+                codes.insert(normalize_coupon_code(&coupon.code), (coupon.id, ours));
+            }
+            if last_page {
+                return Ok(codes);
+            }
+            page += 1;
+        }
+    }
+
+    fn save(&self, manifest: &IssuanceManifest) -> io::Result<()> {
+        match &self.manifest_path {
+            Some(path) => manifest.save(path),
+            None => Ok(()),
+        }
+    }
+}
+
+fn describe(err: &WooCommerceHttpError) -> String {
+    match err {
+        WooCommerceHttpError::ErrorResponse(err) => format!("{}: {}", err.code, err.message),
+        WooCommerceHttpError::SerdeError(err) => err.to_string(),
+        WooCommerceHttpError::InvalidStatusTransition { from, to, .. } => {
+            format!("invalid status transition from {} to {}", from, to)
+        }
+        WooCommerceHttpError::NoteFailed { error, .. } => {
+            format!("order updated, adding the note failed: {}", describe(error))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    #[test]
+    fn test_generated_codes_follow_pattern() {
+        let pattern = CodePattern::new(8).prefix("SUMMER-").check_digit(true);
+        pattern.validate().unwrap();
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..200 {
+            let code = pattern.generate(&mut rng);
+            assert_eq!(code.len(), "SUMMER-".len() + 9);
+            assert!(pattern.is_valid(&code), "{}", code);
+            // WooCommerce hands codes back lowercased.
+            assert!(pattern.is_valid(&code.to_lowercase()));
+            assert!(!code.contains(['0', 'O', '1', 'I']));
+        }
+    }
+
+    #[test]
+    fn test_check_digit_catches_typos() {
+        let pattern = CodePattern::new(6).check_digit(true);
+        let code = pattern.generate(&mut StdRng::seed_from_u64(1));
+        let mut chars: Vec<char> = code.chars().collect();
+        let replacement = pattern.alphabet.iter().find(|c| **c != chars[2]).unwrap();
+        chars[2] = *replacement;
+        assert!(!pattern.is_valid(&chars.iter().collect::<String>()));
+        assert!(!pattern.is_valid(&code[..code.len() - 1]));
+    }
+
+    #[test]
+    fn test_pattern_validation() {
+        assert!(CodePattern::new(0).validate().is_err());
+        assert!(CodePattern::new(6).alphabet("aA").validate().is_err());
+        assert!(CodePattern::new(6).prefix("SALE ").validate().is_err());
+        assert_eq!(CodePattern::new(3).alphabet("AB").capacity(), 8);
+
+        // Not a valid pattern, but checking a code against it must not panic.
+        let pattern = CodePattern::new(2).alphabet("äöü").check_digit(true);
+        assert!(pattern.validate().is_err());
+        let code = pattern.generate(&mut StdRng::seed_from_u64(3));
+        assert!(pattern.is_valid(&code));
+        assert!(!pattern.is_valid("ääx"));
+    }
+}
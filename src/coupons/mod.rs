@@ -0,0 +1,10 @@
+mod generator;
+pub use generator::*;
+#[cfg(feature = "rust_decimal")]
+mod eligibility;
+#[cfg(feature = "rust_decimal")]
+pub use eligibility::*;
+#[cfg(feature = "rust_decimal")]
+mod analytics;
+#[cfg(feature = "rust_decimal")]
+pub use analytics::*;
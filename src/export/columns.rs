@@ -0,0 +1,209 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde_json::Value;
+
+use crate::HasMetaData;
+
+/// Joins the values of an array field that isn't exploded into rows.
+pub const LIST_SEPARATOR: &str = "|";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Source {
+    /// Dotted path into the record's JSON, e.g. `billing.email` or `line_items.sku`.
+    Field(Vec<String>),
+    Meta(String),
+}
+
+/// One exported column.
+///
+/// Parsed from `path`, `meta:<key>`, or either followed by `=<header>`, e.g.
+/// `billing.email`, `line_items.sku=SKU` or `meta:_affiliate_id=affiliate`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Column {
+    pub header: String,
+    source: Source,
+}
+
+impl Column {
+    pub fn field(path: &str) -> Self {
+        Column {
+            header: path.to_string(),
+            source: Source::Field(path.split('.').map(str::to_string).collect()),
+        }
+    }
+
+    /// The record's `meta_data` value for `key`.
+    pub fn meta(key: &str) -> Self {
+        Column {
+            header: key.to_string(),
+            source: Source::Meta(key.to_string()),
+        }
+    }
+
+    pub fn header(mut self, header: &str) -> Self {
+        self.header = header.to_string();
+        self
+    }
+
+    /// The path below `array` if this column reads from that array's elements.
+    pub(crate) fn element_path(&self, array: &str) -> Option<&[String]> {
+        match &self.source {
+            Source::Field(path) if path.len() > 1 && path[0] == array => Some(&path[1..]),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn value<T: HasMetaData>(&self, record: &T, json: &Value) -> Value {
+        match &self.source {
+            Source::Field(path) => lookup(json, path),
+            Source::Meta(key) => record.get_meta_value(key).cloned().unwrap_or(Value::Null),
+        }
+    }
+}
+
+impl FromStr for Column {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (spec, header) = match s.split_once('=') {
+            Some((spec, header)) => (spec.trim(), Some(header.trim())),
+            None => (s.trim(), None),
+        };
+        let column = match spec.strip_prefix("meta:") {
+            Some(key) if !key.is_empty() => Column::meta(key),
+            Some(_) => return Err(format!("missing meta key in column {:?}", s)),
+            None if spec.is_empty() || spec.split('.').any(str::is_empty) => {
+                return Err(format!("invalid column {:?}", s))
+            }
+            None => Column::field(spec),
+        };
+        Ok(match header {
+            Some(header) if !header.is_empty() => column.header(header),
+            _ => column,
+        })
+    }
+}
+
+impl fmt::Display for Column {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.source {
+            Source::Field(path) => write!(f, "{}", path.join("."))?,
+            Source::Meta(key) => write!(f, "meta:{}", key)?,
+        }
+        write!(f, "={}", self.header)
+    }
+}
+
+/// Reads `path` from `value`. Crossing an array collects the path from every element,
+/// `null` included, so lists read from the same array line up.
+pub(crate) fn lookup(value: &Value, path: &[String]) -> Value {
+    let Some((first, rest)) = path.split_first() else {
+        return value.clone();
+    };
+    match value {
+        Value::Object(map) => map
+            .get(first)
+            .map(|value| lookup(value, rest))
+            .unwrap_or(Value::Null),
+        Value::Array(items) => Value::Array(items.iter().map(|item| lookup(item, path)).collect()),
+        _ => Value::Null,
+    }
+}
+
+/// Text of a CSV cell. Lists of scalars are joined with [`LIST_SEPARATOR`], anything
+/// else nested is written as JSON.
+pub(crate) fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        Value::Bool(_) | Value::Number(_) => value.to_string(),
+        Value::Array(items)
+            if items
+                .iter()
+                .all(|item| !item.is_object() && !item.is_array()) =>
+        {
+            items
+                .iter()
+                .map(cell)
+                .collect::<Vec<_>>()
+                .join(LIST_SEPARATOR)
+        }
+        _ => value.to_string(),
+    }
+}
+
+fn columns(specs: &[&str]) -> Vec<Column> {
+    specs.iter().map(|spec| Column::field(spec)).collect()
+}
+
+/// Order columns for accounting, one row per order.
+pub fn default_order_columns() -> Vec<Column> {
+    columns(&[
+        "id",
+        "number",
+        "status",
+        "currency",
+        "date_created_gmt",
+        "date_paid_gmt",
+        "customer_id",
+        "billing.email",
+        "billing.country",
+        "payment_method",
+        "transaction_id",
+        "discount_total",
+        "shipping_total",
+        "total_tax",
+        "total",
+        "coupon_lines.code",
+    ])
+}
+
+pub fn default_line_item_columns() -> Vec<Column> {
+    columns(&[
+        "id",
+        "number",
+        "status",
+        "currency",
+        "date_created_gmt",
+        "line_items.id",
+        "line_items.product_id",
+        "line_items.variation_id",
+        "line_items.sku",
+        "line_items.name",
+        "line_items.quantity",
+        "line_items.subtotal",
+        "line_items.total",
+        "line_items.total_tax",
+    ])
+}
+
+pub fn default_product_columns() -> Vec<Column> {
+    columns(&[
+        "id",
+        "sku",
+        "name",
+        "type",
+        "status",
+        "price",
+        "regular_price",
+        "sale_price",
+        "stock_status",
+        "stock_quantity",
+        "categories.name",
+        "date_modified_gmt",
+    ])
+}
+
+pub fn default_coupon_columns() -> Vec<Column> {
+    columns(&[
+        "id",
+        "code",
+        "discount_type",
+        "amount",
+        "usage_count",
+        "usage_limit",
+        "date_expires_gmt",
+        "date_modified_gmt",
+    ])
+}
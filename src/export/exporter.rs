@@ -0,0 +1,407 @@
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::{
+    CouponClient, CouponQuery, HasMetaData, OrderBy, OrderClient, OrderQuery, ProductClient,
+    ProductQuery, SortOrder, WooCommerceHttpError,
+};
+
+use super::columns::{cell, lookup, Column};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    /// One JSON object per line.
+    NdJson,
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "csv" => Ok(ExportFormat::Csv),
+            "ndjson" | "jsonl" => Ok(ExportFormat::NdJson),
+            _ => Err(format!("unknown export format {:?}", s)),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ExportError {
+    Io(io::Error),
+    Csv(csv::Error),
+    Json(serde_json::Error),
+    Http(WooCommerceHttpError),
+    /// CSV needs at least one column.
+    NoColumns,
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportError::Io(err) => write!(f, "write failed: {}", err),
+            ExportError::Csv(err) => write!(f, "csv: {}", err),
+            ExportError::Json(err) => write!(f, "json: {}", err),
+            ExportError::Http(err) => write!(f, "request failed: {:?}", err),
+            ExportError::NoColumns => write!(f, "no columns selected"),
+        }
+    }
+}
+
+impl std::error::Error for ExportError {}
+
+impl From<io::Error> for ExportError {
+    fn from(err: io::Error) -> Self {
+        ExportError::Io(err)
+    }
+}
+
+impl From<csv::Error> for ExportError {
+    fn from(err: csv::Error) -> Self {
+        ExportError::Csv(err)
+    }
+}
+
+impl From<serde_json::Error> for ExportError {
+    fn from(err: serde_json::Error) -> Self {
+        ExportError::Json(err)
+    }
+}
+
+impl From<WooCommerceHttpError> for ExportError {
+    fn from(err: WooCommerceHttpError) -> Self {
+        ExportError::Http(err)
+    }
+}
+
+enum Output<W: Write> {
+    Csv(Box<csv::Writer<W>>),
+    NdJson(W),
+}
+
+/// Writes records as rows as they come, nothing is buffered beyond the writer.
+///
+/// NDJSON without columns writes each record whole.
+pub struct Exporter<W: Write> {
+    output: Output<W>,
+    columns: Vec<Column>,
+    explode: Option<String>,
+    rows: usize,
+}
+
+impl<W: Write> Exporter<W> {
+    pub fn new(writer: W, format: ExportFormat, columns: Vec<Column>) -> Result<Self, ExportError> {
+        let output = match format {
+            ExportFormat::Csv => {
+                if columns.is_empty() {
+                    return Err(ExportError::NoColumns);
+                }
+                let mut writer = csv::Writer::from_writer(writer);
+                writer.write_record(columns.iter().map(|column| column.header.as_str()))?;
+                Output::Csv(Box::new(writer))
+            }
+            ExportFormat::NdJson => Output::NdJson(writer),
+        };
+        Ok(Exporter {
+            output,
+            columns,
+            explode: None,
+            rows: 0,
+        })
+    }
+
+    /// One row per element of `array` (e.g. `line_items`) instead of one per record.
+    /// Columns under that array read from the element, the others repeat the record's
+    /// values. Records with an empty array still get one row.
+    pub fn explode(mut self, array: &str) -> Self {
+        self.explode = Some(array.to_string());
+        self
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn write<T: Serialize + HasMetaData>(&mut self, record: &T) -> Result<(), ExportError> {
+        let json = serde_json::to_value(record)?;
+        if self.columns.is_empty() {
+            return self.write_row(Row::Whole(&json));
+        }
+
+        let Some(array) = self.explode.clone() else {
+            let values = self
+                .columns
+                .iter()
+                .map(|column| column.value(record, &json))
+                .collect();
+            return self.write_row(Row::Values(values));
+        };
+
+        let elements = match json.get(&array) {
+            Some(Value::Array(elements)) if !elements.is_empty() => elements.as_slice(),
+            _ => &[Value::Null],
+        };
+        for element in elements {
+            let values = self
+                .columns
+                .iter()
+                .map(|column| match column.element_path(&array) {
+                    Some(path) => lookup(element, path),
+                    None => column.value(record, &json),
+                })
+                .collect();
+            self.write_row(Row::Values(values))?;
+        }
+        Ok(())
+    }
+
+    fn write_row(&mut self, row: Row) -> Result<(), ExportError> {
+        match (&mut self.output, row) {
+            (Output::Csv(writer), Row::Values(values)) => {
+                writer.write_record(values.iter().map(cell))?;
+            }
+            (Output::Csv(_), Row::Whole(_)) => return Err(ExportError::NoColumns),
+            (Output::NdJson(writer), Row::Whole(json)) => {
+                serde_json::to_writer(&mut *writer, json)?;
+                writer.write_all(b"\n")?;
+            }
+            (Output::NdJson(writer), Row::Values(values)) => {
+                let object: Map<String, Value> = self
+                    .columns
+                    .iter()
+                    .map(|column| column.header.clone())
+                    .zip(values)
+                    .collect();
+                serde_json::to_writer(&mut *writer, &object)?;
+                writer.write_all(b"\n")?;
+            }
+        }
+        self.rows += 1;
+        Ok(())
+    }
+
+    /// Flushes and hands back the writer.
+    pub fn finish(self) -> Result<W, ExportError> {
+        match self.output {
+            Output::Csv(writer) => writer
+                .into_inner()
+                .map_err(|err| ExportError::Io(err.into_error())),
+            Output::NdJson(mut writer) => {
+                writer.flush()?;
+                Ok(writer)
+            }
+        }
+    }
+}
+
+enum Row<'a> {
+    Whole(&'a Value),
+    Values(Vec<Value>),
+}
+
+const PAGE_SIZE: usize = 100;
+
+/// Streams every order matching `query` (its paging and sorting are ignored), one page in
+/// memory at a time. Returns the number of orders exported.
+pub async fn export_orders<W: Write>(
+    client: &impl OrderClient,
+    query: &OrderQuery,
+    exporter: &mut Exporter<W>,
+) -> Result<usize, ExportError> {
+    let mut exported = 0;
+    let mut page = 1;
+    loop {
+        // Oldest first, so orders placed while paging end up on later pages instead of
+        // shifting rows that were not exported yet.
+        let query = query
+            .clone()
+            .page(page, PAGE_SIZE)
+            .order_by(OrderBy::Id, SortOrder::Asc);
+        let orders = client.list_orders(&query).await?;
+        for order in &orders {
+            exporter.write(order)?;
+        }
+        exported += orders.len();
+        if orders.len() < PAGE_SIZE {
+            return Ok(exported);
+        }
+        page += 1;
+    }
+}
+
+/// Streams every product matching `query`, see [`export_orders`].
+pub async fn export_products<W: Write>(
+    client: &impl ProductClient,
+    query: &ProductQuery,
+    exporter: &mut Exporter<W>,
+) -> Result<usize, ExportError> {
+    let mut exported = 0;
+    let mut page = 1;
+    loop {
+        let query = query
+            .clone()
+            .page(page, PAGE_SIZE)
+            .order_by(OrderBy::Id, SortOrder::Asc);
+        let products = client.list_products(&query).await?;
+        for product in &products {
+            exporter.write(product)?;
+        }
+        exported += products.len();
+        if products.len() < PAGE_SIZE {
+            return Ok(exported);
+        }
+        page += 1;
+    }
+}
+
+/// Streams every coupon matching `query`, see [`export_orders`].
+pub async fn export_coupons<W: Write>(
+    client: &impl CouponClient,
+    query: &CouponQuery,
+    exporter: &mut Exporter<W>,
+) -> Result<usize, ExportError> {
+    let mut exported = 0;
+    let mut page = 1;
+    loop {
+        let query = query
+            .clone()
+            .page(page, PAGE_SIZE)
+            .order_by(OrderBy::Id, SortOrder::Asc);
+        let coupons = client.list_coupons(&query).await?;
+        for coupon in &coupons {
+            exporter.write(coupon)?;
+        }
+        exported += coupons.len();
+        if coupons.len() < PAGE_SIZE {
+            return Ok(exported);
+        }
+        page += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::Method;
+
+    use crate::export::default_line_item_columns;
+    use crate::{Auth, HttpResponse, MockTransport, Order, WooHttpClient};
+
+    use super::*;
+
+    fn order() -> Order {
+        let mut order: Order =
+            serde_json::from_str(include_str!("../../fixtures/order.json")).unwrap();
+        order.set_meta("_affiliate_id", "aff-17").unwrap();
+        order
+    }
+
+    #[test]
+    fn test_csv_line_items() {
+        let mut exporter = Exporter::new(
+            Vec::new(),
+            ExportFormat::Csv,
+            vec![
+                "id".parse().unwrap(),
+                "line_items.sku=sku".parse().unwrap(),
+                "line_items.total=line_total".parse().unwrap(),
+                "coupon_lines.code=coupons".parse().unwrap(),
+                "meta:_affiliate_id=affiliate".parse().unwrap(),
+            ],
+        )
+        .unwrap()
+        .explode("line_items");
+        exporter.write(&order()).unwrap();
+        assert_eq!(exporter.rows(), 2);
+        assert_eq!(
+            String::from_utf8(exporter.finish().unwrap()).unwrap(),
+            "id,sku,line_total,coupons,affiliate\n\
+             727,,18.00,10off,aff-17\n\
+             727,Bar3,27.00,10off,aff-17\n"
+        );
+    }
+
+    #[test]
+    fn test_ndjson_columns() {
+        let mut exporter = Exporter::new(
+            Vec::new(),
+            ExportFormat::NdJson,
+            default_line_item_columns(),
+        )
+        .unwrap();
+        exporter.write(&order()).unwrap();
+        let output = String::from_utf8(exporter.finish().unwrap()).unwrap();
+        let row: Value = serde_json::from_str(output.trim_end()).unwrap();
+        assert_eq!(row["line_items.product_id"], serde_json::json!([93, 22]));
+        assert_eq!(row["line_items.sku"], serde_json::json!(["", "Bar3"]));
+        assert_eq!(row["number"], "727");
+        assert_eq!(output.lines().count(), 1);
+
+        assert!(matches!(
+            Exporter::new(Vec::new(), ExportFormat::Csv, vec![]),
+            Err(ExportError::NoColumns)
+        ));
+        assert!("meta:".parse::<Column>().is_err());
+    }
+
+    #[test]
+    fn test_list_columns_line_up() {
+        let mut order = order();
+        order.line_items[0].sku = None;
+        let columns = vec![
+            "line_items.product_id=products".parse().unwrap(),
+            "line_items.sku=skus".parse().unwrap(),
+        ];
+
+        let mut exporter = Exporter::new(Vec::new(), ExportFormat::Csv, columns.clone()).unwrap();
+        exporter.write(&order).unwrap();
+        assert_eq!(
+            String::from_utf8(exporter.finish().unwrap()).unwrap(),
+            "products,skus\n93|22,|Bar3\n"
+        );
+
+        let mut exporter = Exporter::new(Vec::new(), ExportFormat::NdJson, columns).unwrap();
+        exporter.write(&order).unwrap();
+        let output = String::from_utf8(exporter.finish().unwrap()).unwrap();
+        let row: Value = serde_json::from_str(output.trim_end()).unwrap();
+        assert_eq!(row["skus"], serde_json::json!([null, "Bar3"]));
+    }
+
+    #[tokio::test]
+    async fn test_export_pages_by_ascending_id_mock() {
+        let client = WooHttpClient::with_transport(
+            Auth::basic("ck_test", "cs_test"),
+            "https://example.com/wp-json",
+            MockTransport::new(),
+        );
+        let coupons = format!("[{}]", include_str!("../../fixtures/coupon.json"));
+        client.transport().respond(
+            Method::GET,
+            "/wc/v3/coupons",
+            HttpResponse::new(200, coupons),
+        );
+
+        let mut exporter =
+            Exporter::new(Vec::new(), ExportFormat::Csv, vec!["code".parse().unwrap()]).unwrap();
+        let query = CouponQuery::new().order_by(OrderBy::Date, SortOrder::Desc);
+        let exported = export_coupons(&client, &query, &mut exporter)
+            .await
+            .unwrap();
+        assert_eq!(exported, 1);
+        assert_eq!(
+            String::from_utf8(exporter.finish().unwrap()).unwrap(),
+            "code\n10off\n"
+        );
+
+        let request = client.transport().last_request().unwrap();
+        assert!(
+            request.url.contains("orderby=id&order=asc"),
+            "{}",
+            request.url
+        );
+    }
+}
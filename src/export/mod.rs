@@ -0,0 +1,5 @@
+mod columns;
+pub use columns::*;
+
+mod exporter;
+pub use exporter::*;
@@ -1,5 +1,39 @@
+//! WooCommerce REST API client.
+//!
+//! # Features
+//!
+//! - `rust_decimal` (default): [`Money`] is `rust_decimal::Decimal`, otherwise the amount
+//!   text as sent. Coupon eligibility and analytics (`coupons::evaluate`,
+//!   `coupons::CouponUsageAnalyzer`) and `orders::TotalsVerifier` need the arithmetic and
+//!   are only built with it.
+//! - `chrono` (default): the date types wrap chrono's and support arithmetic.
+//!   `orders::OrderSync` needs it and is only built with it.
+//! - `webhook-server`: a hyper server for webhook deliveries.
+//! - `sqlite`: the `mirror` module, a local SQLite copy of the store. Enables `chrono`.
+//! - `testkit`: a fake WooCommerce server for tests. Enables `chrono`.
+//! - `cli`: the `woo` binary.
+
+// The fake server builds full WooCommerce documents with `json!`.
+#![recursion_limit = "256"]
+
 mod client;
 pub use client::*;
 
 mod models;
 pub use models::*;
+
+pub mod webhook;
+
+pub mod coupons;
+
+pub mod orders;
+
+pub mod export;
+
+#[cfg(feature = "sqlite")]
+pub mod mirror;
+
+// Also built for the crate's own tests, so the end-to-end tests in it run without
+// `--features testkit`. It stamps dates with the clock, which needs `chrono`.
+#[cfg(any(feature = "testkit", all(test, feature = "chrono")))]
+pub mod testkit;
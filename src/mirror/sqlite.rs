@@ -0,0 +1,681 @@
+use std::fmt;
+use std::path::Path;
+
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+
+use crate::{
+    Coupon, CouponClient, CouponQuery, Money, Order, OrderBy, OrderClient, OrderQuery, Product,
+    ProductClient, ProductQuery, SortOrder, WooCommerceHttpError, WooDateTimeUtc,
+};
+
+const SCHEMA_VERSION: i32 = 1;
+
+/// Amounts are TEXT holding the exact decimal, cast them (`CAST(total AS REAL)`) to do
+/// math in SQL. Dates are GMT, formatted like the API (`2024-03-01T10:00:00`), so they
+/// sort as text.
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS orders (
+    id INTEGER PRIMARY KEY,
+    parent_id INTEGER NOT NULL,
+    number TEXT NOT NULL,
+    status TEXT NOT NULL,
+    currency TEXT NOT NULL,
+    customer_id INTEGER NOT NULL,
+    billing_email TEXT NOT NULL,
+    payment_method TEXT NOT NULL,
+    transaction_id TEXT NOT NULL,
+    discount_total TEXT NOT NULL,
+    shipping_total TEXT NOT NULL,
+    total_tax TEXT NOT NULL,
+    total TEXT NOT NULL,
+    date_created_gmt TEXT NOT NULL,
+    date_modified_gmt TEXT NOT NULL,
+    date_paid_gmt TEXT,
+    date_completed_gmt TEXT,
+    raw_json TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS orders_date_modified ON orders (date_modified_gmt);
+CREATE INDEX IF NOT EXISTS orders_customer ON orders (customer_id);
+
+CREATE TABLE IF NOT EXISTS order_line_items (
+    id INTEGER PRIMARY KEY,
+    order_id INTEGER NOT NULL REFERENCES orders (id) ON DELETE CASCADE,
+    product_id INTEGER NOT NULL,
+    variation_id INTEGER NOT NULL,
+    sku TEXT,
+    name TEXT NOT NULL,
+    quantity INTEGER NOT NULL,
+    price TEXT NOT NULL,
+    subtotal TEXT NOT NULL,
+    total TEXT NOT NULL,
+    total_tax TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS order_line_items_order ON order_line_items (order_id);
+CREATE INDEX IF NOT EXISTS order_line_items_product ON order_line_items (product_id);
+
+CREATE TABLE IF NOT EXISTS order_coupon_lines (
+    id INTEGER PRIMARY KEY,
+    order_id INTEGER NOT NULL REFERENCES orders (id) ON DELETE CASCADE,
+    code TEXT NOT NULL,
+    discount TEXT NOT NULL,
+    discount_tax TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS order_coupon_lines_code ON order_coupon_lines (code);
+
+CREATE TABLE IF NOT EXISTS products (
+    id INTEGER PRIMARY KEY,
+    parent_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    slug TEXT NOT NULL,
+    sku TEXT NOT NULL,
+    type TEXT NOT NULL,
+    status TEXT NOT NULL,
+    price TEXT,
+    regular_price TEXT,
+    sale_price TEXT,
+    stock_status TEXT NOT NULL,
+    stock_quantity INTEGER,
+    total_sales INTEGER NOT NULL,
+    date_created_gmt TEXT NOT NULL,
+    date_modified_gmt TEXT NOT NULL,
+    raw_json TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS products_sku ON products (sku);
+
+CREATE TABLE IF NOT EXISTS product_categories (
+    product_id INTEGER NOT NULL REFERENCES products (id) ON DELETE CASCADE,
+    category_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    PRIMARY KEY (product_id, category_id)
+);
+
+CREATE TABLE IF NOT EXISTS coupons (
+    id INTEGER PRIMARY KEY,
+    code TEXT NOT NULL,
+    discount_type TEXT NOT NULL,
+    amount TEXT NOT NULL,
+    usage_count INTEGER NOT NULL,
+    usage_limit INTEGER,
+    date_expires_gmt TEXT,
+    date_created_gmt TEXT NOT NULL,
+    date_modified_gmt TEXT NOT NULL,
+    raw_json TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS coupons_code ON coupons (code);
+";
+
+#[derive(Debug)]
+pub enum MirrorError {
+    Sqlite(rusqlite::Error),
+    Http(WooCommerceHttpError),
+    Json(serde_json::Error),
+    /// The database was written by a newer version of this crate.
+    UnsupportedSchema(i32),
+}
+
+impl fmt::Display for MirrorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MirrorError::Sqlite(err) => write!(f, "sqlite: {}", err),
+            MirrorError::Http(err) => write!(f, "request failed: {:?}", err),
+            MirrorError::Json(err) => write!(f, "json: {}", err),
+            MirrorError::UnsupportedSchema(version) => {
+                write!(f, "unsupported mirror schema version {}", version)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MirrorError {}
+
+impl From<rusqlite::Error> for MirrorError {
+    fn from(err: rusqlite::Error) -> Self {
+        MirrorError::Sqlite(err)
+    }
+}
+
+impl From<WooCommerceHttpError> for MirrorError {
+    fn from(err: WooCommerceHttpError) -> Self {
+        MirrorError::Http(err)
+    }
+}
+
+impl From<serde_json::Error> for MirrorError {
+    fn from(err: serde_json::Error) -> Self {
+        MirrorError::Json(err)
+    }
+}
+
+/// What an upsert did with the records it got.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MirrorStats {
+    pub written: usize,
+    /// Not newer than the stored row, by `date_modified_gmt`.
+    pub skipped: usize,
+}
+
+impl MirrorStats {
+    fn merge(&mut self, other: MirrorStats) {
+        self.written += other.written;
+        self.skipped += other.skipped;
+    }
+
+    fn add(&mut self, written: bool) {
+        if written {
+            self.written += 1;
+        } else {
+            self.skipped += 1;
+        }
+    }
+}
+
+/// A local SQLite copy of orders, products and coupons.
+///
+/// Rows are keyed on the WooCommerce id and only replaced by a record with the same or a
+/// later `date_modified_gmt`, so feeding old pages or webhook payloads out of order is
+/// harmless. Every row keeps the record as JSON in `raw_json` next to the columns.
+pub struct SqliteMirror {
+    conn: Connection,
+}
+
+impl SqliteMirror {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, MirrorError> {
+        Self::init(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self, MirrorError> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self, MirrorError> {
+        conn.pragma_update(None, "foreign_keys", true)?;
+        let version: i32 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        if version > SCHEMA_VERSION {
+            return Err(MirrorError::UnsupportedSchema(version));
+        }
+        conn.execute_batch(SCHEMA)?;
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        Ok(SqliteMirror { conn })
+    }
+
+    /// For running queries against the mirror.
+    pub fn connection(&self) -> &Connection {
+        &self.conn
+    }
+
+    pub fn upsert_orders<'o>(
+        &mut self,
+        orders: impl IntoIterator<Item = &'o Order>,
+    ) -> Result<MirrorStats, MirrorError> {
+        let tx = self.conn.transaction()?;
+        let mut stats = MirrorStats::default();
+        for order in orders {
+            stats.add(upsert_order(&tx, order)?);
+        }
+        tx.commit()?;
+        Ok(stats)
+    }
+
+    pub fn upsert_products<'p>(
+        &mut self,
+        products: impl IntoIterator<Item = &'p Product>,
+    ) -> Result<MirrorStats, MirrorError> {
+        let tx = self.conn.transaction()?;
+        let mut stats = MirrorStats::default();
+        for product in products {
+            stats.add(upsert_product(&tx, product)?);
+        }
+        tx.commit()?;
+        Ok(stats)
+    }
+
+    pub fn upsert_coupons<'c>(
+        &mut self,
+        coupons: impl IntoIterator<Item = &'c Coupon>,
+    ) -> Result<MirrorStats, MirrorError> {
+        let tx = self.conn.transaction()?;
+        let mut stats = MirrorStats::default();
+        for coupon in coupons {
+            stats.add(upsert_coupon(&tx, coupon)?);
+        }
+        tx.commit()?;
+        Ok(stats)
+    }
+
+    /// Latest `date_modified_gmt` stored in `table`, for incremental pulls.
+    fn watermark(&self, table: &str) -> Result<Option<WooDateTimeUtc>, MirrorError> {
+        let sql = format!("SELECT MAX(date_modified_gmt) FROM {}", table);
+        let latest: Option<String> = self
+            .conn
+            .query_row(&sql, [], |row| row.get(0))
+            .optional()?
+            .flatten();
+        Ok(latest.and_then(|latest| latest.parse().ok()))
+    }
+
+    /// Pulls orders modified since the newest one in the mirror, all of them the first time.
+    pub async fn pull_orders(
+        &mut self,
+        client: &impl OrderClient,
+    ) -> Result<MirrorStats, MirrorError> {
+        let mut cursor = ModifiedCursor::new(self.watermark("orders")?);
+        let mut stats = MirrorStats::default();
+        loop {
+            let query = OrderQuery::new()
+                .page(cursor.page, PAGE_SIZE)
+                .modified_between(cursor.after, None)
+                .order_by(OrderBy::Modified, SortOrder::Asc);
+            let orders = client.list_orders(&query).await?;
+            stats.merge(self.upsert_orders(&orders)?);
+            match orders.last() {
+                Some(last) if orders.len() == PAGE_SIZE => cursor.advance(last.date_modified_gmt),
+                _ => return Ok(stats),
+            }
+        }
+    }
+
+    /// Pulls coupons modified since the newest one in the mirror, all of them the first time.
+    pub async fn pull_coupons(
+        &mut self,
+        client: &impl CouponClient,
+    ) -> Result<MirrorStats, MirrorError> {
+        let mut cursor = ModifiedCursor::new(self.watermark("coupons")?);
+        let mut stats = MirrorStats::default();
+        loop {
+            let mut query = CouponQuery::new()
+                .page(cursor.page, PAGE_SIZE)
+                .order_by(OrderBy::Modified, SortOrder::Asc);
+            query.modified_after = cursor.after;
+            let coupons = client.list_coupons(&query).await?;
+            stats.merge(self.upsert_coupons(&coupons)?);
+            match coupons.last() {
+                Some(last) if coupons.len() == PAGE_SIZE => cursor.advance(last.date_modified_gmt),
+                _ => return Ok(stats),
+            }
+        }
+    }
+
+    /// Pulls products modified since the newest one in the mirror, all of them the first
+    /// time.
+    pub async fn pull_products(
+        &mut self,
+        client: &impl ProductClient,
+    ) -> Result<MirrorStats, MirrorError> {
+        let mut cursor = ModifiedCursor::new(self.watermark("products")?);
+        let mut stats = MirrorStats::default();
+        loop {
+            let mut query = ProductQuery::new()
+                .page(cursor.page, PAGE_SIZE)
+                .order_by(OrderBy::Modified, SortOrder::Asc);
+            query.modified_after = cursor.after;
+            let products = client.list_products(&query).await?;
+            stats.merge(self.upsert_products(&products)?);
+            match products.last() {
+                Some(last) if products.len() == PAGE_SIZE => cursor.advance(last.date_modified_gmt),
+                _ => return Ok(stats),
+            }
+        }
+    }
+}
+
+const PAGE_SIZE: usize = 100;
+
+/// Where an incremental pull continues from. Records come oldest modification first and
+/// every page is committed before the next is fetched, so the mirror's watermark never
+/// passes a record that was not stored yet, even when a request fails halfway.
+///
+/// Each full page restarts from the last modification time seen instead of moving to the
+/// next page number, so records modified while paging (which move to the end) do not
+/// shift unseen ones onto pages that were already read.
+struct ModifiedCursor {
+    after: Option<WooDateTimeUtc>,
+    page: usize,
+}
+
+impl ModifiedCursor {
+    fn new(latest: Option<WooDateTimeUtc>) -> Self {
+        ModifiedCursor {
+            after: latest.map(Self::before),
+            page: 1,
+        }
+    }
+
+    fn advance(&mut self, last: WooDateTimeUtc) {
+        let after = Some(Self::before(last));
+        if after == self.after {
+            // A whole page modified within the same second, step over it.
+            self.page += 1;
+        } else {
+            self.after = after;
+            self.page = 1;
+        }
+    }
+
+    /// `modified_after` is exclusive and has second precision, refetch that second.
+    fn before(date: WooDateTimeUtc) -> WooDateTimeUtc {
+        date - std::time::Duration::from_secs(1)
+    }
+}
+
+fn money(amount: &Money) -> String {
+    amount.to_string()
+}
+
+fn upsert_order(tx: &Transaction, order: &Order) -> Result<bool, MirrorError> {
+    let raw = serde_json::to_string(order)?;
+    let written = tx.execute(
+        "INSERT INTO orders (id, parent_id, number, status, currency, customer_id,
+            billing_email, payment_method, transaction_id, discount_total, shipping_total,
+            total_tax, total, date_created_gmt, date_modified_gmt, date_paid_gmt,
+            date_completed_gmt, raw_json)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)
+         ON CONFLICT (id) DO UPDATE SET
+            parent_id = excluded.parent_id,
+            number = excluded.number,
+            status = excluded.status,
+            currency = excluded.currency,
+            customer_id = excluded.customer_id,
+            billing_email = excluded.billing_email,
+            payment_method = excluded.payment_method,
+            transaction_id = excluded.transaction_id,
+            discount_total = excluded.discount_total,
+            shipping_total = excluded.shipping_total,
+            total_tax = excluded.total_tax,
+            total = excluded.total,
+            date_created_gmt = excluded.date_created_gmt,
+            date_modified_gmt = excluded.date_modified_gmt,
+            date_paid_gmt = excluded.date_paid_gmt,
+            date_completed_gmt = excluded.date_completed_gmt,
+            raw_json = excluded.raw_json
+         WHERE excluded.date_modified_gmt >= orders.date_modified_gmt",
+        params![
+            order.id.0 as i64,
+            order.parent_id.0 as i64,
+            order.number,
+            order.status.as_str(),
+            order.currency,
+            order.customer_id.0 as i64,
+            order.billing.email,
+            order.payment_method,
+            order.transaction_id,
+            money(&order.discount_total),
+            money(&order.shipping_total),
+            money(&order.total_tax),
+            money(&order.total),
+            order.date_created_gmt.to_string(),
+            order.date_modified_gmt.to_string(),
+            order.date_paid_gmt.map(|date| date.to_string()),
+            order.date_completed_gmt.map(|date| date.to_string()),
+            raw,
+        ],
+    )? > 0;
+    if !written {
+        return Ok(false);
+    }
+
+    let order_id = order.id.0 as i64;
+    tx.execute(
+        "DELETE FROM order_line_items WHERE order_id = ?1",
+        [order_id],
+    )?;
+    tx.execute(
+        "DELETE FROM order_coupon_lines WHERE order_id = ?1",
+        [order_id],
+    )?;
+    let mut insert_line = tx.prepare_cached(
+        "INSERT OR REPLACE INTO order_line_items (id, order_id, product_id, variation_id, sku,
+            name, quantity, price, subtotal, total, total_tax)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+    )?;
+    for line in &order.line_items {
+        insert_line.execute(params![
+            line.id,
+            order_id,
+            line.product_id.0 as i64,
+            line.variation_id.0 as i64,
+            line.sku,
+            line.name,
+            line.quantity,
+            money(&line.price),
+            money(&line.subtotal),
+            money(&line.total),
+            money(&line.total_tax),
+        ])?;
+    }
+    let mut insert_coupon = tx.prepare_cached(
+        "INSERT OR REPLACE INTO order_coupon_lines (id, order_id, code, discount, discount_tax)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+    )?;
+    for line in order.coupon_lines.iter().flatten() {
+        insert_coupon.execute(params![
+            line.id,
+            order_id,
+            line.code.to_lowercase(),
+            money(&line.discount),
+            money(&line.discount_tax),
+        ])?;
+    }
+    Ok(true)
+}
+
+fn upsert_product(tx: &Transaction, product: &Product) -> Result<bool, MirrorError> {
+    let raw = serde_json::to_string(product)?;
+    let written = tx.execute(
+        "INSERT INTO products (id, parent_id, name, slug, sku, type, status, price,
+            regular_price, sale_price, stock_status, stock_quantity, total_sales,
+            date_created_gmt, date_modified_gmt, raw_json)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)
+         ON CONFLICT (id) DO UPDATE SET
+            parent_id = excluded.parent_id,
+            name = excluded.name,
+            slug = excluded.slug,
+            sku = excluded.sku,
+            type = excluded.type,
+            status = excluded.status,
+            price = excluded.price,
+            regular_price = excluded.regular_price,
+            sale_price = excluded.sale_price,
+            stock_status = excluded.stock_status,
+            stock_quantity = excluded.stock_quantity,
+            total_sales = excluded.total_sales,
+            date_created_gmt = excluded.date_created_gmt,
+            date_modified_gmt = excluded.date_modified_gmt,
+            raw_json = excluded.raw_json
+         WHERE excluded.date_modified_gmt >= products.date_modified_gmt",
+        params![
+            product.id.0 as i64,
+            product.parent_id.0 as i64,
+            product.name,
+            product.slug,
+            product.sku,
+            product.product_type.as_str(),
+            product.status.as_str(),
+            product.price.as_ref().map(money),
+            product.regular_price.as_ref().map(money),
+            product.sale_price.as_ref().map(money),
+            product.stock_status.as_str(),
+            product.stock_quantity,
+            product.total_sales,
+            product.date_created_gmt.to_string(),
+            product.date_modified_gmt.to_string(),
+            raw,
+        ],
+    )? > 0;
+    if !written {
+        return Ok(false);
+    }
+
+    let product_id = product.id.0 as i64;
+    tx.execute(
+        "DELETE FROM product_categories WHERE product_id = ?1",
+        [product_id],
+    )?;
+    let mut insert_category = tx.prepare_cached(
+        "INSERT OR REPLACE INTO product_categories (product_id, category_id, name)
+         VALUES (?1, ?2, ?3)",
+    )?;
+    for category in &product.categories {
+        insert_category.execute(params![product_id, category.id, category.name])?;
+    }
+    Ok(true)
+}
+
+fn upsert_coupon(tx: &Transaction, coupon: &Coupon) -> Result<bool, MirrorError> {
+    let raw = serde_json::to_string(coupon)?;
+    let discount_type = serde_json::to_value(&coupon.discount_type)?;
+    let written = tx.execute(
+        "INSERT INTO coupons (id, code, discount_type, amount, usage_count, usage_limit,
+            date_expires_gmt, date_created_gmt, date_modified_gmt, raw_json)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+         ON CONFLICT (id) DO UPDATE SET
+            code = excluded.code,
+            discount_type = excluded.discount_type,
+            amount = excluded.amount,
+            usage_count = excluded.usage_count,
+            usage_limit = excluded.usage_limit,
+            date_expires_gmt = excluded.date_expires_gmt,
+            date_created_gmt = excluded.date_created_gmt,
+            date_modified_gmt = excluded.date_modified_gmt,
+            raw_json = excluded.raw_json
+         WHERE excluded.date_modified_gmt >= coupons.date_modified_gmt",
+        params![
+            coupon.id.0 as i64,
+            coupon.code.to_lowercase(),
+            discount_type.as_str().unwrap_or_default(),
+            money(&coupon.amount),
+            coupon.usage_count,
+            coupon.usage_limit,
+            coupon.date_expires_gmt.map(|date| date.to_string()),
+            coupon.date_created_gmt.to_string(),
+            coupon.date_modified_gmt.to_string(),
+            raw,
+        ],
+    )? > 0;
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use reqwest::Method;
+
+    use crate::{Auth, HttpResponse, MockTransport, OrderId, WooHttpClient};
+
+    use super::*;
+
+    fn order() -> Order {
+        serde_json::from_str(include_str!("../../fixtures/order.json")).unwrap()
+    }
+
+    #[test]
+    fn test_upsert_keeps_newest() {
+        let mut mirror = SqliteMirror::open_in_memory().unwrap();
+        let mut order = order();
+        assert_eq!(mirror.upsert_orders([&order]).unwrap().written, 1);
+
+        let older_status = order.status;
+        order.status = crate::OrderStatus::Completed;
+        order.line_items.truncate(1);
+        order.date_modified_gmt = order.date_modified_gmt + std::time::Duration::from_secs(60);
+        let newer = serde_json::to_string(&order).unwrap();
+        assert_eq!(mirror.upsert_orders([&order]).unwrap().written, 1);
+
+        order.status = older_status;
+        order.date_modified_gmt = order.date_modified_gmt - std::time::Duration::from_secs(120);
+        let stats = mirror.upsert_orders([&order]).unwrap();
+        assert_eq!(
+            stats,
+            MirrorStats {
+                written: 0,
+                skipped: 1
+            }
+        );
+
+        let (status, raw): (String, String) = mirror
+            .connection()
+            .query_row(
+                "SELECT status, raw_json FROM orders WHERE id = 727",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(status, "completed");
+        assert_eq!(raw, newer);
+
+        let (lines, coupon): (i64, String) = mirror
+            .connection()
+            .query_row(
+                "SELECT COUNT(*), (SELECT code FROM order_coupon_lines WHERE order_id = 727)
+                 FROM order_line_items WHERE order_id = 727",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((lines, coupon.as_str()), (1, "10off"));
+    }
+
+    fn mock_client() -> WooHttpClient<MockTransport> {
+        WooHttpClient::with_transport(
+            Auth::basic("ck_test", "cs_test"),
+            "https://example.com/wp-json",
+            MockTransport::new(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_pull_resumes_after_partial_failure() {
+        let base = order().date_modified_gmt;
+        let first_page: Vec<Order> = (1..=PAGE_SIZE as u64)
+            .map(|i| {
+                let mut order = order();
+                order.id = OrderId(1000 - i);
+                order.date_modified_gmt = base + Duration::from_secs(i);
+                order
+            })
+            .collect();
+        let client = mock_client();
+        client
+            .transport()
+            .respond_json(Method::GET, "/wc/v3/orders", 200, &first_page);
+        client.transport().respond(
+            Method::GET,
+            "/wc/v3/orders",
+            HttpResponse::new(
+                500,
+                r#"{"code":"internal_server_error","message":"","data":{}}"#,
+            ),
+        );
+
+        let mut mirror = SqliteMirror::open_in_memory().unwrap();
+        assert!(mirror.pull_orders(&client).await.is_err());
+        let requests = client.transport().requests();
+        assert!(requests[0].url.contains("orderby=modified&order=asc"));
+        assert!(!requests[0].url.contains("modified_after"));
+        let last = base + Duration::from_secs(PAGE_SIZE as u64);
+        let resume =
+            format!("modified_after={}", last - Duration::from_secs(1)).replace(':', "%3A");
+        assert!(requests[1].url.contains(&resume), "{}", requests[1].url);
+        assert!(requests[1].url.contains("page=1&"), "{}", requests[1].url);
+
+        // Never stored because its page failed, modified in the same second as the last
+        // stored order but with a higher id.
+        let mut missed = order();
+        missed.id = OrderId(2000);
+        missed.date_modified_gmt = last;
+        let client = mock_client();
+        client
+            .transport()
+            .respond_json(Method::GET, "/wc/v3/orders", 200, &[&missed]);
+        let stats = mirror.pull_orders(&client).await.unwrap();
+        assert_eq!(stats.written, 1);
+        let request = client.transport().last_request().unwrap();
+        assert!(request.url.contains(&resume), "{}", request.url);
+
+        let count: i64 = mirror
+            .connection()
+            .query_row("SELECT COUNT(*) FROM orders", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, PAGE_SIZE as i64 + 1);
+    }
+}
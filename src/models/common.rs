@@ -1,21 +1,238 @@
+use std::fmt;
+
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{Map, Value};
 
-#[derive(Serialize, Deserialize, Debug)]
+use crate::{ErrorResponse, WooDateTimeUtc};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct MetaData {
-    pub id: i32,
+    /// `None` for entries added locally and not saved yet.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<i32>,
     pub key: String,
     pub value: Value,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+/// Meta entry for create requests, WooCommerce assigns the id.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NewMetaData {
+    pub key: String,
+    pub value: Value,
+}
+
+impl NewMetaData {
+    pub fn new(key: &str, value: impl Into<Value>) -> Self {
+        Self {
+            key: key.to_string(),
+            value: value.into(),
+        }
+    }
+}
+
+impl From<NewMetaData> for MetaData {
+    fn from(meta: NewMetaData) -> Self {
+        MetaData {
+            id: None,
+            key: meta.key,
+            value: meta.value,
+            extra: Map::new(),
+        }
+    }
+}
+
+/// `order` param of list endpoints.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+impl SortOrder {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SortOrder::Asc => "asc",
+            SortOrder::Desc => "desc",
+        }
+    }
+}
+
+/// `orderby` param of the order, coupon and product list endpoints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderBy {
+    Date,
+    Id,
+    Include,
+    Title,
+    Slug,
+    Modified,
+}
+
+impl OrderBy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderBy::Date => "date",
+            OrderBy::Id => "id",
+            OrderBy::Include => "include",
+            OrderBy::Title => "title",
+            OrderBy::Slug => "slug",
+            OrderBy::Modified => "modified",
+        }
+    }
+}
+
+/// Query string of a list endpoint. Unset values and empty lists are left out.
+#[derive(Debug, Default)]
+pub(crate) struct QueryPairs {
+    pairs: Vec<(&'static str, String)>,
+    /// Where `dates_are_gmt` goes, right after the last date.
+    dates_end: Option<usize>,
+}
+
+impl QueryPairs {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn page(self, page: Option<usize>, per_page: Option<usize>) -> Self {
+        self.value("page", page).value("per_page", per_page)
+    }
+
+    pub(crate) fn value(mut self, name: &'static str, value: Option<impl ToString>) -> Self {
+        if let Some(value) = value {
+            self.pairs.push((name, value.to_string()));
+        }
+        self
+    }
+
+    /// Comma separated, e.g. `include=1,2,3`.
+    pub(crate) fn list<T: fmt::Display>(mut self, name: &'static str, items: &[T]) -> Self {
+        if !items.is_empty() {
+            let items: Vec<String> = items.iter().map(T::to_string).collect();
+            self.pairs.push((name, items.join(",")));
+        }
+        self
+    }
+
+    /// Dates are sent as GMT, which WooCommerce needs to be told once.
+    pub(crate) fn date(mut self, name: &'static str, date: Option<WooDateTimeUtc>) -> Self {
+        if let Some(date) = date {
+            self.pairs.push((name, date.to_string()));
+            self.dates_end = Some(self.pairs.len());
+        }
+        self
+    }
+
+    pub(crate) fn sort(self, orderby: Option<OrderBy>, order: Option<SortOrder>) -> Self {
+        self.value("orderby", orderby.map(|orderby| orderby.as_str()))
+            .value("order", order.map(|order| order.as_str()))
+    }
+
+    pub(crate) fn finish(mut self) -> Vec<(&'static str, String)> {
+        if let Some(index) = self.dates_end {
+            self.pairs.insert(index, ("dates_are_gmt", "true".to_string()));
+        }
+        self.pairs
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
 pub struct Links {
     #[serde(rename = "self")]
     pub self_link: Vec<Link>,
     pub collection: Vec<Link>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Link {
     pub href: String,
 }
+
+/// Body of a `POST /<resource>/batch` request, `I` is the resource's id type.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BatchRequest<C, U, I> {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default = "Vec::new")]
+    pub create: Vec<C>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default = "Vec::new")]
+    pub update: Vec<BatchUpdate<U, I>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub delete: Vec<I>,
+}
+
+impl<C, U, I> Default for BatchRequest<C, U, I> {
+    fn default() -> Self {
+        Self {
+            create: Vec::new(),
+            update: Vec::new(),
+            delete: Vec::new(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BatchUpdate<U, I> {
+    pub id: I,
+    #[serde(flatten)]
+    pub update: U,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BatchResponse<T> {
+    #[serde(default = "Vec::new")]
+    pub create: Vec<BatchItem<T>>,
+    #[serde(default = "Vec::new")]
+    pub update: Vec<BatchItem<T>>,
+    #[serde(default = "Vec::new")]
+    pub delete: Vec<BatchItem<T>>,
+}
+
+/// Every entry of a batch is processed on its own, so failures are reported per item.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+pub enum BatchItem<T> {
+    Err(BatchItemError),
+    Ok(T),
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BatchItemError {
+    pub id: u64,
+    pub error: ErrorResponse,
+}
+
+/// Returned by the `build()` of the request builders when a required piece is missing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuilderError {
+    Missing(&'static str),
+    Invalid { field: String, reason: String },
+}
+
+impl BuilderError {
+    pub(crate) fn invalid(field: impl Into<String>, reason: impl Into<String>) -> Self {
+        BuilderError::Invalid {
+            field: field.into(),
+            reason: reason.into(),
+        }
+    }
+}
+
+impl fmt::Display for BuilderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuilderError::Missing(field) => write!(f, "missing {}", field),
+            BuilderError::Invalid { field, reason } => write!(f, "invalid {}: {}", field, reason),
+        }
+    }
+}
+
+impl std::error::Error for BuilderError {}
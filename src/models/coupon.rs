@@ -1,132 +1,441 @@
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 
-use crate::{MetaData, Links};
+use crate::{
+    BuilderError, CouponId, Links, MetaData, Money, NewMetaData, OrderBy, ProductId, SortOrder,
+    WooDateTime, WooDateTimeUtc,
+};
 
-#[derive(Serialize, Deserialize)]
+use super::common::QueryPairs;
+
+/// Body of `POST /coupons`. [`CreateCoupon::builder`] checks the restrictions against
+/// each other.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct CreateCoupon {
     pub code: String,
-    pub amount: String,
+    pub amount: Money,
     pub discount_type: DiscountType,
     pub description: String,
-    pub date_expires_gmt: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date_expires_gmt: Option<WooDateTimeUtc>,
     pub individual_use: bool,
-    pub product_ids: Vec<i32>,
-    #[serde(default)]
+    pub product_ids: Vec<ProductId>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub excluded_product_ids: Vec<ProductId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub usage_limit: Option<i32>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub usage_limit_per_user: Option<i32>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub limit_usage_to_x_items: Option<i32>,
-}
-
-
-#[derive(Serialize, Deserialize, Debug)]
-pub struct Coupon {
-    pub id: i32,
-    pub code: String,
-    pub amount: String,
-    pub date_created: String,
-    pub date_created_gmt: String,
-    pub date_modified: String,
-    pub date_modified_gmt: String,
-    pub discount_type: DiscountType,
-    pub description: String,
-    pub date_expires: Option<String>,
-    pub date_expires_gmt: Option<String>,
-    pub usage_count: i32,
-    pub individual_use: bool,
-    pub product_ids: Vec<i32>,
-    pub excluded_product_ids: Vec<i32>,
-    #[serde(default)]
-    pub usage_limit: Option<i32>,
     #[serde(default)]
-    pub usage_limit_per_user: Option<i32>,
-    #[serde(default)]
-    pub limit_usage_to_x_items: Option<i32>,
     pub free_shipping: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub product_categories: Vec<i32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub excluded_product_categories: Vec<i32>,
+    #[serde(default)]
     pub exclude_sale_items: bool,
-    pub minimum_amount: String,
-    pub maximum_amount: String,
+    /// Minimum cart subtotal.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub minimum_amount: Option<Money>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub maximum_amount: Option<Money>,
+    /// Billing emails allowed to use the coupon, `*` works as a wildcard.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub email_restrictions: Vec<String>,
-    pub used_by: Vec<String>,
-    pub meta_data: Vec<MetaData>,
-    pub _links: Links,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub meta_data: Vec<NewMetaData>,
+}
+
+impl CreateCoupon {
+    pub fn builder(code: &str, discount_type: DiscountType, amount: Money) -> CreateCouponBuilder {
+        CreateCouponBuilder {
+            coupon: CreateCoupon {
+                code: code.to_string(),
+                discount_type,
+                amount,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct CreateCouponBuilder {
+    coupon: CreateCoupon,
+}
+
+impl CreateCouponBuilder {
+    pub fn description(mut self, description: &str) -> Self {
+        self.coupon.description = description.to_string();
+        self
+    }
+
+    pub fn expires(mut self, date_expires_gmt: WooDateTimeUtc) -> Self {
+        self.coupon.date_expires_gmt = Some(date_expires_gmt);
+        self
+    }
+
+    /// The coupon can't be combined with other coupons.
+    pub fn individual_use(mut self, individual_use: bool) -> Self {
+        self.coupon.individual_use = individual_use;
+        self
+    }
+
+    pub fn product(mut self, product_id: ProductId) -> Self {
+        self.coupon.product_ids.push(product_id);
+        self
+    }
+
+    pub fn exclude_product(mut self, product_id: ProductId) -> Self {
+        self.coupon.excluded_product_ids.push(product_id);
+        self
+    }
+
+    pub fn category(mut self, category_id: i32) -> Self {
+        self.coupon.product_categories.push(category_id);
+        self
+    }
+
+    pub fn exclude_category(mut self, category_id: i32) -> Self {
+        self.coupon.excluded_product_categories.push(category_id);
+        self
+    }
+
+    pub fn exclude_sale_items(mut self, exclude_sale_items: bool) -> Self {
+        self.coupon.exclude_sale_items = exclude_sale_items;
+        self
+    }
+
+    pub fn free_shipping(mut self, free_shipping: bool) -> Self {
+        self.coupon.free_shipping = free_shipping;
+        self
+    }
+
+    pub fn minimum_amount(mut self, amount: Money) -> Self {
+        self.coupon.minimum_amount = Some(amount);
+        self
+    }
+
+    pub fn maximum_amount(mut self, amount: Money) -> Self {
+        self.coupon.maximum_amount = Some(amount);
+        self
+    }
+
+    pub fn usage_limit(mut self, usage_limit: i32) -> Self {
+        self.coupon.usage_limit = Some(usage_limit);
+        self
+    }
+
+    pub fn usage_limit_per_user(mut self, usage_limit: i32) -> Self {
+        self.coupon.usage_limit_per_user = Some(usage_limit);
+        self
+    }
+
+    /// Only for `Percent` and `FixedProduct` coupons.
+    pub fn limit_usage_to_x_items(mut self, items: i32) -> Self {
+        self.coupon.limit_usage_to_x_items = Some(items);
+        self
+    }
+
+    pub fn email_restriction(mut self, email: &str) -> Self {
+        self.coupon.email_restrictions.push(email.to_string());
+        self
+    }
+
+    pub fn meta(mut self, key: &str, value: impl Into<Value>) -> Self {
+        self.coupon.meta_data.push(NewMetaData::new(key, value));
+        self
+    }
+
+    pub fn build(self) -> Result<CreateCoupon, BuilderError> {
+        let coupon = self.coupon;
+        if coupon.code.trim().is_empty() {
+            return Err(BuilderError::Missing("code"));
+        }
+        #[cfg(feature = "rust_decimal")]
+        check_amounts(&coupon)?;
+        let limits = [
+            ("usage_limit", coupon.usage_limit),
+            ("usage_limit_per_user", coupon.usage_limit_per_user),
+            ("limit_usage_to_x_items", coupon.limit_usage_to_x_items),
+        ];
+        for (field, limit) in limits {
+            if limit.is_some_and(|limit| limit <= 0) {
+                return Err(BuilderError::invalid(field, "must be positive"));
+            }
+        }
+        let per_item = coupon.discount_type != DiscountType::FixedCart;
+        if coupon.limit_usage_to_x_items.is_some() && !per_item {
+            return Err(BuilderError::invalid(
+                "limit_usage_to_x_items",
+                "ignored by fixed_cart coupons",
+            ));
+        }
+        for (index, email) in coupon.email_restrictions.iter().enumerate() {
+            if !email.contains('@') {
+                return Err(BuilderError::invalid(
+                    format!("email_restrictions[{}]", index),
+                    "not an email address",
+                ));
+            }
+        }
+        Ok(coupon)
+    }
+}
+
+/// The amount checks need decimal comparisons, so they only run with `rust_decimal`.
+#[cfg(feature = "rust_decimal")]
+fn check_amounts(coupon: &CreateCoupon) -> Result<(), BuilderError> {
+    if coupon.amount.is_sign_negative() {
+        return Err(BuilderError::invalid("amount", "must not be negative"));
+    }
+    if coupon.discount_type == DiscountType::Percent && coupon.amount > Money::from(100) {
+        return Err(BuilderError::invalid("amount", "a percentage above 100"));
+    }
+    if let (Some(minimum), Some(maximum)) = (coupon.minimum_amount, coupon.maximum_amount) {
+        if minimum > maximum {
+            return Err(BuilderError::invalid(
+                "minimum_amount",
+                "must not exceed maximum_amount",
+            ));
+        }
+    }
+    Ok(())
 }
 
 #[derive(Serialize, Deserialize, Debug)]
-pub struct UpdateCoupon {
-    pub id: i32,
-    // CODE SHOULD NOT BE UPDATED YOU WILL GET AN ERROR
-    //pub code: String,
-    pub amount: String,
-    pub date_created: String,
-    pub date_created_gmt: String,
-    pub date_modified: String,
-    pub date_modified_gmt: String,
+pub struct Coupon {
+    pub id: CouponId,
+    pub code: String,
+    pub amount: Money,
+    pub date_created: WooDateTime,
+    pub date_created_gmt: WooDateTimeUtc,
+    pub date_modified: WooDateTime,
+    pub date_modified_gmt: WooDateTimeUtc,
     pub discount_type: DiscountType,
+    #[serde(default)]
     pub description: String,
-    pub date_expires: Option<String>,
-    pub date_expires_gmt: Option<String>,
+    pub date_expires: Option<WooDateTime>,
+    pub date_expires_gmt: Option<WooDateTimeUtc>,
+    #[serde(default)]
     pub usage_count: i32,
+    #[serde(default)]
     pub individual_use: bool,
-    pub product_ids: Vec<i32>,
-    pub excluded_product_ids: Vec<i32>,
+    #[serde(default)]
+    pub product_ids: Vec<ProductId>,
+    #[serde(default)]
+    pub excluded_product_ids: Vec<ProductId>,
     #[serde(default)]
     pub usage_limit: Option<i32>,
     #[serde(default)]
     pub usage_limit_per_user: Option<i32>,
     #[serde(default)]
     pub limit_usage_to_x_items: Option<i32>,
+    #[serde(default)]
     pub free_shipping: bool,
+    #[serde(default)]
     pub product_categories: Vec<i32>,
+    #[serde(default)]
     pub excluded_product_categories: Vec<i32>,
+    #[serde(default)]
     pub exclude_sale_items: bool,
-    pub minimum_amount: String,
-    pub maximum_amount: String,
+    #[serde(default, with = "crate::optional_money")]
+    pub minimum_amount: Option<Money>,
+    #[serde(default, with = "crate::optional_money")]
+    pub maximum_amount: Option<Money>,
+    #[serde(default)]
     pub email_restrictions: Vec<String>,
+    #[serde(default)]
     pub used_by: Vec<String>,
+    #[serde(default)]
     pub meta_data: Vec<MetaData>,
+    #[serde(default)]
     pub _links: Links,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Body of `PUT /coupons/<id>`, only the fields that are set are changed.
+/// The code can't be changed, WooCommerce rejects it.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct UpdateCoupon {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub amount: Option<Money>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub discount_type: Option<DiscountType>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// `Some(None)` removes the expiry date.
+    #[serde(
+        default,
+        with = "crate::clearable_date",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub date_expires_gmt: Option<Option<WooDateTimeUtc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub individual_use: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub product_ids: Option<Vec<ProductId>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub excluded_product_ids: Option<Vec<ProductId>>,
+    /// `Some(None)` removes the limit.
+    #[serde(
+        default,
+        with = "clearable_limit",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub usage_limit: Option<Option<i32>>,
+    /// `Some(None)` removes the limit.
+    #[serde(
+        default,
+        with = "clearable_limit",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub usage_limit_per_user: Option<Option<i32>>,
+    /// `Some(None)` removes the limit.
+    #[serde(
+        default,
+        with = "clearable_limit",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub limit_usage_to_x_items: Option<Option<i32>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub free_shipping: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub product_categories: Option<Vec<i32>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub excluded_product_categories: Option<Vec<i32>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exclude_sale_items: Option<bool>,
+    /// `Some(None)` removes the minimum.
+    #[serde(
+        default,
+        with = "crate::clearable_money",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub minimum_amount: Option<Option<Money>>,
+    /// `Some(None)` removes the maximum.
+    #[serde(
+        default,
+        with = "crate::clearable_money",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub maximum_amount: Option<Option<Money>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email_restrictions: Option<Vec<String>>,
+    /// Entries with an `id` are updated, the others added.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta_data: Option<Vec<MetaData>>,
 }
 
-// impl into from coupon to update coupon
-impl Into<UpdateCoupon> for Coupon {
-    fn into(self) -> UpdateCoupon {
-        UpdateCoupon {
-            id: self.id,
-            amount: self.amount,
-            date_created: self.date_created,
-            date_created_gmt: self.date_created_gmt,
-            date_modified: self.date_modified,
-            date_modified_gmt: self.date_modified_gmt,
-            discount_type: self.discount_type,
-            description: self.description,
-            date_expires: self.date_expires,
-            date_expires_gmt: self.date_expires_gmt,
-            usage_count: self.usage_count,
-            individual_use: self.individual_use,
-            product_ids: self.product_ids,
-            excluded_product_ids: self.excluded_product_ids,
-            usage_limit: self.usage_limit,
-            usage_limit_per_user: self.usage_limit_per_user,
-            limit_usage_to_x_items: self.limit_usage_to_x_items,
-            free_shipping: self.free_shipping,
-            product_categories: self.product_categories,
-            excluded_product_categories: self.excluded_product_categories,
-            exclude_sale_items: self.exclude_sale_items,
-            minimum_amount: self.minimum_amount,
-            maximum_amount: self.maximum_amount,
-            email_restrictions: self.email_restrictions,
-            used_by: self.used_by,
-            meta_data: self.meta_data,
-            _links: self._links,
+/// Usage limits for update bodies. WooCommerce stores "no limit" as 0 and rejects `null`,
+/// so `Some(None)` is written as 0.
+mod clearable_limit {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        value: &Option<Option<i32>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(limit) => serializer.serialize_i32(limit.unwrap_or(0)),
+            None => serializer.serialize_none(),
         }
     }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Option<i32>>, D::Error> {
+        let limit = Option::<i32>::deserialize(deserializer)?;
+        Ok(Some(limit.filter(|limit| *limit > 0)))
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug, Default)]
+/// WooCommerce stores codes lowercased, so lookups should go through this.
+pub fn normalize_coupon_code(code: &str) -> String {
+    code.trim().to_lowercase()
+}
+
+impl Coupon {
+    /// Case-insensitive, like WooCommerce.
+    pub fn matches_code(&self, code: &str) -> bool {
+        normalize_coupon_code(&self.code) == normalize_coupon_code(code)
+    }
+}
+
+/// Filters for `GET /coupons`, unset fields use the WooCommerce defaults.
+#[derive(Debug, Clone, Default)]
+pub struct CouponQuery {
+    pub page: Option<usize>,
+    pub per_page: Option<usize>,
+    pub search: Option<String>,
+    /// Exact code, matched case-insensitively.
+    pub code: Option<String>,
+    pub include: Vec<CouponId>,
+    pub exclude: Vec<CouponId>,
+    pub modified_after: Option<WooDateTimeUtc>,
+    pub orderby: Option<OrderBy>,
+    pub order: Option<SortOrder>,
+}
+
+impl CouponQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn page(mut self, page: usize, per_page: usize) -> Self {
+        self.page = Some(page);
+        self.per_page = Some(per_page);
+        self
+    }
+
+    pub fn search(mut self, search: &str) -> Self {
+        self.search = Some(search.to_string());
+        self
+    }
+
+    pub fn code(mut self, code: &str) -> Self {
+        self.code = Some(code.to_string());
+        self
+    }
+
+    pub fn include(mut self, ids: &[CouponId]) -> Self {
+        self.include.extend_from_slice(ids);
+        self
+    }
+
+    pub fn exclude(mut self, ids: &[CouponId]) -> Self {
+        self.exclude.extend_from_slice(ids);
+        self
+    }
+
+    pub fn modified_after(mut self, modified_after: WooDateTimeUtc) -> Self {
+        self.modified_after = Some(modified_after);
+        self
+    }
+
+    pub fn order_by(mut self, orderby: OrderBy, order: SortOrder) -> Self {
+        self.orderby = Some(orderby);
+        self.order = Some(order);
+        self
+    }
+
+    pub(crate) fn query_pairs(&self) -> Vec<(&'static str, String)> {
+        QueryPairs::new()
+            .page(self.page, self.per_page)
+            .value("search", self.search.as_ref())
+            .value("code", self.code.as_deref().map(normalize_coupon_code))
+            .list("include", &self.include)
+            .list("exclude", &self.exclude)
+            .date("modified_after", self.modified_after)
+            .sort(self.orderby, self.order)
+            .finish()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
 pub enum DiscountType {
     #[default]
     #[serde(rename = "fixed_cart")]
@@ -135,4 +444,4 @@ pub enum DiscountType {
     Percent,
     #[serde(rename = "fixed_product")]
     FixedProduct,
-}
\ No newline at end of file
+}
@@ -0,0 +1,395 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+#[cfg(feature = "chrono")]
+use chrono::Timelike;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DateTimeParseError(pub String);
+
+impl fmt::Display for DateTimeParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid WooCommerce date {:?}", self.0)
+    }
+}
+
+impl std::error::Error for DateTimeParseError {}
+
+/// The format WooCommerce sends and expects, without an offset.
+#[cfg(feature = "chrono")]
+const FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+/// Store local time, as in `date_created` and the other fields without the `_gmt` suffix.
+/// It has no offset, so it can't be compared with [`WooDateTimeUtc`].
+///
+/// Serializes as `YYYY-MM-DDTHH:MM:SS`. Derefs to [`chrono::NaiveDateTime`].
+#[cfg(feature = "chrono")]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct WooDateTime(chrono::NaiveDateTime);
+
+/// UTC time, as in `date_created_gmt` and the other `*_gmt` fields.
+///
+/// Serializes as `YYYY-MM-DDTHH:MM:SS`, the format WooCommerce expects for `*_gmt` fields.
+/// Parsing also accepts a space separator, fractional seconds and a `Z` or `+02:00` suffix.
+/// Derefs to [`chrono::DateTime<Utc>`](chrono::DateTime).
+#[cfg(feature = "chrono")]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct WooDateTimeUtc(chrono::DateTime<chrono::Utc>);
+
+/// Store local time as WooCommerce sent it, `YYYY-MM-DDTHH:MM:SS`. Enable the `chrono`
+/// feature for date arithmetic.
+#[cfg(not(feature = "chrono"))]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct WooDateTime([u8; 19]);
+
+/// UTC time as WooCommerce sent it, `YYYY-MM-DDTHH:MM:SS`. The fixed format sorts
+/// chronologically, so comparisons work; enable the `chrono` feature for arithmetic.
+#[cfg(not(feature = "chrono"))]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct WooDateTimeUtc([u8; 19]);
+
+#[cfg(feature = "chrono")]
+impl WooDateTimeUtc {
+    pub fn now() -> Self {
+        WooDateTimeUtc(std::time::SystemTime::now().into())
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl std::ops::Deref for WooDateTime {
+    type Target = chrono::NaiveDateTime;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl std::ops::Deref for WooDateTimeUtc {
+    type Target = chrono::DateTime<chrono::Utc>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl std::ops::Add<std::time::Duration> for WooDateTimeUtc {
+    type Output = WooDateTimeUtc;
+
+    fn add(self, rhs: std::time::Duration) -> WooDateTimeUtc {
+        WooDateTimeUtc(self.0 + chrono::TimeDelta::seconds(rhs.as_secs() as i64))
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl std::ops::Sub<std::time::Duration> for WooDateTimeUtc {
+    type Output = WooDateTimeUtc;
+
+    fn sub(self, rhs: std::time::Duration) -> WooDateTimeUtc {
+        WooDateTimeUtc(self.0 - chrono::TimeDelta::seconds(rhs.as_secs() as i64))
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<chrono::NaiveDateTime> for WooDateTime {
+    fn from(value: chrono::NaiveDateTime) -> Self {
+        WooDateTime(value.with_nanosecond(0).unwrap_or(value))
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<WooDateTime> for chrono::NaiveDateTime {
+    fn from(value: WooDateTime) -> Self {
+        value.0
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl<Tz: chrono::TimeZone> From<chrono::DateTime<Tz>> for WooDateTimeUtc {
+    fn from(value: chrono::DateTime<Tz>) -> Self {
+        let value = value.with_timezone(&chrono::Utc);
+        WooDateTimeUtc(value.with_nanosecond(0).unwrap_or(value))
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<WooDateTimeUtc> for chrono::DateTime<chrono::Utc> {
+    fn from(value: WooDateTimeUtc) -> Self {
+        value.0
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<service_sdk::rust_extensions::date_time::DateTimeAsMicroseconds> for WooDateTimeUtc {
+    fn from(value: service_sdk::rust_extensions::date_time::DateTimeAsMicroseconds) -> Self {
+        let seconds = value.unix_microseconds.div_euclid(1_000_000);
+        WooDateTimeUtc(chrono::DateTime::from_timestamp(seconds, 0).unwrap_or_default())
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<WooDateTimeUtc> for service_sdk::rust_extensions::date_time::DateTimeAsMicroseconds {
+    fn from(value: WooDateTimeUtc) -> Self {
+        Self::new(value.0.timestamp() * 1_000_000)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl fmt::Display for WooDateTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.format(FORMAT))
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl fmt::Display for WooDateTimeUtc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.format(FORMAT))
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl FromStr for WooDateTime {
+    type Err = DateTimeParseError;
+
+    /// An offset suffix is accepted but ignored, the wall clock time is kept.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (local, _) = parse(s).ok_or_else(|| DateTimeParseError(s.to_string()))?;
+        Ok(WooDateTime::from(local))
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl FromStr for WooDateTimeUtc {
+    type Err = DateTimeParseError;
+
+    /// Without an offset suffix the time is taken as UTC.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (local, offset) = parse(s).ok_or_else(|| DateTimeParseError(s.to_string()))?;
+        let offset = offset.unwrap_or(chrono::FixedOffset::east_opt(0).unwrap());
+        let date = local
+            .and_local_timezone(offset)
+            .single()
+            .ok_or_else(|| DateTimeParseError(s.to_string()))?;
+        Ok(WooDateTimeUtc::from(date))
+    }
+}
+
+/// The wall clock time and the offset if one was given.
+#[cfg(feature = "chrono")]
+fn parse(value: &str) -> Option<(chrono::NaiveDateTime, Option<chrono::FixedOffset>)> {
+    use chrono::{DateTime, NaiveDate, NaiveDateTime};
+
+    let value = value.trim();
+    for format in ["%Y-%m-%dT%H:%M:%S%.f%#z", "%Y-%m-%d %H:%M:%S%.f%#z"] {
+        if let Ok(date) = DateTime::parse_from_str(value, format) {
+            return Some((date.naive_local(), Some(*date.offset())));
+        }
+    }
+    let (value, utc) = match value.strip_suffix(['Z', 'z']) {
+        Some(value) => (value, true),
+        None => (value, false),
+    };
+    let offset = utc.then(|| chrono::FixedOffset::east_opt(0).unwrap());
+    for format in ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"] {
+        if let Ok(date) = NaiveDateTime::parse_from_str(value, format) {
+            return Some((date, offset));
+        }
+    }
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?;
+    Some((date.and_hms_opt(0, 0, 0)?, offset))
+}
+
+#[cfg(not(feature = "chrono"))]
+macro_rules! impl_text {
+    ($name:ident) => {
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                // Only ASCII is ever stored.
+                f.write_str(std::str::from_utf8(&self.0).unwrap_or_default())
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = DateTimeParseError;
+
+            /// Only `YYYY-MM-DDTHH:MM:SS` (or with a space), optionally followed by
+            /// fractional seconds and `Z` or `+00:00`, which are dropped.
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                parse_text(s)
+                    .map($name)
+                    .ok_or_else(|| DateTimeParseError(s.to_string()))
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "chrono"))]
+impl_text!(WooDateTime);
+#[cfg(not(feature = "chrono"))]
+impl_text!(WooDateTimeUtc);
+
+#[cfg(not(feature = "chrono"))]
+fn parse_text(value: &str) -> Option<[u8; 19]> {
+    let value = value.trim();
+    let bytes = value.as_bytes();
+    if bytes.len() < 19 {
+        return None;
+    }
+    let mut text = [0; 19];
+    text.copy_from_slice(&bytes[..19]);
+    if text[10] == b' ' {
+        text[10] = b'T';
+    }
+    for (index, byte) in text.iter().enumerate() {
+        let valid = match index {
+            4 | 7 => *byte == b'-',
+            10 => *byte == b'T',
+            13 | 16 => *byte == b':',
+            _ => byte.is_ascii_digit(),
+        };
+        if !valid {
+            return None;
+        }
+    }
+    let mut rest = &value[19..];
+    if let Some(fraction) = rest.strip_prefix('.') {
+        rest = fraction.trim_start_matches(|c: char| c.is_ascii_digit());
+    }
+    match rest {
+        "" | "Z" | "z" | "+00:00" | "+0000" => Some(text),
+        _ => None,
+    }
+}
+
+macro_rules! impl_serde {
+    ($name:ident) => {
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{}({})", stringify!($name), self)
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let value = String::deserialize(deserializer)?;
+                value.parse().map_err(de::Error::custom)
+            }
+        }
+    };
+}
+
+impl_serde!(WooDateTime);
+impl_serde!(WooDateTimeUtc);
+
+/// Dates for update bodies, where `None` leaves the date alone and `Some(None)` clears it.
+/// WooCommerce skips `null` values, so a cleared date is sent as `""`. Use it with
+/// `skip_serializing_if = "Option::is_none"`.
+pub(crate) mod clearable_date {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use serde_json::Value;
+
+    use super::WooDateTimeUtc;
+
+    pub fn serialize<S: Serializer>(
+        value: &Option<Option<WooDateTimeUtc>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(Some(date)) => serializer.collect_str(date),
+            Some(None) => serializer.serialize_str(""),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Option<WooDateTimeUtc>>, D::Error> {
+        match Value::deserialize(deserializer)? {
+            Value::Null => Ok(Some(None)),
+            Value::String(value) if value.trim().is_empty() => Ok(Some(None)),
+            Value::String(value) => value
+                .parse()
+                .map(|date| Some(Some(date)))
+                .map_err(serde::de::Error::custom),
+            value => Err(serde::de::Error::custom(format!(
+                "expected a date, got {}",
+                value
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_parse_woocommerce_formats() {
+        let expected: WooDateTimeUtc = chrono::NaiveDate::from_ymd_opt(2024, 2, 29)
+            .and_then(|date| date.and_hms_opt(13, 5, 9))
+            .unwrap()
+            .and_utc()
+            .into();
+        for value in [
+            "2024-02-29T13:05:09",
+            "2024-02-29 13:05:09",
+            "2024-02-29T13:05:09Z",
+            "2024-02-29T13:05:09.123456",
+            "2024-02-29T15:05:09+02:00",
+            "2024-02-29T08:05:09-0500",
+        ] {
+            assert_eq!(
+                value.parse::<WooDateTimeUtc>().unwrap(),
+                expected,
+                "{}",
+                value
+            );
+        }
+        assert_eq!(expected.timestamp(), 1_709_211_909);
+
+        let local: WooDateTime = "2024-02-29T15:05:09+02:00".parse().unwrap();
+        assert_eq!(local.to_string(), "2024-02-29T15:05:09");
+
+        for value in [
+            "",
+            "2023-02-29T00:00:00",
+            "2024-13-01",
+            "2024-01-01T24:00:00",
+            "2024-01-01Tx",
+        ] {
+            assert!(value.parse::<WooDateTimeUtc>().is_err(), "{}", value);
+        }
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_serialize_without_offset() {
+        let epoch: WooDateTimeUtc = "1970-01-01T00:00:00".parse().unwrap();
+        let date = epoch + std::time::Duration::from_secs(86_400 + 61);
+        assert_eq!(
+            serde_json::to_string(&date).unwrap(),
+            r#""1970-01-02T00:01:01""#
+        );
+        let date: Option<WooDateTimeUtc> = serde_json::from_str("null").unwrap();
+        assert_eq!(date, None);
+    }
+
+    #[test]
+    fn test_ordering_is_chronological() {
+        let earlier: WooDateTimeUtc = "2023-12-31T23:59:59".parse().unwrap();
+        let later: WooDateTimeUtc = "2024-01-01 01:00:00".parse().unwrap();
+        assert!(earlier < later);
+        assert_eq!(later.to_string(), "2024-01-01T01:00:00");
+    }
+}
@@ -0,0 +1,366 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::{Coupon, MetaData, NewMetaData, Order, Product};
+
+/// Typed access to the `meta_data` list of a resource.
+///
+/// Changes are local, send the resource back with an update to store them.
+pub trait HasMetaData {
+    fn meta_data(&self) -> &[MetaData];
+
+    fn meta_data_mut(&mut self) -> &mut Vec<MetaData>;
+
+    /// Entries marked for deletion by [`remove_meta`](Self::remove_meta) are skipped.
+    fn get_meta_value(&self, key: &str) -> Option<&Value> {
+        self.meta_data()
+            .iter()
+            .find(|meta| meta.key == key && !meta.value.is_null())
+            .map(|meta| &meta.value)
+    }
+
+    /// `Ok(None)` when the key is not set.
+    fn get_meta<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, serde_json::Error> {
+        match self.get_meta_value(key) {
+            Some(value) => Ok(Some(T::deserialize(value)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Replaces the value of an existing entry, keeping its id, or adds a new one.
+    fn set_meta<T: Serialize>(&mut self, key: &str, value: T) -> Result<(), serde_json::Error> {
+        let value = serde_json::to_value(value)?;
+        let meta_data = self.meta_data_mut();
+        match meta_data.iter_mut().find(|meta| meta.key == key) {
+            Some(meta) => meta.value = value,
+            None => meta_data.push(NewMetaData::new(key, value).into()),
+        }
+        Ok(())
+    }
+
+    /// Removes every entry with `key` and returns the first value.
+    ///
+    /// WooCommerce only deletes meta that is sent as `{id, key, value: null}`, so saved
+    /// entries stay in the list with a `null` value until the next update. Entries that
+    /// were never saved are dropped.
+    fn remove_meta(&mut self, key: &str) -> Option<Value> {
+        let mut removed = None;
+        self.meta_data_mut().retain_mut(|meta| {
+            if meta.key != key {
+                return true;
+            }
+            let value = std::mem::take(&mut meta.value);
+            if removed.is_none() && !value.is_null() {
+                removed = Some(value);
+            }
+            meta.id.is_some()
+        });
+        removed
+    }
+
+    fn get_meta_schema<S: MetaSchema>(&self) -> Result<S, serde_json::Error> {
+        S::from_meta(self.meta_data())
+    }
+
+    fn set_meta_schema<S: MetaSchema>(&mut self, schema: &S) -> Result<(), serde_json::Error> {
+        for meta in schema.to_meta()? {
+            self.set_meta(&meta.key, meta.value)?;
+        }
+        Ok(())
+    }
+}
+
+impl HasMetaData for Order {
+    fn meta_data(&self) -> &[MetaData] {
+        &self.meta_data
+    }
+
+    fn meta_data_mut(&mut self) -> &mut Vec<MetaData> {
+        &mut self.meta_data
+    }
+}
+
+impl HasMetaData for Product {
+    fn meta_data(&self) -> &[MetaData] {
+        &self.meta_data
+    }
+
+    fn meta_data_mut(&mut self) -> &mut Vec<MetaData> {
+        &mut self.meta_data
+    }
+}
+
+impl HasMetaData for Coupon {
+    fn meta_data(&self) -> &[MetaData] {
+        &self.meta_data
+    }
+
+    fn meta_data_mut(&mut self) -> &mut Vec<MetaData> {
+        &mut self.meta_data
+    }
+}
+
+/// A struct stored as one meta entry per field, keyed by the serialized field name.
+///
+/// Implement it with an empty `impl`, rename fields with `#[serde(rename = "...")]`
+/// to match existing keys. Missing keys must be optional or have a serde default.
+/// WordPress hands numbers and booleans back as strings (`"50000"`, `"1"`, `"yes"`),
+/// those are converted to the field's type when reading.
+pub trait MetaSchema: Serialize + DeserializeOwned {
+    fn from_meta(meta_data: &[MetaData]) -> Result<Self, serde_json::Error> {
+        let mut fields = Map::new();
+        for meta in meta_data.iter().filter(|meta| !meta.value.is_null()) {
+            // The first entry wins, like `get_meta`.
+            fields
+                .entry(meta.key.clone())
+                .or_insert_with(|| meta.value.clone());
+        }
+        let mut value = Value::Object(fields);
+
+        // Retype one mismatched value per attempt, each value at most once.
+        let limit = meta_data.len() + 1;
+        for _ in 0..limit {
+            let err = match serde_path_to_error::deserialize::<_, Self>(&value) {
+                Ok(schema) => return Ok(schema),
+                Err(err) => err,
+            };
+            let path = err.path().clone();
+            let inner = err.into_inner();
+            if !retype_at(&mut value, &path, &inner.to_string()) {
+                return Err(inner);
+            }
+        }
+        serde_json::from_value(value)
+    }
+
+    /// `None` fields are left out rather than written as `null`.
+    fn to_meta(&self) -> Result<Vec<NewMetaData>, serde_json::Error> {
+        let value = serde_json::to_value(self)?;
+        let Value::Object(fields) = value else {
+            return Err(serde::ser::Error::custom(
+                "meta schema must serialize to an object",
+            ));
+        };
+        Ok(fields
+            .into_iter()
+            .filter(|(_, value)| !value.is_null())
+            .map(|(key, value)| NewMetaData { key, value })
+            .collect())
+    }
+}
+
+/// Converts the scalar at `path` to what the error says was expected. `false` if it
+/// can't be converted.
+fn retype_at(value: &mut Value, path: &serde_path_to_error::Path, error: &str) -> bool {
+    use serde_path_to_error::Segment;
+
+    let Some((_, expected)) = error.rsplit_once(", expected ") else {
+        return false;
+    };
+    let mut current = value;
+    for segment in path.iter() {
+        let next = match segment {
+            Segment::Seq { index } => current.get_mut(*index),
+            Segment::Map { key } => current.get_mut(key.as_str()),
+            _ => None,
+        };
+        match next {
+            Some(next) => current = next,
+            None => return false,
+        }
+    }
+
+    let retyped = match (&*current, expected) {
+        (Value::String(text), expected) if expected.contains("bool") => {
+            match text.trim().to_ascii_lowercase().as_str() {
+                "1" | "true" | "yes" | "on" => Value::Bool(true),
+                "" | "0" | "false" | "no" | "off" => Value::Bool(false),
+                _ => return false,
+            }
+        }
+        (Value::Number(number), expected) if expected.contains("bool") => match number.as_u64() {
+            Some(0) => Value::Bool(false),
+            Some(1) => Value::Bool(true),
+            _ => return false,
+        },
+        (Value::String(text), expected) if is_numeric(expected) => {
+            match text.trim().parse::<serde_json::Number>() {
+                Ok(number) => Value::Number(number),
+                Err(_) => return false,
+            }
+        }
+        (Value::Number(_) | Value::Bool(_), expected) if expected.contains("string") => {
+            Value::String(current.to_string())
+        }
+        _ => return false,
+    };
+    *current = retyped;
+    true
+}
+
+fn is_numeric(expected: &str) -> bool {
+    let expected = expected.trim_start_matches("a ").trim_start_matches("an ");
+    matches!(
+        expected,
+        "u8" | "u16"
+            | "u32"
+            | "u64"
+            | "u128"
+            | "usize"
+            | "i8"
+            | "i16"
+            | "i32"
+            | "i64"
+            | "i128"
+            | "isize"
+            | "f32"
+            | "f64"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use super::*;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct ChallengeMeta {
+        account_size: u64,
+        platform: String,
+        #[serde(default)]
+        phase: Option<u8>,
+    }
+
+    impl MetaSchema for ChallengeMeta {}
+
+    fn order() -> Order {
+        let mut order: Order =
+            serde_json::from_str(include_str!("../../fixtures/order.json")).unwrap();
+        order.meta_data = vec![
+            MetaData {
+                id: Some(1),
+                key: "account_size".to_string(),
+                value: json!("50000"),
+                extra: Map::new(),
+            },
+            MetaData {
+                id: Some(2),
+                key: "platform".to_string(),
+                value: json!("mt5"),
+                extra: Map::new(),
+            },
+        ];
+        order
+    }
+
+    #[test]
+    fn test_get_and_set_meta() {
+        let mut order = order();
+        assert_eq!(
+            order.get_meta::<String>("platform").unwrap().as_deref(),
+            Some("mt5")
+        );
+        assert_eq!(order.get_meta::<u8>("phase").unwrap(), None);
+        assert!(order.get_meta::<u64>("platform").is_err());
+
+        order.set_meta("platform", "ctrader").unwrap();
+        order.set_meta("phase", 2).unwrap();
+        assert_eq!(order.meta_data[1].id, Some(2));
+        assert_eq!(order.meta_data[1].value, json!("ctrader"));
+        assert_eq!(order.meta_data[2].id, None);
+        assert_eq!(order.get_meta::<u8>("phase").unwrap(), Some(2));
+
+        assert_eq!(order.remove_meta("phase"), Some(json!(2)));
+        assert_eq!(order.remove_meta("phase"), None);
+        assert_eq!(order.meta_data.len(), 2);
+
+        // Saved entries are sent back with a null value so WooCommerce deletes them.
+        assert_eq!(order.remove_meta("account_size"), Some(json!("50000")));
+        assert_eq!(order.get_meta::<String>("account_size").unwrap(), None);
+        let body = serde_json::to_value(&order.meta_data).unwrap();
+        assert_eq!(
+            body[0],
+            json!({"id": 1, "key": "account_size", "value": null})
+        );
+    }
+
+    #[test]
+    fn test_meta_schema_round_trip() {
+        let mut order = order();
+        // Numbers stored by WordPress come back as strings.
+        assert_eq!(
+            order.get_meta_schema::<ChallengeMeta>().unwrap(),
+            ChallengeMeta {
+                account_size: 50000,
+                platform: "mt5".to_string(),
+                phase: None,
+            }
+        );
+
+        let meta = ChallengeMeta {
+            account_size: 100000,
+            platform: "mt5".to_string(),
+            phase: Some(1),
+        };
+        order.set_meta_schema(&meta).unwrap();
+        assert_eq!(order.meta_data.len(), 3);
+        assert_eq!(order.meta_data[0].id, Some(1));
+        assert_eq!(order.get_meta_schema::<ChallengeMeta>().unwrap(), meta);
+
+        let created = ChallengeMeta {
+            phase: None,
+            ..meta
+        }
+        .to_meta()
+        .unwrap();
+        assert_eq!(
+            created,
+            vec![
+                NewMetaData::new("account_size", 100000),
+                NewMetaData::new("platform", "mt5"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_meta_schema_string_scalars() {
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Flags {
+            funded: bool,
+            payout_share: f64,
+            label: String,
+        }
+
+        impl MetaSchema for Flags {}
+
+        let meta = |key: &str, value: Value| MetaData {
+            id: None,
+            key: key.to_string(),
+            value,
+            extra: Map::new(),
+        };
+        let flags = Flags::from_meta(&[
+            meta("funded", json!("yes")),
+            meta("payout_share", json!(" 0.8 ")),
+            meta("label", json!(7)),
+        ])
+        .unwrap();
+        assert_eq!(
+            flags,
+            Flags {
+                funded: true,
+                payout_share: 0.8,
+                label: "7".to_string(),
+            }
+        );
+        assert!(Flags::from_meta(&[
+            meta("funded", json!("maybe")),
+            meta("payout_share", json!("1")),
+            meta("label", json!("x")),
+        ])
+        .is_err());
+    }
+}
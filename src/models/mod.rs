@@ -9,4 +9,18 @@ mod coupon;
 pub use coupon::*;
 
 mod common;
-pub use common::*;
\ No newline at end of file
+pub use common::*;
+
+mod meta;
+pub use meta::*;
+
+mod money;
+pub use money::*;
+
+mod datetime;
+pub use datetime::*;
+
+mod ids;
+pub use ids::*;
+mod webhook;
+pub use webhook::*;
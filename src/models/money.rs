@@ -0,0 +1,337 @@
+use std::fmt;
+#[cfg(not(feature = "rust_decimal"))]
+use std::str::FromStr;
+
+#[cfg(not(feature = "rust_decimal"))]
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MoneyError {
+    Invalid(String),
+    Overflow,
+    CurrencyMismatch(String, String),
+}
+
+impl fmt::Display for MoneyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoneyError::Invalid(value) => write!(f, "invalid amount {:?}", value),
+            MoneyError::Overflow => write!(f, "amount overflow"),
+            MoneyError::CurrencyMismatch(left, right) => {
+                write!(f, "currency mismatch: {} and {}", left, right)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MoneyError {}
+
+/// Exact decimal amount. WooCommerce sends amounts as strings (`"18.00"`) or, for a few
+/// fields such as `LineItem.price`, as JSON numbers; both deserialize. It serializes back as
+/// a string keeping the number of decimals it was created with.
+#[cfg(feature = "rust_decimal")]
+pub type Money = rust_decimal::Decimal;
+
+/// An amount exactly as WooCommerce sent it, string or number, serialized back as a string.
+/// Enable the `rust_decimal` feature to get a decimal with arithmetic instead.
+///
+/// Only offers what `rust_decimal::Decimal` offers too (`Display`, `FromStr`, serde,
+/// `From<i64>`), so code written against it keeps compiling when another crate in the
+/// build turns the feature on. Read the text with `to_string()`.
+#[cfg(not(feature = "rust_decimal"))]
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct Money(String);
+
+#[cfg(not(feature = "rust_decimal"))]
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(not(feature = "rust_decimal"))]
+impl fmt::Debug for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Money({})", self.0)
+    }
+}
+
+/// Accepts a plain decimal such as `-12.50`, nothing is rounded or reformatted.
+#[cfg(not(feature = "rust_decimal"))]
+impl FromStr for Money {
+    type Err = MoneyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value = s.trim();
+        let digits = value.strip_prefix(['-', '+']).unwrap_or(value);
+        let (int_part, frac_part) = digits.split_once('.').unwrap_or((digits, ""));
+        let valid = !(int_part.is_empty() && frac_part.is_empty())
+            && int_part
+                .chars()
+                .chain(frac_part.chars())
+                .all(|c| c.is_ascii_digit());
+        if !valid {
+            return Err(MoneyError::Invalid(s.to_string()));
+        }
+        Ok(Money(value.to_string()))
+    }
+}
+
+#[cfg(not(feature = "rust_decimal"))]
+impl From<i64> for Money {
+    fn from(value: i64) -> Self {
+        Money(value.to_string())
+    }
+}
+
+#[cfg(not(feature = "rust_decimal"))]
+impl Serialize for Money {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+#[cfg(not(feature = "rust_decimal"))]
+struct MoneyVisitor;
+
+#[cfg(not(feature = "rust_decimal"))]
+impl<'de> de::Visitor<'de> for MoneyVisitor {
+    type Value = Money;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a decimal amount as a string or a number")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Money, E> {
+        value.parse().map_err(E::custom)
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Money, E> {
+        Ok(Money(value.to_string()))
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Money, E> {
+        Ok(Money(value.to_string()))
+    }
+
+    /// The shortest representation of the float, so `9.95` stays `9.95`.
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<Money, E> {
+        if !value.is_finite() {
+            return Err(E::custom(MoneyError::Invalid(value.to_string())));
+        }
+        Ok(Money(value.to_string()))
+    }
+}
+
+#[cfg(not(feature = "rust_decimal"))]
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(MoneyVisitor)
+    }
+}
+
+/// Serde helper for amounts WooCommerce leaves empty, like `sale_price` or `maximum_amount`.
+/// `""` and `null` read as `None`, `None` is written as `""`.
+///
+/// ```ignore
+/// #[serde(default, with = "crate::optional_money")]
+/// pub sale_price: Option<Money>,
+/// ```
+pub(crate) mod optional_money {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use serde_json::Value;
+
+    use super::Money;
+
+    pub fn serialize<S: Serializer>(
+        value: &Option<Money>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(value) => serializer.collect_str(value),
+            None => serializer.serialize_str(""),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Money>, D::Error> {
+        match Value::deserialize(deserializer)? {
+            Value::Null => Ok(None),
+            Value::String(value) if value.trim().is_empty() => Ok(None),
+            value => <Money as Deserialize>::deserialize(value)
+                .map(Some)
+                .map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+/// [`optional_money`] for update bodies, where `None` leaves the amount alone and
+/// `Some(None)` clears it. Use it with `skip_serializing_if = "Option::is_none"`.
+pub(crate) mod clearable_money {
+    use serde::{Deserializer, Serializer};
+
+    use super::{optional_money, Money};
+
+    pub fn serialize<S: Serializer>(
+        value: &Option<Option<Money>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(value) => optional_money::serialize(value, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Option<Money>>, D::Error> {
+        optional_money::deserialize(deserializer).map(Some)
+    }
+}
+
+/// Decimals used for amounts in `currency` (ISO 4217), 2 unless listed.
+pub fn currency_decimals(currency: &str) -> u32 {
+    match currency.to_ascii_uppercase().as_str() {
+        "BIF" | "CLP" | "DJF" | "GNF" | "ISK" | "JPY" | "KMF" | "KRW" | "PYG" | "RWF" | "UGX"
+        | "VND" | "VUV" | "XAF" | "XOF" | "XPF" => 0,
+        "BHD" | "IQD" | "JOD" | "KWD" | "LYD" | "OMR" | "TND" => 3,
+        _ => 2,
+    }
+}
+
+/// Rounds half away from zero to the decimals used by `currency`, like PHP's `round()`
+/// which WooCommerce uses. `10.5` in USD becomes `10.50`.
+#[cfg(feature = "rust_decimal")]
+pub fn round_for(amount: Money, currency: &str) -> Money {
+    let decimals = currency_decimals(currency);
+    let mut rounded = amount.round_dp_with_strategy(
+        decimals,
+        rust_decimal::RoundingStrategy::MidpointAwayFromZero,
+    );
+    rounded.rescale(decimals);
+    rounded
+}
+
+/// An amount tagged with its currency, so amounts of different orders can't be mixed.
+#[cfg(feature = "rust_decimal")]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CurrencyAmount {
+    pub amount: Money,
+    pub currency: String,
+}
+
+#[cfg(feature = "rust_decimal")]
+impl CurrencyAmount {
+    pub fn new(amount: Money, currency: &str) -> Self {
+        Self {
+            amount,
+            currency: currency.to_ascii_uppercase(),
+        }
+    }
+
+    pub fn zero(currency: &str) -> Self {
+        Self::new(Money::ZERO, currency)
+    }
+
+    pub fn checked_add(&self, rhs: &CurrencyAmount) -> Result<Self, MoneyError> {
+        self.check_currency(rhs)?;
+        let amount = self
+            .amount
+            .checked_add(rhs.amount)
+            .ok_or(MoneyError::Overflow)?;
+        Ok(Self::new(amount, &self.currency))
+    }
+
+    pub fn checked_sub(&self, rhs: &CurrencyAmount) -> Result<Self, MoneyError> {
+        self.check_currency(rhs)?;
+        let amount = self
+            .amount
+            .checked_sub(rhs.amount)
+            .ok_or(MoneyError::Overflow)?;
+        Ok(Self::new(amount, &self.currency))
+    }
+
+    /// The amount rounded to the currency's decimals.
+    pub fn rounded(&self) -> Money {
+        round_for(self.amount, &self.currency)
+    }
+
+    fn check_currency(&self, rhs: &CurrencyAmount) -> Result<(), MoneyError> {
+        if self.currency != rhs.currency {
+            return Err(MoneyError::CurrencyMismatch(
+                self.currency.clone(),
+                rhs.currency.clone(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "rust_decimal")]
+impl fmt::Display for CurrencyAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.amount, self.currency)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+
+    fn money(value: &str) -> Money {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn test_serde() {
+        let values: Vec<Money> = serde_json::from_str(r#"["10.00", 9, 9.95, "-3.5"]"#).unwrap();
+        assert_eq!(values[1], money("9"));
+        assert_eq!(values[2].to_string(), "9.95");
+        assert_eq!(
+            serde_json::to_string(&values).unwrap(),
+            r#"["10.00","9","9.95","-3.5"]"#
+        );
+        assert!(serde_json::from_str::<Money>(r#""12a""#).is_err());
+
+        #[derive(Deserialize, Serialize)]
+        struct Price {
+            #[serde(default, with = "optional_money")]
+            sale_price: Option<Money>,
+        }
+        let price: Price = serde_json::from_str(r#"{"sale_price":""}"#).unwrap();
+        assert_eq!(price.sale_price, None);
+        assert_eq!(
+            serde_json::to_string(&price).unwrap(),
+            r#"{"sale_price":""}"#
+        );
+    }
+
+    #[cfg(feature = "rust_decimal")]
+    #[test]
+    fn test_currency_rounding() {
+        let total: Money = ["0.10", "0.20", "0.30"].iter().map(|v| money(v)).sum();
+        assert_eq!(total.to_string(), "0.60");
+        assert_eq!(round_for(money("2.675"), "USD"), money("2.68"));
+        assert_eq!(round_for(money("-2.675"), "USD"), money("-2.68"));
+        assert_eq!(round_for(money("10.5"), "USD").to_string(), "10.50");
+
+        let usd = CurrencyAmount::new(money("10.005"), "usd");
+        assert_eq!(usd.rounded().to_string(), "10.01");
+        assert_eq!(
+            CurrencyAmount::new(money("1500.4"), "JPY")
+                .rounded()
+                .to_string(),
+            "1500"
+        );
+        assert_eq!(
+            usd.checked_add(&CurrencyAmount::new(money("1"), "EUR")),
+            Err(MoneyError::CurrencyMismatch(
+                "USD".to_string(),
+                "EUR".to_string()
+            ))
+        );
+    }
+}
@@ -1,78 +1,321 @@
+use std::fmt;
+
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 
-use crate::Links;
+use crate::{
+    BuilderError, CustomerId, Links, Money, OrderBy, OrderId, ProductId, SortOrder, VariationId,
+    WooDateTime, WooDateTimeUtc,
+};
 
-use super::common::MetaData;
+use super::common::{MetaData, NewMetaData, QueryPairs};
 
-//This is enough for us to create an order
-#[derive(Serialize, Deserialize, Debug)]
+/// Body of `POST /orders`. [`CreateOrder::builder`] checks the required pieces.
+#[derive(Serialize, Deserialize, Debug, Default)]
 pub struct CreateOrder {
-    /* pub payment_method: String,
-    pub payment_method_title: String, */
+    /// Defaults to `pending`, or `processing` with `set_paid`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<OrderStatus>,
+    /// ISO 4217 code, defaults to the store currency.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
     pub set_paid: bool,
     pub billing: ContactDetails,
-    pub meta_data: Vec<MetaData>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shipping: Option<ContactDetails>,
+    /// Gateway id, e.g. `bacs` or `stripe`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payment_method: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payment_method_title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transaction_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub customer_note: Option<String>,
+    pub meta_data: Vec<NewMetaData>,
     pub line_items: Vec<CreateLineItem>,
-    pub customer_id: i32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub shipping_lines: Vec<CreateShippingLine>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fee_lines: Vec<CreateFeeLine>,
+    pub customer_id: CustomerId,
     pub coupon_lines: Vec<CouponLineCreate>,
 }
 
+impl CreateOrder {
+    pub fn builder() -> CreateOrderBuilder {
+        CreateOrderBuilder::default()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct CreateOrderBuilder {
+    order: CreateOrder,
+}
+
+impl CreateOrderBuilder {
+    pub fn status(mut self, status: OrderStatus) -> Self {
+        self.order.status = Some(status);
+        self
+    }
+
+    pub fn currency(mut self, currency: &str) -> Self {
+        self.order.currency = Some(currency.to_string());
+        self
+    }
+
+    pub fn set_paid(mut self, set_paid: bool) -> Self {
+        self.order.set_paid = set_paid;
+        self
+    }
+
+    pub fn customer_id(mut self, customer_id: CustomerId) -> Self {
+        self.order.customer_id = customer_id;
+        self
+    }
+
+    pub fn billing(mut self, billing: ContactDetails) -> Self {
+        self.order.billing = billing;
+        self
+    }
+
+    pub fn shipping(mut self, shipping: ContactDetails) -> Self {
+        self.order.shipping = Some(shipping);
+        self
+    }
+
+    /// Orders paid outside of WooCommerce can use any id, e.g. `bacs` or `cod`.
+    pub fn payment_method(mut self, method: &str, title: &str) -> Self {
+        self.order.payment_method = Some(method.to_string());
+        self.order.payment_method_title = Some(title.to_string());
+        self
+    }
+
+    pub fn transaction_id(mut self, transaction_id: &str) -> Self {
+        self.order.transaction_id = Some(transaction_id.to_string());
+        self
+    }
+
+    pub fn customer_note(mut self, note: &str) -> Self {
+        self.order.customer_note = Some(note.to_string());
+        self
+    }
+
+    pub fn meta(mut self, key: &str, value: impl Into<Value>) -> Self {
+        self.order.meta_data.push(NewMetaData::new(key, value));
+        self
+    }
+
+    pub fn line_item(mut self, line_item: CreateLineItem) -> Self {
+        self.order.line_items.push(line_item);
+        self
+    }
+
+    pub fn product(self, product_id: ProductId, quantity: i32) -> Self {
+        self.line_item(CreateLineItem {
+            product_id: Some(product_id),
+            quantity,
+            ..Default::default()
+        })
+    }
+
+    pub fn variation(
+        self,
+        product_id: ProductId,
+        variation_id: VariationId,
+        quantity: i32,
+    ) -> Self {
+        self.line_item(CreateLineItem {
+            product_id: Some(product_id),
+            variation_id: Some(variation_id),
+            quantity,
+            ..Default::default()
+        })
+    }
+
+    /// WooCommerce looks the product up by its SKU.
+    pub fn sku(self, sku: &str, quantity: i32) -> Self {
+        self.line_item(CreateLineItem {
+            sku: Some(sku.to_string()),
+            quantity,
+            ..Default::default()
+        })
+    }
+
+    pub fn shipping_line(mut self, method_id: &str, method_title: &str, total: Money) -> Self {
+        self.order.shipping_lines.push(CreateShippingLine {
+            method_id: method_id.to_string(),
+            method_title: method_title.to_string(),
+            total,
+            meta_data: vec![],
+        });
+        self
+    }
+
+    pub fn fee(mut self, name: &str, total: Money) -> Self {
+        self.order.fee_lines.push(CreateFeeLine {
+            name: name.to_string(),
+            total,
+            ..Default::default()
+        });
+        self
+    }
+
+    pub fn coupon(mut self, code: &str) -> Self {
+        self.order.coupon_lines.push(CouponLineCreate {
+            code: code.to_string(),
+            exclude_product_ids: None,
+        });
+        self
+    }
+
+    pub fn build(self) -> Result<CreateOrder, BuilderError> {
+        let order = self.order;
+        if order.line_items.is_empty() {
+            return Err(BuilderError::Missing("line_items"));
+        }
+        for (index, line) in order.line_items.iter().enumerate() {
+            let field = format!("line_items[{}]", index);
+            if line.product_id.is_none() && line.sku.is_none() {
+                return Err(BuilderError::invalid(field, "needs a product_id or sku"));
+            }
+            if line.quantity <= 0 {
+                return Err(BuilderError::invalid(field, "quantity must be positive"));
+            }
+        }
+        if let Some(currency) = &order.currency {
+            if currency.len() != 3 || !currency.chars().all(|c| c.is_ascii_uppercase()) {
+                return Err(BuilderError::invalid(
+                    "currency",
+                    "expected an ISO 4217 code",
+                ));
+            }
+        }
+        if order.transaction_id.is_some() && order.payment_method.is_none() {
+            return Err(BuilderError::invalid(
+                "transaction_id",
+                "set together with a payment_method",
+            ));
+        }
+        for (index, line) in order.fee_lines.iter().enumerate() {
+            if line.name.trim().is_empty() {
+                return Err(BuilderError::invalid(
+                    format!("fee_lines[{}]", index),
+                    "name is required",
+                ));
+            }
+        }
+        Ok(order)
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Order {
-    pub id: i32,
-    pub parent_id: i32,
+    pub id: OrderId,
+    #[serde(default)]
+    pub parent_id: OrderId,
     pub status: OrderStatus,
     pub currency: String,
+    #[serde(default)]
     pub version: String,
+    #[serde(default)]
     pub prices_include_tax: bool,
-    pub date_created: String,
-    pub date_modified: String,
-    pub discount_total: String,
-    pub discount_tax: String,
-    pub shipping_total: String,
-    pub shipping_tax: String,
-    pub cart_tax: String,
-    pub total: String,
-    pub total_tax: String,
-    pub customer_id: i32,
+    pub date_created: WooDateTime,
+    pub date_modified: WooDateTime,
+    #[serde(default)]
+    pub discount_total: Money,
+    #[serde(default)]
+    pub discount_tax: Money,
+    #[serde(default)]
+    pub shipping_total: Money,
+    #[serde(default)]
+    pub shipping_tax: Money,
+    #[serde(default)]
+    pub cart_tax: Money,
+    pub total: Money,
+    #[serde(default)]
+    pub total_tax: Money,
+    #[serde(default)]
+    pub customer_id: CustomerId,
+    #[serde(default)]
     pub order_key: String,
+    #[serde(default)]
     pub billing: ContactDetails,
+    #[serde(default)]
     pub shipping: ContactDetails,
+    #[serde(default)]
     pub payment_method: String,
+    #[serde(default)]
     pub payment_method_title: String,
+    #[serde(default)]
     pub transaction_id: String,
+    #[serde(default)]
     pub customer_ip_address: String,
+    #[serde(default)]
     pub customer_user_agent: String,
+    #[serde(default)]
     pub created_via: String,
+    #[serde(default)]
     pub customer_note: String,
-    pub date_completed: Option<String>,
-    pub date_paid: Option<String>,
+    pub date_completed: Option<WooDateTime>,
+    pub date_paid: Option<WooDateTime>,
+    #[serde(default)]
     pub cart_hash: String,
+    #[serde(default)]
     pub number: String,
+    #[serde(default)]
     pub meta_data: Vec<MetaData>,
+    #[serde(default)]
     pub line_items: Vec<LineItem>,
+    #[serde(default)]
     pub tax_lines: Vec<TaxLine>,
+    #[serde(default)]
     pub shipping_lines: Vec<ShippingLine>,
+    #[serde(default)]
     pub fee_lines: Vec<FeeLine>,
     //serde do not serialize None
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub coupon_lines: Option<Vec<CouponLine>>,
+    #[serde(default)]
     pub refunds: Vec<Refund>,
+    #[serde(default)]
     pub payment_url: String,
+    #[serde(default)]
     pub is_editable: bool,
+    #[serde(default)]
     pub needs_payment: bool,
-    pub needs_processing: bool,
-    pub date_created_gmt: String,
-    pub date_modified_gmt: String,
-    pub date_completed_gmt: Option<String>,
-    pub date_paid_gmt: Option<String>,
     #[serde(default)]
+    pub needs_processing: bool,
+    pub date_created_gmt: WooDateTimeUtc,
+    pub date_modified_gmt: WooDateTimeUtc,
+    pub date_completed_gmt: Option<WooDateTimeUtc>,
+    pub date_paid_gmt: Option<WooDateTimeUtc>,
+    /// Added by the WooCommerce Gift Cards plugin, `None` without it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub gift_cards: Option<Vec<GiftCard>>,
+    #[serde(default)]
     pub currency_symbol: String,
+    #[serde(default)]
     pub _links: Links,
+    /// Fields without a typed counterpart, e.g. added by plugins. Serialized back as they came.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[cfg(feature = "rust_decimal")]
+impl Order {
+    /// `amount` tagged with this order's currency.
+    pub fn currency_amount(&self, amount: Money) -> crate::CurrencyAmount {
+        crate::CurrencyAmount::new(amount, &self.currency)
+    }
+
+    pub fn total_amount(&self) -> crate::CurrencyAmount {
+        self.currency_amount(self.total)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
 pub struct ContactDetails {
     pub first_name: String,
     pub last_name: String,
@@ -86,51 +329,98 @@ pub struct ContactDetails {
     #[serde(default)]
     pub email: String,
     pub phone: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct LineItem {
     pub id: i32,
+    #[serde(default)]
     pub name: String,
-    pub product_id: i32,
-    pub variation_id: i32,
+    pub product_id: ProductId,
+    #[serde(default)]
+    pub variation_id: VariationId,
     pub quantity: i32,
+    #[serde(default)]
     pub tax_class: String,
-    pub subtotal: String,
-    pub subtotal_tax: String,
-    pub total: String,
-    pub total_tax: String,
+    #[serde(default)]
+    pub subtotal: Money,
+    #[serde(default)]
+    pub subtotal_tax: Money,
+    pub total: Money,
+    #[serde(default)]
+    pub total_tax: Money,
+    #[serde(default)]
     pub taxes: Vec<Tax>,
+    #[serde(default)]
     pub meta_data: Vec<MetaData>,
-    pub price: f64,
-    /*pub sku: Option<String>,
-     pub image: Image,
-    pub parent_name: Option<String>, */
+    #[serde(default)]
+    pub price: Money,
+    pub sku: Option<String>,
+    pub image: Option<Image>,
+    pub parent_name: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+/// Needs a `product_id` or a `sku`.
+#[derive(Serialize, Deserialize, Debug, Default)]
 pub struct CreateLineItem {
-    pub product_id: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub product_id: Option<ProductId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub variation_id: Option<VariationId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sku: Option<String>,
     pub quantity: i32,
     /// Line total (after discounts).
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub total: Option<String>,
+    pub total: Option<Money>,
     /// Line subtotal (before discounts).
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub subtotal: Option<String>,
+    pub subtotal: Option<Money>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub meta_data: Vec<NewMetaData>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct CreateShippingLine {
+    pub method_id: String,
+    pub method_title: String,
+    pub total: Money,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub meta_data: Vec<NewMetaData>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct CreateFeeLine {
+    pub name: String,
+    pub total: Money,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tax_class: Option<String>,
+    /// `taxable` or `none`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tax_status: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Tax {
     // Assuming a simple structure; update as needed
     pub id: i32,
-    pub total: String,
-    pub subtotal: String,
+    #[serde(default)]
+    pub total: Money,
+    #[serde(default, with = "crate::optional_money")]
+    pub subtotal: Option<Money>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Image {
-    pub id: String,
+    /// A number, or `""` when the product has no image.
+    pub id: Value,
+    #[serde(default)]
     pub src: String,
 }
 
@@ -138,33 +428,52 @@ pub struct Image {
 pub struct TaxLine {
     // Assuming a simple structure; update as needed
     pub id: i32,
+    #[serde(default)]
     pub rate_code: String,
+    #[serde(default)]
     pub rate_id: i32,
+    #[serde(default)]
     pub label: String,
+    #[serde(default)]
     pub compound: bool,
-    pub tax_total: String,
-    pub shipping_tax_total: String,
+    #[serde(default)]
+    pub tax_total: Money,
+    #[serde(default)]
+    pub shipping_tax_total: Money,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ShippingLine {
     // Assuming a simple structure; update as needed
     pub id: i32,
+    #[serde(default)]
     pub method_title: String,
+    #[serde(default)]
     pub method_id: String,
-    pub total: String,
-    pub total_tax: String,
+    pub total: Money,
+    #[serde(default)]
+    pub total_tax: Money,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct FeeLine {
     // Assuming a simple structure; update as needed
     pub id: i32,
+    #[serde(default)]
     pub name: String,
+    #[serde(default)]
     pub tax_class: String,
+    #[serde(default)]
     pub tax_status: String,
-    pub total: String,
-    pub total_tax: String,
+    pub total: Money,
+    #[serde(default)]
+    pub total_tax: Money,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -172,13 +481,18 @@ pub struct CouponLine {
     // Assuming a simple structure; update as needed
     pub id: i32,
     pub code: String,
-    pub discount: String,
-    pub discount_tax: String,
+    pub discount: Money,
+    #[serde(default)]
+    pub discount_tax: Money,
+    #[serde(default)]
     pub discount_type: String,
-    pub nominal_amount: f64,
+    #[serde(default)]
+    pub nominal_amount: Money,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Default)]
 pub struct CouponLineCreate {
     pub code: String,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -189,19 +503,25 @@ pub struct CouponLineCreate {
 pub struct Refund {
     // Assuming a simple structure; update as needed
     pub id: i32,
+    #[serde(default)]
     pub reason: String,
-    pub total: String,
+    pub total: Money,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
+/// Entry of `Order.gift_cards`.
 #[derive(Serialize, Deserialize, Debug)]
 pub struct GiftCard {
     // Assuming a simple structure; update as needed
     pub id: i32,
-    pub amount: String,
-    pub balance: String,
+    pub amount: Money,
+    pub balance: Money,
 }
 
-#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
 pub enum OrderStatus {
     #[default]
     #[serde(rename = "pending")]
@@ -223,3 +543,269 @@ pub enum OrderStatus {
     #[serde(rename = "checkout-draft")]
     CheckoutDraft,
 }
+
+impl OrderStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderStatus::Pending => "pending",
+            OrderStatus::Processing => "processing",
+            OrderStatus::OnHold => "on-hold",
+            OrderStatus::Completed => "completed",
+            OrderStatus::Cancelled => "cancelled",
+            OrderStatus::Refunded => "refunded",
+            OrderStatus::Failed => "failed",
+            OrderStatus::Trash => "trash",
+            OrderStatus::CheckoutDraft => "checkout-draft",
+        }
+    }
+
+    /// Statuses WooCommerce's own flows move an order to from this one.
+    ///
+    /// Paid orders only move forward: a completed order can be refunded or trashed,
+    /// never reopened. Trashed orders come back through untrash, not a status update.
+    pub fn allowed_transitions(&self) -> &'static [OrderStatus] {
+        use OrderStatus::*;
+        match self {
+            CheckoutDraft => &[Pending, Processing, OnHold, Failed, Cancelled, Trash],
+            Pending => &[Processing, OnHold, Completed, Failed, Cancelled, Trash],
+            Failed => &[Pending, Processing, OnHold, Completed, Cancelled, Trash],
+            OnHold => &[Pending, Processing, Completed, Failed, Cancelled, Trash],
+            Processing => &[OnHold, Completed, Cancelled, Refunded, Trash],
+            Completed => &[Refunded, Trash],
+            Cancelled => &[Pending, Processing, OnHold, Trash],
+            Refunded => &[Trash],
+            Trash => &[],
+        }
+    }
+
+    /// Staying in the same status is always allowed.
+    pub fn can_transition_to(&self, target: OrderStatus) -> bool {
+        *self == target || self.allowed_transitions().contains(&target)
+    }
+}
+
+impl fmt::Display for OrderStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// What [`OrderClient::transition_order`](crate::OrderClient::transition_order) does with
+/// a move [`OrderStatus::can_transition_to`] doesn't allow.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TransitionPolicy {
+    #[default]
+    Reject,
+    /// Log a warning and update the order anyway.
+    Warn,
+}
+
+#[derive(Debug, Clone)]
+pub struct StatusTransition {
+    pub to: OrderStatus,
+    pub policy: TransitionPolicy,
+    /// Added as an order note after the status changed.
+    pub note: Option<String>,
+    pub notify_customer: bool,
+}
+
+impl StatusTransition {
+    pub fn new(to: OrderStatus) -> Self {
+        StatusTransition {
+            to,
+            policy: TransitionPolicy::Reject,
+            note: None,
+            notify_customer: false,
+        }
+    }
+
+    pub fn policy(mut self, policy: TransitionPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn note(mut self, note: &str) -> Self {
+        self.note = Some(note.to_string());
+        self
+    }
+
+    /// Makes the note a customer note, which WooCommerce emails to the customer.
+    pub fn notify_customer(mut self, notify_customer: bool) -> Self {
+        self.notify_customer = notify_customer;
+        self
+    }
+}
+
+/// Body of `POST /orders/<id>/notes`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CreateOrderNote {
+    pub note: String,
+    #[serde(default)]
+    pub customer_note: bool,
+    /// Attributes the note to the API user instead of the system.
+    #[serde(default)]
+    pub added_by_user: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OrderNote {
+    pub id: i64,
+    #[serde(default)]
+    pub author: String,
+    pub date_created: WooDateTime,
+    pub date_created_gmt: WooDateTimeUtc,
+    pub note: String,
+    #[serde(default)]
+    pub customer_note: bool,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Body of `POST /orders/<id>/refunds`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CreateRefund {
+    pub amount: Money,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub reason: String,
+    /// Refunds through the payment gateway. Otherwise the refund is only recorded and the
+    /// money has to be returned by hand.
+    pub api_refund: bool,
+}
+
+impl CreateRefund {
+    /// A manual refund, see [`CreateRefund::api_refund`].
+    pub fn new(amount: Money) -> Self {
+        CreateRefund {
+            amount,
+            reason: String::new(),
+            api_refund: false,
+        }
+    }
+
+    pub fn reason(mut self, reason: &str) -> Self {
+        self.reason = reason.to_string();
+        self
+    }
+
+    pub fn api_refund(mut self, api_refund: bool) -> Self {
+        self.api_refund = api_refund;
+        self
+    }
+}
+
+/// A refund as returned by the refunds endpoint. `Order.refunds` only lists a summary,
+/// see [`Refund`].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OrderRefund {
+    pub id: i64,
+    pub date_created: WooDateTime,
+    pub date_created_gmt: WooDateTimeUtc,
+    pub amount: Money,
+    #[serde(default)]
+    pub reason: String,
+    #[serde(default)]
+    pub refunded_by: i64,
+    /// Whether the gateway returned the money.
+    #[serde(default)]
+    pub refunded_payment: bool,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Filters for `GET /orders`. Dates are sent as GMT; an empty `status` means any status.
+#[derive(Debug, Clone, Default)]
+pub struct OrderQuery {
+    pub page: Option<usize>,
+    pub per_page: Option<usize>,
+    pub status: Vec<OrderStatus>,
+    pub customer: Option<CustomerId>,
+    pub search: Option<String>,
+    pub after: Option<WooDateTimeUtc>,
+    pub before: Option<WooDateTimeUtc>,
+    pub modified_after: Option<WooDateTimeUtc>,
+    pub modified_before: Option<WooDateTimeUtc>,
+    pub include: Vec<OrderId>,
+    pub exclude: Vec<OrderId>,
+    pub orderby: Option<OrderBy>,
+    pub order: Option<SortOrder>,
+}
+
+impl OrderQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn page(mut self, page: usize, per_page: usize) -> Self {
+        self.page = Some(page);
+        self.per_page = Some(per_page);
+        self
+    }
+
+    pub fn status(mut self, statuses: &[OrderStatus]) -> Self {
+        self.status.extend_from_slice(statuses);
+        self
+    }
+
+    pub fn customer(mut self, customer_id: CustomerId) -> Self {
+        self.customer = Some(customer_id);
+        self
+    }
+
+    pub fn search(mut self, search: &str) -> Self {
+        self.search = Some(search.to_string());
+        self
+    }
+
+    /// Orders created in `[after, before)`, either bound may be open.
+    pub fn created_between(
+        mut self,
+        after: Option<WooDateTimeUtc>,
+        before: Option<WooDateTimeUtc>,
+    ) -> Self {
+        self.after = after;
+        self.before = before;
+        self
+    }
+
+    pub fn modified_between(
+        mut self,
+        after: Option<WooDateTimeUtc>,
+        before: Option<WooDateTimeUtc>,
+    ) -> Self {
+        self.modified_after = after;
+        self.modified_before = before;
+        self
+    }
+
+    pub fn include(mut self, ids: &[OrderId]) -> Self {
+        self.include.extend_from_slice(ids);
+        self
+    }
+
+    pub fn exclude(mut self, ids: &[OrderId]) -> Self {
+        self.exclude.extend_from_slice(ids);
+        self
+    }
+
+    pub fn order_by(mut self, orderby: OrderBy, order: SortOrder) -> Self {
+        self.orderby = Some(orderby);
+        self.order = Some(order);
+        self
+    }
+
+    pub(crate) fn query_pairs(&self) -> Vec<(&'static str, String)> {
+        QueryPairs::new()
+            .page(self.page, self.per_page)
+            .list("status", &self.status)
+            .value("customer", self.customer)
+            .value("search", self.search.as_ref())
+            .list("include", &self.include)
+            .list("exclude", &self.exclude)
+            .date("after", self.after)
+            .date("before", self.before)
+            .date("modified_after", self.modified_after)
+            .date("modified_before", self.modified_before)
+            .sort(self.orderby, self.order)
+            .finish()
+    }
+}
@@ -1,156 +1,981 @@
-use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
 
-use crate::{MetaData, Links};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
 
-#[derive(Serialize, Deserialize)]
+use crate::{
+    BuilderError, Links, MetaData, Money, NewMetaData, OrderBy, ProductId, SortOrder,
+    VariationId, WooDateTime, WooDateTimeUtc,
+};
+
+use super::common::QueryPairs;
+
+/// Body of `POST /products`. [`CreateProduct::builder`] checks the fields that depend on
+/// each other, e.g. a sale price above the regular price.
+#[derive(Serialize, Deserialize, Debug, Default)]
 pub struct CreateProduct {
-   pub name: String,
-   #[serde(rename = "type")]
-   pub product_type: String,
-   pub regular_price: String,
-   pub price: String,
-   #[serde(rename = "virtual")]
-   pub virtual_type: bool,
-   pub description: String,
-   pub short_description: String,
-   pub categories: Vec<Category>,
-   pub images: Vec<Image>,
-   pub meta_data: Vec<MetaData>,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slug: Option<String>,
+    #[serde(default, rename = "type", skip_serializing_if = "Option::is_none")]
+    pub product_type: Option<ProductType>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<ProductStatus>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub featured: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub catalog_visibility: Option<CatalogVisibility>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub short_description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sku: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub regular_price: Option<Money>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sale_price: Option<Money>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date_on_sale_from_gmt: Option<WooDateTimeUtc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date_on_sale_to_gmt: Option<WooDateTimeUtc>,
+    #[serde(default, rename = "virtual", skip_serializing_if = "Option::is_none")]
+    pub virtual_type: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub downloadable: Option<bool>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub downloads: Vec<NewDownload>,
+    /// `-1` for unlimited.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub download_limit: Option<i32>,
+    /// Days until the download link expires, `-1` for never.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub download_expiry: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub external_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub button_text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tax_status: Option<TaxStatus>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tax_class: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub manage_stock: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stock_quantity: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stock_status: Option<StockStatus>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backorders: Option<Backorders>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sold_individually: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weight: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dimensions: Option<Dimensions>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shipping_class: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reviews_allowed: Option<bool>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub upsell_ids: Vec<ProductId>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cross_sell_ids: Vec<ProductId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<ProductId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub purchase_note: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub categories: Vec<TermRef>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<TermRef>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<ImageRef>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attributes: Vec<NewAttribute>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub menu_order: Option<i32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub meta_data: Vec<NewMetaData>,
+}
+
+impl CreateProduct {
+    pub fn builder(name: &str) -> CreateProductBuilder {
+        CreateProductBuilder {
+            product: CreateProduct {
+                name: name.to_string(),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct CreateProductBuilder {
+    product: CreateProduct,
+}
+
+impl CreateProductBuilder {
+    pub fn product_type(mut self, product_type: ProductType) -> Self {
+        self.product.product_type = Some(product_type);
+        self
+    }
+
+    pub fn status(mut self, status: ProductStatus) -> Self {
+        self.product.status = Some(status);
+        self
+    }
+
+    pub fn featured(mut self, featured: bool) -> Self {
+        self.product.featured = Some(featured);
+        self
+    }
+
+    pub fn catalog_visibility(mut self, visibility: CatalogVisibility) -> Self {
+        self.product.catalog_visibility = Some(visibility);
+        self
+    }
+
+    pub fn slug(mut self, slug: &str) -> Self {
+        self.product.slug = Some(slug.to_string());
+        self
+    }
+
+    pub fn description(mut self, description: &str) -> Self {
+        self.product.description = Some(description.to_string());
+        self
+    }
+
+    pub fn short_description(mut self, short_description: &str) -> Self {
+        self.product.short_description = Some(short_description.to_string());
+        self
+    }
+
+    pub fn sku(mut self, sku: &str) -> Self {
+        self.product.sku = Some(sku.to_string());
+        self
+    }
+
+    pub fn regular_price(mut self, price: Money) -> Self {
+        self.product.regular_price = Some(price);
+        self
+    }
+
+    pub fn sale_price(mut self, price: Money) -> Self {
+        self.product.sale_price = Some(price);
+        self
+    }
+
+    /// Limits the sale price to a period, either end may be open.
+    pub fn sale_period(
+        mut self,
+        from: Option<WooDateTimeUtc>,
+        to: Option<WooDateTimeUtc>,
+    ) -> Self {
+        self.product.date_on_sale_from_gmt = from;
+        self.product.date_on_sale_to_gmt = to;
+        self
+    }
+
+    pub fn virtual_product(mut self, virtual_type: bool) -> Self {
+        self.product.virtual_type = Some(virtual_type);
+        self
+    }
+
+    /// Adds a file and marks the product downloadable.
+    pub fn download(mut self, name: &str, file: &str) -> Self {
+        self.product.downloadable = Some(true);
+        self.product.downloads.push(NewDownload {
+            id: None,
+            name: name.to_string(),
+            file: file.to_string(),
+        });
+        self
+    }
+
+    pub fn download_limits(mut self, limit: i32, expiry_days: i32) -> Self {
+        self.product.download_limit = Some(limit);
+        self.product.download_expiry = Some(expiry_days);
+        self
+    }
+
+    /// Makes this an external (affiliate) product.
+    pub fn external(mut self, url: &str, button_text: &str) -> Self {
+        self.product.product_type = Some(ProductType::External);
+        self.product.external_url = Some(url.to_string());
+        self.product.button_text = Some(button_text.to_string());
+        self
+    }
+
+    pub fn tax(mut self, status: TaxStatus, class: &str) -> Self {
+        self.product.tax_status = Some(status);
+        self.product.tax_class = Some(class.to_string());
+        self
+    }
+
+    /// Enables stock management with `quantity` in stock.
+    pub fn stock(mut self, quantity: i32) -> Self {
+        self.product.manage_stock = Some(true);
+        self.product.stock_quantity = Some(quantity);
+        self
+    }
+
+    pub fn stock_status(mut self, stock_status: StockStatus) -> Self {
+        self.product.stock_status = Some(stock_status);
+        self
+    }
+
+    pub fn backorders(mut self, backorders: Backorders) -> Self {
+        self.product.backorders = Some(backorders);
+        self
+    }
+
+    pub fn sold_individually(mut self, sold_individually: bool) -> Self {
+        self.product.sold_individually = Some(sold_individually);
+        self
+    }
+
+    pub fn weight(mut self, weight: &str) -> Self {
+        self.product.weight = Some(weight.to_string());
+        self
+    }
+
+    pub fn dimensions(mut self, length: &str, width: &str, height: &str) -> Self {
+        self.product.dimensions = Some(Dimensions {
+            length: length.to_string(),
+            width: width.to_string(),
+            height: height.to_string(),
+            extra: Map::new(),
+        });
+        self
+    }
+
+    pub fn shipping_class(mut self, shipping_class: &str) -> Self {
+        self.product.shipping_class = Some(shipping_class.to_string());
+        self
+    }
+
+    pub fn reviews_allowed(mut self, reviews_allowed: bool) -> Self {
+        self.product.reviews_allowed = Some(reviews_allowed);
+        self
+    }
+
+    pub fn upsell(mut self, product_id: ProductId) -> Self {
+        self.product.upsell_ids.push(product_id);
+        self
+    }
+
+    pub fn cross_sell(mut self, product_id: ProductId) -> Self {
+        self.product.cross_sell_ids.push(product_id);
+        self
+    }
+
+    pub fn parent_id(mut self, parent_id: ProductId) -> Self {
+        self.product.parent_id = Some(parent_id);
+        self
+    }
+
+    pub fn purchase_note(mut self, note: &str) -> Self {
+        self.product.purchase_note = Some(note.to_string());
+        self
+    }
+
+    pub fn category(mut self, category_id: i32) -> Self {
+        self.product.categories.push(TermRef { id: category_id });
+        self
+    }
+
+    pub fn tag(mut self, tag_id: i32) -> Self {
+        self.product.tags.push(TermRef { id: tag_id });
+        self
+    }
+
+    /// The first image is the featured image.
+    pub fn image(mut self, image: ImageRef) -> Self {
+        self.product.images.push(image);
+        self
+    }
+
+    pub fn attribute(mut self, attribute: NewAttribute) -> Self {
+        self.product.attributes.push(attribute);
+        self
+    }
+
+    pub fn menu_order(mut self, menu_order: i32) -> Self {
+        self.product.menu_order = Some(menu_order);
+        self
+    }
+
+    pub fn meta(mut self, key: &str, value: impl Into<Value>) -> Self {
+        self.product.meta_data.push(NewMetaData::new(key, value));
+        self
+    }
+
+    pub fn build(self) -> Result<CreateProduct, BuilderError> {
+        let product = self.product;
+        if product.name.trim().is_empty() {
+            return Err(BuilderError::Missing("name"));
+        }
+        // Comparing prices needs `rust_decimal`.
+        #[cfg(feature = "rust_decimal")]
+        if let (Some(sale), Some(regular)) = (product.sale_price, product.regular_price) {
+            if sale > regular {
+                return Err(BuilderError::invalid(
+                    "sale_price",
+                    "must not exceed the regular price",
+                ));
+            }
+        }
+        let has_sale_period =
+            product.date_on_sale_from_gmt.is_some() || product.date_on_sale_to_gmt.is_some();
+        if has_sale_period && product.sale_price.is_none() {
+            return Err(BuilderError::invalid(
+                "date_on_sale_from_gmt",
+                "a sale period needs a sale_price",
+            ));
+        }
+        if let (Some(from), Some(to)) =
+            (product.date_on_sale_from_gmt, product.date_on_sale_to_gmt)
+        {
+            if to <= from {
+                return Err(BuilderError::invalid(
+                    "date_on_sale_to_gmt",
+                    "must be after date_on_sale_from_gmt",
+                ));
+            }
+        }
+        if product.product_type == Some(ProductType::External) && product.external_url.is_none() {
+            return Err(BuilderError::Missing("external_url"));
+        }
+        for (index, download) in product.downloads.iter().enumerate() {
+            if download.file.trim().is_empty() {
+                return Err(BuilderError::invalid(
+                    format!("downloads[{}]", index),
+                    "file is required",
+                ));
+            }
+        }
+        for (index, image) in product.images.iter().enumerate() {
+            if image.id.is_none() && image.src.is_none() {
+                return Err(BuilderError::invalid(
+                    format!("images[{}]", index),
+                    "needs an id or src",
+                ));
+            }
+        }
+        Ok(product)
+    }
+}
+
+/// Body of `PUT /products/<id>`, only the fields that are set are changed.
+/// A list replaces the current one, e.g. `Some(vec![])` removes every image.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct UpdateProduct {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slug: Option<String>,
+    #[serde(default, rename = "type", skip_serializing_if = "Option::is_none")]
+    pub product_type: Option<ProductType>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<ProductStatus>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub featured: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub catalog_visibility: Option<CatalogVisibility>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub short_description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sku: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub regular_price: Option<Money>,
+    /// `Some(None)` ends the sale.
+    #[serde(
+        default,
+        with = "crate::clearable_money",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub sale_price: Option<Option<Money>>,
+    /// `Some(None)` removes the start of the sale period.
+    #[serde(
+        default,
+        with = "crate::clearable_date",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub date_on_sale_from_gmt: Option<Option<WooDateTimeUtc>>,
+    /// `Some(None)` removes the end of the sale period.
+    #[serde(
+        default,
+        with = "crate::clearable_date",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub date_on_sale_to_gmt: Option<Option<WooDateTimeUtc>>,
+    #[serde(default, rename = "virtual", skip_serializing_if = "Option::is_none")]
+    pub virtual_type: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub downloadable: Option<bool>,
+    /// Files without an `id` are added, existing files not listed are removed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub downloads: Option<Vec<NewDownload>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub download_limit: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub download_expiry: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub external_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub button_text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tax_status: Option<TaxStatus>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tax_class: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub manage_stock: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stock_quantity: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stock_status: Option<StockStatus>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backorders: Option<Backorders>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sold_individually: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weight: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dimensions: Option<Dimensions>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shipping_class: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reviews_allowed: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upsell_ids: Option<Vec<ProductId>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cross_sell_ids: Option<Vec<ProductId>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<ProductId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub purchase_note: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub categories: Option<Vec<TermRef>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<TermRef>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub images: Option<Vec<ImageRef>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attributes: Option<Vec<NewAttribute>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub menu_order: Option<i32>,
+    /// Entries with an `id` are updated, the others added.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta_data: Option<Vec<MetaData>>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Product {
-    pub id: i32,
+    pub id: ProductId,
     pub name: String,
+    #[serde(default)]
     pub slug: String,
+    #[serde(default)]
     pub permalink: String,
-    pub date_created: String,
-    pub date_created_gmt: String,
-    pub date_modified: String,
-    pub date_modified_gmt: String,
-    #[serde(rename = "type")]
-    pub product_type: String,
-    pub status: String,
+    pub date_created: WooDateTime,
+    pub date_created_gmt: WooDateTimeUtc,
+    pub date_modified: WooDateTime,
+    pub date_modified_gmt: WooDateTimeUtc,
+    #[serde(default, rename = "type")]
+    pub product_type: ProductType,
+    #[serde(default)]
+    pub status: ProductStatus,
+    #[serde(default)]
     pub featured: bool,
-    pub catalog_visibility: String,
+    #[serde(default)]
+    pub catalog_visibility: CatalogVisibility,
+    #[serde(default)]
     pub description: String,
+    #[serde(default)]
     pub short_description: String,
+    #[serde(default)]
     pub sku: String,
-    pub price: String,
-    pub regular_price: String,
+    #[serde(default, with = "crate::optional_money")]
+    pub price: Option<Money>,
+    #[serde(default, with = "crate::optional_money")]
+    pub regular_price: Option<Money>,
     
+    #[serde(default, with = "crate::optional_money")]
+    pub sale_price: Option<Money>,
     #[serde(default)]
-    pub sale_price: String,
+    pub date_on_sale_from: Option<WooDateTime>,
     #[serde(default)]
-    pub date_on_sale_from: Option<String>,
+    pub date_on_sale_from_gmt: Option<WooDateTimeUtc>,
     #[serde(default)]
-    pub date_on_sale_from_gmt: Option<String>,
+    pub date_on_sale_to: Option<WooDateTime>,
     #[serde(default)]
-    pub date_on_sale_to: Option<String>,
-    #[serde(default)]
-    pub date_on_sale_to_gmt: Option<String>,
+    pub date_on_sale_to_gmt: Option<WooDateTimeUtc>,
      
+    #[serde(default)]
     pub price_html: String,
+    #[serde(default)]
     pub on_sale: bool,
+    #[serde(default)]
     pub purchasable: bool,
+    #[serde(default)]
     pub total_sales: i32,
-    #[serde(rename = "virtual")]
+    #[serde(default, rename = "virtual")]
     pub virtual_type: bool,
     
+    #[serde(default)]
     pub downloadable: bool,
+    #[serde(default)]
     pub downloads: Vec<Download>,
+    #[serde(default)]
     pub download_limit: i32,
+    #[serde(default)]
     pub download_expiry: i32,
+    #[serde(default)]
     pub external_url: String,
+    #[serde(default)]
     pub button_text: String,
-    pub tax_status: String,
+    #[serde(default)]
+    pub tax_status: TaxStatus,
+    #[serde(default)]
     pub tax_class: String,
+    #[serde(default)]
     pub manage_stock: bool,
     #[serde(default)]
     pub stock_quantity: Option<i32>,
-    pub stock_status: String,
-    pub backorders: String,
+    #[serde(default)]
+    pub stock_status: StockStatus,
+    #[serde(default)]
+    pub backorders: Backorders,
+    #[serde(default)]
     pub backorders_allowed: bool,
+    #[serde(default)]
     pub backordered: bool,
+    #[serde(default)]
     pub sold_individually: bool,
+    #[serde(default)]
     pub weight: String,
+    #[serde(default)]
     pub dimensions: Dimensions,
+    #[serde(default)]
     pub shipping_required: bool,
+    #[serde(default)]
     pub shipping_taxable: bool,
+    #[serde(default)]
     pub shipping_class: String,
+    #[serde(default)]
     pub shipping_class_id: i32,
+    #[serde(default)]
     pub reviews_allowed: bool,
+    #[serde(default)]
     pub average_rating: String,
+    #[serde(default)]
     pub rating_count: i32,
-    pub related_ids: Vec<i32>,
-    pub upsell_ids: Vec<i32>,
-    pub cross_sell_ids: Vec<i32>,
-    pub parent_id: i32,
+    #[serde(default)]
+    pub related_ids: Vec<ProductId>,
+    #[serde(default)]
+    pub upsell_ids: Vec<ProductId>,
+    #[serde(default)]
+    pub cross_sell_ids: Vec<ProductId>,
+    #[serde(default)]
+    pub parent_id: ProductId,
+    #[serde(default)]
     pub purchase_note: String,
+    #[serde(default)]
     pub categories: Vec<Category>,
+    #[serde(default)]
     pub tags: Vec<Tag>,
+    #[serde(default)]
     pub images: Vec<Image>,
+    #[serde(default)]
     pub attributes: Vec<Attribute>,
+    #[serde(default)]
     pub default_attributes: Vec<Attribute>,
-    pub variations: Vec<i32>,
-    pub grouped_products: Vec<i32>,
+    #[serde(default)]
+    pub variations: Vec<VariationId>,
+    #[serde(default)]
+    pub grouped_products: Vec<ProductId>,
+    #[serde(default)]
     pub menu_order: i32,
+    #[serde(default)]
     pub meta_data: Vec<MetaData>,
+    #[serde(default)]
     pub _links: Links,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Download {
-    pub id: i32,
-    pub date_created: String,
-    pub date_created_gmt: String,
-    pub date_modified: String,
-    pub date_modified_gmt: String,
-    pub src: String,
+    /// File ID, a hash string.
+    pub id: String,
+    #[serde(default)]
     pub name: String,
-    pub alt: String,
+    #[serde(default)]
+    pub file: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
 pub struct Dimensions {
     pub length: String,
     pub width: String,
     pub height: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Category {
     pub id: i32,
+    #[serde(default)]
     pub name: String,
+    #[serde(default)]
     pub slug: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Tag {
     // Assuming similar structure to Category
     pub id: i32,
+    #[serde(default)]
     pub name: String,
+    #[serde(default)]
     pub slug: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Image {
    pub id: i32,
-   pub date_created: String,
-   pub date_created_gmt: String,
-   pub date_modified: String,
-   pub date_modified_gmt: String,
+   pub date_created: Option<WooDateTime>,
+   pub date_created_gmt: Option<WooDateTimeUtc>,
+   pub date_modified: Option<WooDateTime>,
+   pub date_modified_gmt: Option<WooDateTimeUtc>,
    pub src: String,
+   #[serde(default)]
    pub name: String,
+   #[serde(default)]
    pub alt: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Attribute {
     pub id: i32,
+    #[serde(default)]
     pub name: String,
+    #[serde(default)]
     pub position: i32,
+    #[serde(default)]
     pub visible: bool,
+    #[serde(default)]
     pub variation: bool,
+    #[serde(default)]
     pub options: Vec<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
+
+/// Product or image category/tag by id, as sent in create and update requests.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TermRef {
+    pub id: i32,
+}
+
+/// Image in create and update requests: an existing media library item by `id`,
+/// or a URL (`src`) that WooCommerce downloads.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImageRef {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub src: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alt: Option<String>,
+}
+
+impl ImageRef {
+    pub fn from_id(id: i32) -> Self {
+        ImageRef {
+            id: Some(id),
+            ..Default::default()
+        }
+    }
+
+    pub fn from_src(src: &str) -> Self {
+        ImageRef {
+            src: Some(src.to_string()),
+            ..Default::default()
+        }
+    }
+
+    pub fn alt(mut self, alt: &str) -> Self {
+        self.alt = Some(alt.to_string());
+        self
+    }
+}
+
+impl From<&Image> for ImageRef {
+    fn from(image: &Image) -> Self {
+        ImageRef::from_id(image.id)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct NewDownload {
+    /// Set to keep an existing file when updating.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub name: String,
+    pub file: String,
+}
+
+/// Attribute in create and update requests. Global attributes are referenced by `id`,
+/// custom ones by `name`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct NewAttribute {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub visible: Option<bool>,
+    /// Used to build variations of a variable product.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub variation: Option<bool>,
+    pub options: Vec<String>,
+}
+
+impl NewAttribute {
+    pub fn global(id: i32, options: &[&str]) -> Self {
+        NewAttribute {
+            id: Some(id),
+            options: options.iter().map(|option| option.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    pub fn custom(name: &str, options: &[&str]) -> Self {
+        NewAttribute {
+            name: Some(name.to_string()),
+            options: options.iter().map(|option| option.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    pub fn visible(mut self, visible: bool) -> Self {
+        self.visible = Some(visible);
+        self
+    }
+
+    pub fn for_variations(mut self, variation: bool) -> Self {
+        self.variation = Some(variation);
+        self
+    }
+}
+
+/// Filters for `GET /products`, unset fields use the WooCommerce defaults.
+#[derive(Debug, Clone, Default)]
+pub struct ProductQuery {
+    pub page: Option<usize>,
+    pub per_page: Option<usize>,
+    pub search: Option<String>,
+    pub sku: Option<String>,
+    pub status: Option<ProductStatus>,
+    pub include: Vec<ProductId>,
+    pub exclude: Vec<ProductId>,
+    pub modified_after: Option<WooDateTimeUtc>,
+    pub orderby: Option<OrderBy>,
+    pub order: Option<SortOrder>,
+}
+
+impl ProductQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn page(mut self, page: usize, per_page: usize) -> Self {
+        self.page = Some(page);
+        self.per_page = Some(per_page);
+        self
+    }
+
+    pub fn search(mut self, search: &str) -> Self {
+        self.search = Some(search.to_string());
+        self
+    }
+
+    pub fn sku(mut self, sku: &str) -> Self {
+        self.sku = Some(sku.to_string());
+        self
+    }
+
+    pub fn status(mut self, status: ProductStatus) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    pub fn include(mut self, ids: &[ProductId]) -> Self {
+        self.include.extend_from_slice(ids);
+        self
+    }
+
+    pub fn exclude(mut self, ids: &[ProductId]) -> Self {
+        self.exclude.extend_from_slice(ids);
+        self
+    }
+
+    pub fn modified_after(mut self, modified_after: WooDateTimeUtc) -> Self {
+        self.modified_after = Some(modified_after);
+        self
+    }
+
+    pub fn order_by(mut self, orderby: OrderBy, order: SortOrder) -> Self {
+        self.orderby = Some(orderby);
+        self.order = Some(order);
+        self
+    }
+
+    pub(crate) fn query_pairs(&self) -> Vec<(&'static str, String)> {
+        QueryPairs::new()
+            .page(self.page, self.per_page)
+            .value("search", self.search.as_ref())
+            .value("sku", self.sku.as_ref())
+            .value("status", self.status.as_ref())
+            .list("include", &self.include)
+            .list("exclude", &self.exclude)
+            .date("modified_after", self.modified_after)
+            .sort(self.orderby, self.order)
+            .finish()
+    }
+}
+
+/// String enums whose first variant is the WooCommerce default. Values from plugins
+/// or newer WooCommerce versions are kept in `Other`.
+macro_rules! woo_enum {
+    (
+        $(#[$doc:meta])*
+        $name:ident {
+            $default:ident => $default_value:literal
+            $(, $variant:ident => $value:literal)* $(,)?
+        }
+    ) => {
+        $(#[$doc])*
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $default,
+            $($variant,)*
+            Other(String),
+        }
+
+        impl $name {
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $name::$default => $default_value,
+                    $($name::$variant => $value,)*
+                    $name::Other(value) => value,
+                }
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                $name::$default
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = std::convert::Infallible;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                Ok(match s {
+                    $default_value => $name::$default,
+                    $($value => $name::$variant,)*
+                    other => $name::Other(other.to_string()),
+                })
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(self.as_str())
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let value = String::deserialize(deserializer)?;
+                Ok(value.parse().unwrap())
+            }
+        }
+    };
+}
+
+woo_enum!(ProductType {
+    Simple => "simple",
+    Grouped => "grouped",
+    External => "external",
+    Variable => "variable",
+    Variation => "variation",
+});
+
+woo_enum!(ProductStatus {
+    Publish => "publish",
+    Draft => "draft",
+    Pending => "pending",
+    Private => "private",
+    Future => "future",
+    Trash => "trash",
+});
+
+woo_enum!(StockStatus {
+    InStock => "instock",
+    OutOfStock => "outofstock",
+    OnBackorder => "onbackorder",
+});
+
+woo_enum!(
+    /// `Shipping` means only the shipping cost is taxed.
+    TaxStatus {
+        Taxable => "taxable",
+        Shipping => "shipping",
+        None => "none",
+    }
+);
+
+woo_enum!(Backorders {
+    No => "no",
+    Notify => "notify",
+    Yes => "yes",
+});
+
+woo_enum!(
+    /// Where the product is listed: `Catalog` is shop pages only, `Search` is search
+    /// results only.
+    CatalogVisibility {
+        Visible => "visible",
+        Catalog => "catalog",
+        Search => "search",
+        Hidden => "hidden",
+    }
+);
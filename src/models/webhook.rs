@@ -0,0 +1,309 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
+
+use crate::{Links, WebhookId, WooDateTime, WooDateTimeUtc};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CreateWebhook {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<WebhookStatus>,
+    pub topic: WebhookTopic,
+    pub delivery_url: String,
+    /// Used to sign deliveries (`X-WC-Webhook-Signature`). WooCommerce never returns it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_version: Option<WebhookApiVersion>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct UpdateWebhook {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<WebhookStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub topic: Option<WebhookTopic>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delivery_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_version: Option<WebhookApiVersion>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Webhook {
+    pub id: WebhookId,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub status: WebhookStatus,
+    pub topic: WebhookTopic,
+    #[serde(default)]
+    pub resource: String,
+    #[serde(default)]
+    pub event: String,
+    #[serde(default)]
+    pub hooks: Vec<String>,
+    pub delivery_url: String,
+    #[serde(default)]
+    pub api_version: Option<WebhookApiVersion>,
+    pub date_created: WooDateTime,
+    pub date_created_gmt: WooDateTimeUtc,
+    #[serde(default)]
+    pub date_modified: Option<WooDateTime>,
+    #[serde(default)]
+    pub date_modified_gmt: Option<WooDateTimeUtc>,
+    #[serde(default)]
+    pub _links: Links,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Webhook {
+    /// The patch needed to bring this webhook in line with `desired`, if any.
+    /// The secret is write-only and can't be compared, so a desired secret is always sent.
+    pub fn diff(&self, desired: &CreateWebhook) -> Option<UpdateWebhook> {
+        let mut update = UpdateWebhook::default();
+        let mut changed = false;
+
+        if let Some(name) = &desired.name {
+            if name != &self.name {
+                update.name = Some(name.clone());
+                changed = true;
+            }
+        }
+
+        let status = desired.status.clone().unwrap_or(WebhookStatus::Active);
+        if status != self.status {
+            update.status = Some(status);
+            changed = true;
+        }
+
+        if let Some(api_version) = &desired.api_version {
+            if Some(api_version) != self.api_version.as_ref() {
+                update.api_version = Some(api_version.clone());
+                changed = true;
+            }
+        }
+
+        if let Some(secret) = &desired.secret {
+            update.secret = Some(secret.clone());
+            changed = true;
+        }
+
+        if !changed {
+            return None;
+        }
+        Some(update)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub enum WebhookStatus {
+    #[default]
+    #[serde(rename = "active")]
+    Active,
+    #[serde(rename = "paused")]
+    Paused,
+    #[serde(rename = "disabled")]
+    Disabled,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum WebhookApiVersion {
+    #[serde(rename = "wp_api_v1")]
+    WpApiV1,
+    #[serde(rename = "wp_api_v2")]
+    WpApiV2,
+    #[serde(rename = "wp_api_v3")]
+    WpApiV3,
+    #[serde(rename = "legacy_v3")]
+    LegacyV3,
+}
+
+/// `<resource>.<event>` as used by WooCommerce, e.g. `order.created`.
+/// Action hooks (`action.<hook>`) and unknown topics are kept as `Custom`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum WebhookTopic {
+    OrderCreated,
+    OrderUpdated,
+    OrderDeleted,
+    OrderRestored,
+    ProductCreated,
+    ProductUpdated,
+    ProductDeleted,
+    ProductRestored,
+    CouponCreated,
+    CouponUpdated,
+    CouponDeleted,
+    CouponRestored,
+    CustomerCreated,
+    CustomerUpdated,
+    CustomerDeleted,
+    Custom(String),
+}
+
+impl WebhookTopic {
+    pub fn as_str(&self) -> &str {
+        match self {
+            WebhookTopic::OrderCreated => "order.created",
+            WebhookTopic::OrderUpdated => "order.updated",
+            WebhookTopic::OrderDeleted => "order.deleted",
+            WebhookTopic::OrderRestored => "order.restored",
+            WebhookTopic::ProductCreated => "product.created",
+            WebhookTopic::ProductUpdated => "product.updated",
+            WebhookTopic::ProductDeleted => "product.deleted",
+            WebhookTopic::ProductRestored => "product.restored",
+            WebhookTopic::CouponCreated => "coupon.created",
+            WebhookTopic::CouponUpdated => "coupon.updated",
+            WebhookTopic::CouponDeleted => "coupon.deleted",
+            WebhookTopic::CouponRestored => "coupon.restored",
+            WebhookTopic::CustomerCreated => "customer.created",
+            WebhookTopic::CustomerUpdated => "customer.updated",
+            WebhookTopic::CustomerDeleted => "customer.deleted",
+            WebhookTopic::Custom(topic) => topic,
+        }
+    }
+
+    /// The part before the dot, e.g. `order`.
+    pub fn resource(&self) -> &str {
+        self.as_str().split('.').next().unwrap_or_default()
+    }
+
+    /// The part after the dot, e.g. `created`.
+    pub fn event(&self) -> &str {
+        self.as_str()
+            .split_once('.')
+            .map(|(_, event)| event)
+            .unwrap_or_default()
+    }
+}
+
+impl FromStr for WebhookTopic {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let topic = match s {
+            "order.created" => WebhookTopic::OrderCreated,
+            "order.updated" => WebhookTopic::OrderUpdated,
+            "order.deleted" => WebhookTopic::OrderDeleted,
+            "order.restored" => WebhookTopic::OrderRestored,
+            "product.created" => WebhookTopic::ProductCreated,
+            "product.updated" => WebhookTopic::ProductUpdated,
+            "product.deleted" => WebhookTopic::ProductDeleted,
+            "product.restored" => WebhookTopic::ProductRestored,
+            "coupon.created" => WebhookTopic::CouponCreated,
+            "coupon.updated" => WebhookTopic::CouponUpdated,
+            "coupon.deleted" => WebhookTopic::CouponDeleted,
+            "coupon.restored" => WebhookTopic::CouponRestored,
+            "customer.created" => WebhookTopic::CustomerCreated,
+            "customer.updated" => WebhookTopic::CustomerUpdated,
+            "customer.deleted" => WebhookTopic::CustomerDeleted,
+            other => WebhookTopic::Custom(other.to_string()),
+        };
+        Ok(topic)
+    }
+}
+
+impl fmt::Display for WebhookTopic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for WebhookTopic {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for WebhookTopic {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let topic = String::deserialize(deserializer)?;
+        Ok(topic.parse().unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn webhook_json(id: u64, topic: &str, delivery_url: &str) -> Value {
+        json!({
+            "id": id,
+            "name": "Order sync",
+            "status": "active",
+            "topic": topic,
+            "resource": "order",
+            "event": "created",
+            "hooks": ["woocommerce_new_order"],
+            "delivery_url": delivery_url,
+            "date_created": "2024-03-01T10:00:00",
+            "date_created_gmt": "2024-03-01T13:00:00",
+            "date_modified": null,
+            "date_modified_gmt": null
+        })
+    }
+
+    #[test]
+    fn test_topic_round_trip() {
+        let topic: WebhookTopic = "coupon.restored".parse().unwrap();
+        assert_eq!(topic, WebhookTopic::CouponRestored);
+        assert_eq!((topic.resource(), topic.event()), ("coupon", "restored"));
+
+        let custom: WebhookTopic = "action.woocommerce_payment_complete".parse().unwrap();
+        assert_eq!(
+            custom,
+            WebhookTopic::Custom("action.woocommerce_payment_complete".to_string())
+        );
+
+        for topic in [WebhookTopic::OrderUpdated, custom] {
+            let value = serde_json::to_value(&topic).unwrap();
+            assert_eq!(value, json!(topic.as_str()));
+            assert_eq!(
+                serde_json::from_value::<WebhookTopic>(value).unwrap(),
+                topic
+            );
+        }
+    }
+
+    #[test]
+    fn test_diff() {
+        let webhook: Webhook = serde_json::from_value(webhook_json(
+            11,
+            "order.created",
+            "https://example.com/hooks",
+        ))
+        .unwrap();
+        let mut desired = CreateWebhook {
+            name: Some("Order sync".to_string()),
+            status: None,
+            topic: WebhookTopic::OrderCreated,
+            delivery_url: "https://example.com/hooks".to_string(),
+            secret: None,
+            api_version: None,
+        };
+        assert!(webhook.diff(&desired).is_none());
+
+        desired.status = Some(WebhookStatus::Paused);
+        let update = webhook.diff(&desired).unwrap();
+        assert_eq!(update.status, Some(WebhookStatus::Paused));
+        assert_eq!(update.name, None);
+
+        // A rotated secret can't be compared, it is sent on its own.
+        desired.status = None;
+        desired.secret = Some("rotated".to_string());
+        let update = webhook.diff(&desired).unwrap();
+        assert_eq!(update.secret.as_deref(), Some("rotated"));
+        assert_eq!(update.status, None);
+    }
+}
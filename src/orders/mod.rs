@@ -0,0 +1,9 @@
+#[cfg(feature = "rust_decimal")]
+mod totals;
+#[cfg(feature = "rust_decimal")]
+pub use totals::*;
+
+#[cfg(feature = "chrono")]
+mod sync;
+#[cfg(feature = "chrono")]
+pub use sync::*;
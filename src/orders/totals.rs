@@ -0,0 +1,457 @@
+use std::fmt;
+
+use crate::{currency_decimals, round_for, Money, Order, OrderId, Tax};
+
+/// The order field or line a mismatch was found in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TotalsComponent {
+    Total,
+    TotalTax,
+    CartTax,
+    ShippingTotal,
+    ShippingTax,
+    DiscountTotal,
+    DiscountTax,
+    /// `coupon_lines` discounts against `discount_total`.
+    CouponDiscounts,
+    /// `tax_lines` against `total_tax`.
+    TaxLines,
+    /// A line item's `total_tax` against its per-rate `taxes`.
+    LineItemTax {
+        line_id: i32,
+    },
+    /// A line item's `total` is above its `subtotal`, i.e. a negative discount.
+    LineItemDiscount {
+        line_id: i32,
+    },
+    ShippingLineTax {
+        line_id: i32,
+    },
+}
+
+impl fmt::Display for TotalsComponent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TotalsComponent::Total => write!(f, "total"),
+            TotalsComponent::TotalTax => write!(f, "total_tax"),
+            TotalsComponent::CartTax => write!(f, "cart_tax"),
+            TotalsComponent::ShippingTotal => write!(f, "shipping_total"),
+            TotalsComponent::ShippingTax => write!(f, "shipping_tax"),
+            TotalsComponent::DiscountTotal => write!(f, "discount_total"),
+            TotalsComponent::DiscountTax => write!(f, "discount_tax"),
+            TotalsComponent::CouponDiscounts => write!(f, "coupon_lines"),
+            TotalsComponent::TaxLines => write!(f, "tax_lines"),
+            TotalsComponent::LineItemTax { line_id } => {
+                write!(f, "line_items[{}].total_tax", line_id)
+            }
+            TotalsComponent::LineItemDiscount { line_id } => {
+                write!(f, "line_items[{}].total", line_id)
+            }
+            TotalsComponent::ShippingLineTax { line_id } => {
+                write!(f, "shipping_lines[{}].total_tax", line_id)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TotalsMismatch {
+    pub component: TotalsComponent,
+    /// Recomputed from the order's parts.
+    pub expected: Money,
+    /// As stored on the order.
+    pub actual: Money,
+    /// How `expected` was computed.
+    pub explanation: String,
+}
+
+impl TotalsMismatch {
+    pub fn difference(&self) -> Money {
+        self.actual - self.expected
+    }
+}
+
+impl fmt::Display for TotalsMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} is {}, expected {} ({}), off by {}",
+            self.component,
+            self.actual,
+            self.expected,
+            self.explanation,
+            self.difference()
+        )
+    }
+}
+
+/// The order-level amounts rebuilt from the lines.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RecomputedTotals {
+    pub items_subtotal: Money,
+    pub items_total: Money,
+    pub fees_total: Money,
+    pub shipping_total: Money,
+    pub shipping_tax: Money,
+    pub cart_tax: Money,
+    pub total_tax: Money,
+    pub discount_total: Money,
+    pub discount_tax: Money,
+    pub gift_cards: Money,
+    pub total: Money,
+}
+
+#[derive(Debug, Clone)]
+pub struct TotalsReport {
+    pub order_id: OrderId,
+    pub currency: String,
+    pub tolerance: Money,
+    pub recomputed: RecomputedTotals,
+    pub mismatches: Vec<TotalsMismatch>,
+}
+
+impl TotalsReport {
+    pub fn is_consistent(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+
+    /// The mismatch of the `total` field, the one payouts are based on.
+    pub fn total_mismatch(&self) -> Option<&TotalsMismatch> {
+        self.mismatches
+            .iter()
+            .find(|mismatch| mismatch.component == TotalsComponent::Total)
+    }
+}
+
+impl fmt::Display for TotalsReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_consistent() {
+            return write!(f, "order {}: totals are consistent", self.order_id);
+        }
+        write!(f, "order {}: ", self.order_id)?;
+        for (index, mismatch) in self.mismatches.iter().enumerate() {
+            if index > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{}", mismatch)?;
+        }
+        Ok(())
+    }
+}
+
+/// Recomputes an order's totals from its lines and compares them with the stored ones.
+///
+/// Follows how WooCommerce calculates totals:
+/// `total = Σ line_items.total + Σ fee_lines.total + shipping_total + total_tax - gift cards`,
+/// with `discount_total` as `Σ (subtotal - total)` over the line items. Amounts are
+/// compared after rounding both sides to the currency.
+#[derive(Debug, Clone, Default)]
+pub struct TotalsVerifier {
+    tolerance: Option<Money>,
+}
+
+impl TotalsVerifier {
+    /// Allows one minor unit of the order currency, the most WooCommerce's per-line
+    /// rounding can drift.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Largest absolute difference still accepted.
+    pub fn tolerance(mut self, tolerance: Money) -> Self {
+        self.tolerance = Some(tolerance.abs());
+        self
+    }
+
+    pub fn verify(&self, order: &Order) -> TotalsReport {
+        let currency = order.currency.as_str();
+        let tolerance = self
+            .tolerance
+            .unwrap_or_else(|| Money::new(1, currency_decimals(currency)));
+        let mut check = Checker {
+            currency,
+            tolerance,
+            mismatches: Vec::new(),
+        };
+
+        let mut recomputed = RecomputedTotals::default();
+        for line in &order.line_items {
+            recomputed.items_subtotal += line.subtotal;
+            recomputed.items_total += line.total;
+            recomputed.cart_tax += line.total_tax;
+            recomputed.discount_total += line.subtotal - line.total;
+            recomputed.discount_tax += line.subtotal_tax - line.total_tax;
+
+            if line.total > line.subtotal {
+                check.compare(
+                    TotalsComponent::LineItemDiscount { line_id: line.id },
+                    line.subtotal,
+                    line.total,
+                    || "a line total can't exceed its subtotal".to_string(),
+                );
+            }
+            if !line.taxes.is_empty() {
+                let taxes: Money = line.taxes.iter().map(|tax| tax.total).sum();
+                check.compare(
+                    TotalsComponent::LineItemTax { line_id: line.id },
+                    taxes,
+                    line.total_tax,
+                    || format!("sum of {} tax rates", line.taxes.len()),
+                );
+            }
+        }
+
+        for fee in &order.fee_lines {
+            recomputed.fees_total += fee.total;
+            recomputed.cart_tax += fee.total_tax;
+        }
+
+        for line in &order.shipping_lines {
+            recomputed.shipping_total += line.total;
+            recomputed.shipping_tax += line.total_tax;
+
+            let taxes = line
+                .extra
+                .get("taxes")
+                .and_then(|taxes| serde_json::from_value::<Vec<Tax>>(taxes.clone()).ok());
+            if let Some(taxes) = taxes.filter(|taxes| !taxes.is_empty()) {
+                let sum: Money = taxes.iter().map(|tax| tax.total).sum();
+                check.compare(
+                    TotalsComponent::ShippingLineTax { line_id: line.id },
+                    sum,
+                    line.total_tax,
+                    || format!("sum of {} tax rates", taxes.len()),
+                );
+            }
+        }
+
+        recomputed.total_tax = recomputed.cart_tax + recomputed.shipping_tax;
+        recomputed.gift_cards = order
+            .gift_cards
+            .iter()
+            .flatten()
+            .map(|card| card.amount)
+            .sum();
+        recomputed.total = recomputed.items_total
+            + recomputed.fees_total
+            + recomputed.shipping_total
+            + recomputed.total_tax
+            - recomputed.gift_cards;
+
+        check.compare(
+            TotalsComponent::ShippingTotal,
+            recomputed.shipping_total,
+            order.shipping_total,
+            || format!("sum of {} shipping lines", order.shipping_lines.len()),
+        );
+        check.compare(
+            TotalsComponent::ShippingTax,
+            recomputed.shipping_tax,
+            order.shipping_tax,
+            || format!("sum of {} shipping line taxes", order.shipping_lines.len()),
+        );
+        check.compare(
+            TotalsComponent::CartTax,
+            recomputed.cart_tax,
+            order.cart_tax,
+            || {
+                format!(
+                    "line item taxes {} + fee taxes {}",
+                    order
+                        .line_items
+                        .iter()
+                        .map(|line| line.total_tax)
+                        .sum::<Money>(),
+                    order
+                        .fee_lines
+                        .iter()
+                        .map(|fee| fee.total_tax)
+                        .sum::<Money>(),
+                )
+            },
+        );
+        check.compare(
+            TotalsComponent::TotalTax,
+            recomputed.total_tax,
+            order.total_tax,
+            || {
+                format!(
+                    "cart tax {} + shipping tax {}",
+                    recomputed.cart_tax, recomputed.shipping_tax
+                )
+            },
+        );
+        if !order.tax_lines.is_empty() {
+            let tax_lines: Money = order
+                .tax_lines
+                .iter()
+                .map(|line| line.tax_total + line.shipping_tax_total)
+                .sum();
+            check.compare(
+                TotalsComponent::TaxLines,
+                order.total_tax,
+                tax_lines,
+                || {
+                    format!(
+                        "sum of {} tax lines against total_tax",
+                        order.tax_lines.len()
+                    )
+                },
+            );
+        }
+        check.compare(
+            TotalsComponent::DiscountTotal,
+            recomputed.discount_total,
+            order.discount_total,
+            || {
+                format!(
+                    "line item subtotals {} - totals {}",
+                    recomputed.items_subtotal, recomputed.items_total
+                )
+            },
+        );
+        check.compare(
+            TotalsComponent::DiscountTax,
+            recomputed.discount_tax,
+            order.discount_tax,
+            || "line item subtotal taxes - total taxes".to_string(),
+        );
+        if let Some(coupon_lines) = order
+            .coupon_lines
+            .as_ref()
+            .filter(|lines| !lines.is_empty())
+        {
+            let discounts: Money = coupon_lines.iter().map(|line| line.discount).sum();
+            check.compare(
+                TotalsComponent::CouponDiscounts,
+                order.discount_total,
+                discounts,
+                || {
+                    format!(
+                        "sum of {} coupon lines against discount_total",
+                        coupon_lines.len()
+                    )
+                },
+            );
+        }
+        check.compare(
+            TotalsComponent::Total,
+            recomputed.total,
+            order.total,
+            || {
+                let mut explanation = format!(
+                    "line items {} + fees {} + shipping {} + tax {}",
+                    recomputed.items_total,
+                    recomputed.fees_total,
+                    recomputed.shipping_total,
+                    recomputed.total_tax
+                );
+                if !recomputed.gift_cards.is_zero() {
+                    explanation.push_str(&format!(" - gift cards {}", recomputed.gift_cards));
+                }
+                explanation
+            },
+        );
+
+        TotalsReport {
+            order_id: order.id,
+            currency: order.currency.clone(),
+            tolerance,
+            recomputed,
+            mismatches: check.mismatches,
+        }
+    }
+}
+
+struct Checker<'a> {
+    currency: &'a str,
+    tolerance: Money,
+    mismatches: Vec<TotalsMismatch>,
+}
+
+impl Checker<'_> {
+    fn compare(
+        &mut self,
+        component: TotalsComponent,
+        expected: Money,
+        actual: Money,
+        explain: impl FnOnce() -> String,
+    ) {
+        let expected = round_for(expected, self.currency);
+        let actual = round_for(actual, self.currency);
+        if (actual - expected).abs() > self.tolerance {
+            self.mismatches.push(TotalsMismatch {
+                component,
+                expected,
+                actual,
+                explanation: explain(),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order() -> Order {
+        serde_json::from_str(include_str!("../../fixtures/order.json")).unwrap()
+    }
+
+    #[test]
+    fn test_consistent_order() {
+        let report = TotalsVerifier::new().verify(&order());
+        assert!(report.is_consistent(), "{}", report);
+        assert_eq!(report.recomputed.total, "66.00".parse().unwrap());
+        assert_eq!(report.tolerance, "0.01".parse().unwrap());
+    }
+
+    #[test]
+    fn test_gift_cards_reduce_total() {
+        let mut order: serde_json::Value =
+            serde_json::from_str(include_str!("../../fixtures/order.json")).unwrap();
+        order["total"] = "46.00".into();
+        order["gift_cards"] = serde_json::json!([
+            { "id": 3, "amount": "20.00", "balance": "0.00" }
+        ]);
+        let order: Order = serde_json::from_value(order).unwrap();
+        assert!(!order.extra.contains_key("gift_cards"));
+
+        let report = TotalsVerifier::new().verify(&order);
+        assert!(report.is_consistent(), "{}", report);
+        assert_eq!(report.recomputed.gift_cards, "20.00".parse().unwrap());
+    }
+
+    #[test]
+    fn test_explains_divergence() {
+        let mut order = order();
+        order.total = "71.00".parse().unwrap();
+        order.fee_lines[0].total_tax = "0.51".parse().unwrap();
+
+        let report = TotalsVerifier::new().verify(&order);
+        let total = report.total_mismatch().unwrap();
+        assert_eq!(total.expected, "66.01".parse().unwrap());
+        assert_eq!(total.difference(), "4.99".parse().unwrap());
+        assert_eq!(
+            total.to_string(),
+            "total is 71.00, expected 66.01 (line items 45.00 + fees 5.00 + shipping 10.00 \
+             + tax 6.01), off by 4.99"
+        );
+        assert_eq!(report.mismatches.len(), 1);
+
+        let strict = TotalsVerifier::new().tolerance(Money::ZERO).verify(&order);
+        let components: Vec<_> = strict
+            .mismatches
+            .iter()
+            .map(|m| m.component.clone())
+            .collect();
+        assert_eq!(
+            components,
+            [
+                TotalsComponent::CartTax,
+                TotalsComponent::TotalTax,
+                TotalsComponent::Total
+            ]
+        );
+    }
+}
@@ -0,0 +1,57 @@
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub enum Fault {
+    /// Waits before answering.
+    Latency(Duration),
+    /// Answers with this status code and a WooCommerce style error body.
+    Status(u16),
+    /// Answers normally, but with the JSON body cut in half.
+    MalformedJson,
+}
+
+/// A fault applied to matching requests, either a limited number of times or forever.
+#[derive(Debug, Clone)]
+pub struct FaultRule {
+    pub fault: Fault,
+    /// Only requests whose path contains this are affected.
+    pub path: Option<String>,
+    /// `None` keeps the rule active until the faults are cleared.
+    pub remaining: Option<usize>,
+}
+
+impl FaultRule {
+    pub fn once(fault: Fault) -> Self {
+        Self {
+            fault,
+            path: None,
+            remaining: Some(1),
+        }
+    }
+
+    pub fn always(fault: Fault) -> Self {
+        Self {
+            fault,
+            path: None,
+            remaining: None,
+        }
+    }
+
+    pub fn times(mut self, times: usize) -> Self {
+        self.remaining = Some(times);
+        self
+    }
+
+    pub fn on_path(mut self, path: &str) -> Self {
+        self.path = Some(path.to_string());
+        self
+    }
+
+    pub(crate) fn matches(&self, path: &str) -> bool {
+        self.remaining != Some(0)
+            && self
+                .path
+                .as_ref()
+                .is_none_or(|fragment| path.contains(fragment.as_str()))
+    }
+}
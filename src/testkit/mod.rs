@@ -0,0 +1,262 @@
+mod fault;
+pub use fault::*;
+
+mod store;
+pub use store::*;
+
+mod server;
+pub use server::*;
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serde_json::json;
+
+    use crate::coupons::{CodePattern, CouponIssuer};
+    use crate::orders::{MemoryCheckpointStore, OrderSink, OrderSync};
+    use crate::{
+        CouponClient, CouponLineCreate, CreateCoupon, CreateLineItem, CreateOrder, CustomerId,
+        DiscountType, Order, OrderClient, OrderId, OrderStatus, ProductClient, ProductId,
+        StatusTransition, UpdateCoupon, WooCommerceHttpError,
+    };
+
+    use super::*;
+
+    fn billing() -> crate::ContactDetails {
+        crate::ContactDetails {
+            first_name: "John".to_string(),
+            last_name: "Doe".to_string(),
+            company: "".to_string(),
+            address_1: "969 Market".to_string(),
+            address_2: "".to_string(),
+            city: "San Francisco".to_string(),
+            state: "CA".to_string(),
+            postcode: "94103".to_string(),
+            country: "US".to_string(),
+            email: "john.doe@example.com".to_string(),
+            phone: "".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_order_with_coupon_end_to_end() {
+        let server = FakeWooServer::start().await;
+        let product = server.store().insert(
+            FakeResource::Products,
+            json!({ "name": "Challenge 10k", "regular_price": "100.00" }),
+        );
+        let client = server.client();
+
+        let coupon = client
+            .create_coupon(&CreateCoupon {
+                code: "WELCOME10".to_string(),
+                amount: "10".parse().unwrap(),
+                discount_type: DiscountType::Percent,
+                description: "".to_string(),
+                date_expires_gmt: None,
+                individual_use: false,
+                product_ids: vec![],
+                usage_limit: None,
+                usage_limit_per_user: None,
+                limit_usage_to_x_items: None,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(coupon.code, "welcome10");
+
+        let order = client
+            .create_order(&CreateOrder {
+                set_paid: true,
+                billing: billing(),
+                meta_data: vec![],
+                line_items: vec![CreateLineItem {
+                    product_id: Some(ProductId(product["id"].as_u64().unwrap())),
+                    quantity: 2,
+                    ..Default::default()
+                }],
+                customer_id: CustomerId(0),
+                coupon_lines: vec![CouponLineCreate {
+                    code: "welcome10".to_string(),
+                    exclude_product_ids: None,
+                }],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(order.status, OrderStatus::Processing);
+        assert_eq!(order.total, "180.00".parse().unwrap());
+        assert_eq!(order.discount_total, "20.00".parse().unwrap());
+        assert!(order.date_paid_gmt.is_some());
+
+        let coupon = client.get_coupon(coupon.id).await.unwrap().unwrap();
+        assert_eq!(coupon.usage_count, 1);
+        assert_eq!(coupon.used_by, vec!["john.doe@example.com".to_string()]);
+
+        let expires = "2030-01-01T00:00:00".parse().unwrap();
+        let update = UpdateCoupon {
+            date_expires_gmt: Some(Some(expires)),
+            usage_limit: Some(Some(5)),
+            ..Default::default()
+        };
+        let coupon = client.update_coupon(coupon.id, &update).await.unwrap();
+        assert_eq!(coupon.date_expires_gmt, Some(expires));
+
+        let update = UpdateCoupon {
+            date_expires_gmt: Some(None),
+            ..Default::default()
+        };
+        let coupon = client.update_coupon(coupon.id, &update).await.unwrap();
+        assert_eq!(coupon.date_expires_gmt, None);
+        assert_eq!(coupon.usage_limit, Some(5));
+    }
+
+    #[tokio::test]
+    async fn test_pagination_and_errors() {
+        let server = FakeWooServer::start().await;
+        for i in 0..15 {
+            server.store().insert(
+                FakeResource::Products,
+                json!({ "name": format!("Product {}", i), "regular_price": "10.00" }),
+            );
+        }
+        let client = server.client();
+
+        assert_eq!(client.get_products(1, 10).await.unwrap().len(), 10);
+        assert_eq!(client.get_products(2, 10).await.unwrap().len(), 5);
+
+        match client.get_order(OrderId(1)).await {
+            Err(WooCommerceHttpError::ErrorResponse(err)) => {
+                assert_eq!(err.code, "woocommerce_rest_shop_order_invalid_id");
+                assert_eq!(err.data.status, 404);
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_coupon_issuance_resumes() {
+        let server = FakeWooServer::start().await;
+        let client = server.client();
+        let manifest_path =
+            std::env::temp_dir().join(format!("woo-issuance-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&manifest_path);
+
+        let template = CreateCoupon {
+            discount_type: DiscountType::Percent,
+            amount: "15".parse().unwrap(),
+            usage_limit: Some(1),
+            ..Default::default()
+        };
+        let issuer = CouponIssuer::new(
+            &client,
+            CodePattern::new(6).prefix("SPRING-").check_digit(true),
+            template,
+        )
+        .batch_size(40)
+        .concurrency(2)
+        .manifest_path(&manifest_path);
+
+        server.add_fault(FaultRule::once(Fault::Status(500)).on_path("/coupons/batch"));
+        let manifest = issuer.issue("spring", 120).await.unwrap();
+        assert_eq!(manifest.issued().count(), 80);
+        assert_eq!(manifest.failed().count(), 40);
+
+        // A code of the failed batch got taken in the meantime.
+        let (taken, _) = manifest.failed().next().unwrap();
+        let taken = taken.to_string();
+        server.store().insert(
+            FakeResource::Coupons,
+            json!({ "code": taken, "amount": "5" }),
+        );
+
+        let manifest = issuer.issue("spring", 120).await.unwrap();
+        assert!(manifest.is_complete());
+        assert!(manifest.entries.iter().all(|entry| entry.code != taken));
+        assert_eq!(server.store().all(FakeResource::Coupons).len(), 121);
+        let _ = std::fs::remove_file(&manifest_path);
+    }
+
+    #[tokio::test]
+    async fn test_fault_injection() {
+        let server = FakeWooServer::start().await;
+        let client = server.client();
+
+        server.add_fault(FaultRule::once(Fault::Status(503)).on_path("/coupons"));
+        match client.get_coupons(1, 10).await {
+            Err(WooCommerceHttpError::ErrorResponse(err)) => assert_eq!(err.data.status, 503),
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert!(client.get_coupons(1, 10).await.unwrap().is_empty());
+
+        server.add_fault(FaultRule::once(Fault::MalformedJson));
+        assert!(matches!(
+            client.get_coupons(1, 10).await,
+            Err(WooCommerceHttpError::SerdeError(_))
+        ));
+
+        server.add_fault(FaultRule::once(Fault::Latency(Duration::from_millis(50))));
+        let started = std::time::Instant::now();
+        client.get_coupons(1, 10).await.unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[derive(Default)]
+    struct CollectingSink {
+        orders: Vec<(OrderId, OrderStatus)>,
+    }
+
+    impl OrderSink for CollectingSink {
+        async fn handle(
+            &mut self,
+            order: &Order,
+        ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            self.orders.push((order.id, order.status));
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_order_sync_checkpoints() {
+        let server = FakeWooServer::start().await;
+        let product = server.store().insert(
+            FakeResource::Products,
+            json!({ "name": "Challenge 10k", "regular_price": "100.00" }),
+        );
+        // Created within the same second or two, more than a page of them.
+        for _ in 0..25 {
+            server.store().insert(
+                FakeResource::Orders,
+                json!({ "line_items": [{ "product_id": product["id"], "quantity": 1 }] }),
+            );
+        }
+        let client = server.client();
+        let checkpoints = MemoryCheckpointStore::new();
+        let sync = OrderSync::new(&client, &checkpoints).per_page(10);
+
+        let mut sink = CollectingSink::default();
+        let summary = sync.run(&mut sink).await.unwrap();
+        assert_eq!(summary.emitted, 25);
+        let mut ids: Vec<OrderId> = sink.orders.iter().map(|(id, _)| *id).collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), 25);
+        assert_eq!(checkpoints.checkpoint().unwrap().seen.len(), 25);
+
+        let summary = sync.run(&mut sink).await.unwrap();
+        assert_eq!(summary.emitted, 0);
+        assert_eq!(summary.duplicates, summary.fetched);
+
+        // Updated within the same second as the last run saw it.
+        client
+            .transition_order(ids[3], &StatusTransition::new(OrderStatus::Processing))
+            .await
+            .unwrap();
+        let summary = sync.run(&mut sink).await.unwrap();
+        assert_eq!(summary.emitted, 1);
+        assert_eq!(sink.orders.last(), Some(&(ids[3], OrderStatus::Processing)));
+    }
+}
@@ -0,0 +1,182 @@
+use std::convert::Infallible;
+use std::net::{SocketAddr, TcpListener};
+use std::sync::{Arc, Mutex};
+
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server};
+use reqwest::Url;
+use tokio::sync::oneshot;
+
+use crate::{Auth, WooHttpClient};
+
+use super::{FakeResponse, FakeStore, Fault, FaultRule};
+
+pub const FAKE_CONSUMER_KEY: &str = "ck_testkit";
+pub const FAKE_CONSUMER_SECRET: &str = "cs_testkit";
+
+struct ServerState {
+    store: FakeStore,
+    faults: Mutex<Vec<FaultRule>>,
+}
+
+impl ServerState {
+    fn take_faults(&self, path: &str) -> Vec<Fault> {
+        let mut rules = self.faults.lock().unwrap();
+        let mut faults = Vec::new();
+        for rule in rules.iter_mut().filter(|rule| rule.matches(path)) {
+            faults.push(rule.fault.clone());
+            if let Some(remaining) = rule.remaining.as_mut() {
+                *remaining -= 1;
+            }
+        }
+        rules.retain(|rule| rule.remaining != Some(0));
+        faults
+    }
+}
+
+/// Local HTTP server speaking the `/wp-json/wc/v3` orders, products and coupons API
+/// from in-memory state. Stops when dropped.
+///
+/// ```ignore
+/// let server = FakeWooServer::start().await;
+/// let client = server.client();
+/// let coupons = client.get_coupons(1, 10).await?;
+/// ```
+pub struct FakeWooServer {
+    addr: SocketAddr,
+    state: Arc<ServerState>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl FakeWooServer {
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.set_nonblocking(true).unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let state = Arc::new(ServerState {
+            store: FakeStore::new(&format!("http://{}/wp-json", addr)),
+            faults: Mutex::new(Vec::new()),
+        });
+
+        let service_state = state.clone();
+        let make_service = make_service_fn(move |_| {
+            let state = service_state.clone();
+            async move { Ok::<_, Infallible>(service_fn(move |req| handle(state.clone(), req))) }
+        });
+
+        let (shutdown, shutdown_rx) = oneshot::channel::<()>();
+        let server = Server::from_tcp(listener)
+            .unwrap()
+            .serve(make_service)
+            .with_graceful_shutdown(async {
+                let _ = shutdown_rx.await;
+            });
+        tokio::spawn(server);
+
+        Self {
+            addr,
+            state,
+            shutdown: Some(shutdown),
+        }
+    }
+
+    /// Value to pass as `base_url` to [`WooHttpClient`].
+    pub fn base_url(&self) -> String {
+        format!("http://{}/wp-json", self.addr)
+    }
+
+    pub fn client(&self) -> WooHttpClient {
+        WooHttpClient::with_auth(
+            Auth::basic(FAKE_CONSUMER_KEY, FAKE_CONSUMER_SECRET),
+            &self.base_url(),
+        )
+    }
+
+    pub fn store(&self) -> &FakeStore {
+        &self.state.store
+    }
+
+    pub fn add_fault(&self, rule: FaultRule) {
+        self.state.faults.lock().unwrap().push(rule);
+    }
+
+    pub fn clear_faults(&self) {
+        self.state.faults.lock().unwrap().clear();
+    }
+}
+
+impl Drop for FakeWooServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+async fn handle(state: Arc<ServerState>, req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let method = req.method().as_str().to_string();
+    let url = Url::parse(&format!("http://localhost{}", req.uri())).unwrap();
+    let path = url.path().to_string();
+    let query: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+    let has_credentials = req.headers().contains_key("authorization")
+        || query
+            .iter()
+            .any(|(key, _)| key == "consumer_key" || key == "oauth_signature");
+
+    let body = hyper::body::to_bytes(req.into_body())
+        .await
+        .unwrap_or_default();
+
+    let mut malformed = false;
+    for fault in state.take_faults(&path) {
+        match fault {
+            Fault::Latency(delay) => tokio::time::sleep(delay).await,
+            Fault::Status(status) => {
+                return Ok(respond(FakeResponse::error(
+                    status,
+                    "internal_server_error",
+                    "Injected fault",
+                )))
+            }
+            Fault::MalformedJson => malformed = true,
+        }
+    }
+
+    if !has_credentials {
+        return Ok(respond(FakeResponse::error(
+            401,
+            "woocommerce_rest_cannot_view",
+            "Sorry, you cannot list resources.",
+        )));
+    }
+
+    let Some(resource_path) = path.strip_prefix("/wp-json/wc/v3/") else {
+        return Ok(respond(FakeResponse::error(
+            404,
+            "rest_no_route",
+            "No route was found matching the URL and request method.",
+        )));
+    };
+
+    let response = state.store.handle(&method, resource_path, &query, &body);
+    if malformed {
+        let mut body = response.body.to_string().into_bytes();
+        body.truncate(body.len() / 2);
+        let mut response = respond(response);
+        *response.body_mut() = Body::from(body);
+        return Ok(response);
+    }
+
+    Ok(respond(response))
+}
+
+fn respond(response: FakeResponse) -> Response<Body> {
+    let mut builder = Response::builder()
+        .status(response.status)
+        .header("Content-Type", "application/json; charset=UTF-8");
+    for (name, value) in &response.headers {
+        builder = builder.header(name.as_str(), value.as_str());
+    }
+    builder.body(Body::from(response.body.to_string())).unwrap()
+}
@@ -0,0 +1,1098 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use serde_json::{json, Map, Value};
+
+use crate::WooDateTimeUtc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FakeResource {
+    Orders,
+    Products,
+    Coupons,
+}
+
+impl FakeResource {
+    fn from_segment(segment: &str) -> Option<Self> {
+        match segment {
+            "orders" => Some(FakeResource::Orders),
+            "products" => Some(FakeResource::Products),
+            "coupons" => Some(FakeResource::Coupons),
+            _ => None,
+        }
+    }
+
+    fn segment(&self) -> &'static str {
+        match self {
+            FakeResource::Orders => "orders",
+            FakeResource::Products => "products",
+            FakeResource::Coupons => "coupons",
+        }
+    }
+
+    fn post_type(&self) -> &'static str {
+        match self {
+            FakeResource::Orders => "shop_order",
+            FakeResource::Products => "product",
+            FakeResource::Coupons => "shop_coupon",
+        }
+    }
+}
+
+/// What the fake store answers; turned into an http response by the server.
+#[derive(Debug, Clone)]
+pub struct FakeResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Value,
+}
+
+impl FakeResponse {
+    fn ok(status: u16, body: Value) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body,
+        }
+    }
+
+    /// WooCommerce's error body shape: `{ code, message, data: { status } }`.
+    pub fn error(status: u16, code: &str, message: &str) -> Self {
+        Self::ok(status, error_body(status, code, message))
+    }
+}
+
+fn error_body(status: u16, code: &str, message: &str) -> Value {
+    json!({ "code": code, "message": message, "data": { "status": status } })
+}
+
+struct FakeError {
+    status: u16,
+    code: String,
+    message: String,
+}
+
+impl FakeError {
+    fn new(status: u16, code: &str, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            code: code.to_string(),
+            message: message.into(),
+        }
+    }
+
+    fn invalid_id(resource: FakeResource) -> Self {
+        Self::new(
+            404,
+            &format!("woocommerce_rest_{}_invalid_id", resource.post_type()),
+            "Invalid ID.",
+        )
+    }
+
+    fn into_response(self) -> FakeResponse {
+        FakeResponse::error(self.status, &self.code, &self.message)
+    }
+
+    fn into_batch_item(self, id: i64) -> Value {
+        json!({ "id": id, "error": error_body(self.status, &self.code, &self.message) })
+    }
+}
+
+#[derive(Default)]
+struct StoreData {
+    next_id: i64,
+    orders: BTreeMap<i64, Value>,
+    products: BTreeMap<i64, Value>,
+    coupons: BTreeMap<i64, Value>,
+}
+
+impl StoreData {
+    fn next_id(&mut self) -> i64 {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    fn items(&self, resource: FakeResource) -> &BTreeMap<i64, Value> {
+        match resource {
+            FakeResource::Orders => &self.orders,
+            FakeResource::Products => &self.products,
+            FakeResource::Coupons => &self.coupons,
+        }
+    }
+
+    fn items_mut(&mut self, resource: FakeResource) -> &mut BTreeMap<i64, Value> {
+        match resource {
+            FakeResource::Orders => &mut self.orders,
+            FakeResource::Products => &mut self.products,
+            FakeResource::Coupons => &mut self.coupons,
+        }
+    }
+}
+
+/// In-memory state behind [`FakeWooServer`](super::FakeWooServer), implementing the
+/// `/wc/v3` orders, products and coupons endpoints on plain JSON values.
+pub struct FakeStore {
+    base_url: String,
+    data: Mutex<StoreData>,
+}
+
+impl FakeStore {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.to_string(),
+            data: Mutex::new(StoreData {
+                next_id: 100,
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// Creates a resource the same way `POST /wc/v3/<resource>` does and returns it.
+    pub fn insert(&self, resource: FakeResource, body: Value) -> Value {
+        let mut data = self.data.lock().unwrap();
+        match self.create(&mut data, resource, &body) {
+            Ok(item) => item,
+            Err(err) => panic!("Can not seed {}: {}", resource.segment(), err.message),
+        }
+    }
+
+    pub fn get(&self, resource: FakeResource, id: i64) -> Option<Value> {
+        self.data.lock().unwrap().items(resource).get(&id).cloned()
+    }
+
+    pub fn all(&self, resource: FakeResource) -> Vec<Value> {
+        self.data
+            .lock()
+            .unwrap()
+            .items(resource)
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    /// Routes a request below `/wc/v3/`, e.g. `("GET", "orders/12", ...)`.
+    pub fn handle(
+        &self,
+        method: &str,
+        path: &str,
+        query: &[(String, String)],
+        body: &[u8],
+    ) -> FakeResponse {
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+        let Some(resource) = segments.first().and_then(|s| FakeResource::from_segment(s)) else {
+            return no_route();
+        };
+
+        let body: Value = if body.is_empty() {
+            Value::Object(Map::new())
+        } else {
+            match serde_json::from_slice(body) {
+                Ok(body) => body,
+                Err(_) => {
+                    return FakeResponse::error(
+                        400,
+                        "rest_invalid_json",
+                        "Invalid JSON body passed.",
+                    )
+                }
+            }
+        };
+
+        let mut data = self.data.lock().unwrap();
+        let result = match (method, &segments[1..]) {
+            ("GET", []) => return self.list(&data, resource, query),
+            ("POST", []) => self
+                .create(&mut data, resource, &body)
+                .map(|item| FakeResponse::ok(201, item)),
+            ("POST", ["batch"]) | ("PUT", ["batch"]) | ("PATCH", ["batch"]) => {
+                Ok(self.batch(&mut data, resource, &body))
+            }
+            (_, [id]) => {
+                let Ok(id) = id.parse::<i64>() else {
+                    return no_route();
+                };
+                match method {
+                    "GET" => data
+                        .items(resource)
+                        .get(&id)
+                        .cloned()
+                        .ok_or_else(|| FakeError::invalid_id(resource))
+                        .map(|item| FakeResponse::ok(200, item)),
+                    "PUT" | "PATCH" | "POST" => self
+                        .update(&mut data, resource, id, &body)
+                        .map(|item| FakeResponse::ok(200, item)),
+                    "DELETE" => {
+                        let force = query
+                            .iter()
+                            .any(|(key, value)| key == "force" && value == "true");
+                        self.delete(&mut data, resource, id, force)
+                            .map(|item| FakeResponse::ok(200, item))
+                    }
+                    _ => return no_route(),
+                }
+            }
+            _ => return no_route(),
+        };
+
+        result.unwrap_or_else(FakeError::into_response)
+    }
+
+    fn list(
+        &self,
+        data: &StoreData,
+        resource: FakeResource,
+        query: &[(String, String)],
+    ) -> FakeResponse {
+        let param = |name: &str| {
+            query
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.as_str())
+        };
+
+        let page: usize = param("page").and_then(|p| p.parse().ok()).unwrap_or(1);
+        let per_page: usize = param("per_page").and_then(|p| p.parse().ok()).unwrap_or(10);
+        if page == 0 || per_page == 0 || per_page > 100 {
+            return FakeResponse::error(
+                400,
+                "rest_invalid_param",
+                "Invalid parameter(s): per_page",
+            );
+        }
+
+        let ids = |name: &str| -> Vec<i64> {
+            param(name)
+                .map(|ids| {
+                    ids.split(',')
+                        .filter_map(|id| id.trim().parse().ok())
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        let include = ids("include");
+        let exclude = ids("exclude");
+        let statuses: Vec<&str> = param("status")
+            .filter(|status| *status != "any")
+            .map(|status| status.split(',').collect())
+            .unwrap_or_default();
+        let code = param("code").map(|code| code.to_lowercase());
+        let search = param("search").map(|search| search.to_lowercase());
+        let after = param("after").map(normalize_date);
+        let before = param("before").map(normalize_date);
+        let modified_after = param("modified_after").map(normalize_date);
+        let modified_before = param("modified_before").map(normalize_date);
+
+        let mut items: Vec<&Value> = data
+            .items(resource)
+            .values()
+            .filter(|item| {
+                let id = item["id"].as_i64().unwrap_or_default();
+                let created = item["date_created_gmt"].as_str().unwrap_or_default();
+                let modified = item["date_modified_gmt"].as_str().unwrap_or_default();
+                let label = item["name"]
+                    .as_str()
+                    .or_else(|| item["code"].as_str())
+                    .unwrap_or_default()
+                    .to_lowercase();
+
+                (include.is_empty() || include.contains(&id))
+                    && !exclude.contains(&id)
+                    && (statuses.is_empty()
+                        || statuses.contains(&item["status"].as_str().unwrap_or_default()))
+                    && (statuses.contains(&"trash") || item["status"] != "trash")
+                    && code.as_ref().is_none_or(|code| {
+                        item["code"].as_str().unwrap_or_default() == code.as_str()
+                    })
+                    && search.as_ref().is_none_or(|s| label.contains(s.as_str()))
+                    && after.as_ref().is_none_or(|after| created > after.as_str())
+                    && before
+                        .as_ref()
+                        .is_none_or(|before| created < before.as_str())
+                    && modified_after
+                        .as_ref()
+                        .is_none_or(|d| modified > d.as_str())
+                    && modified_before
+                        .as_ref()
+                        .is_none_or(|d| modified < d.as_str())
+            })
+            .collect();
+
+        let sort_key = match param("orderby").unwrap_or("date") {
+            "id" => "id",
+            "modified" => "date_modified_gmt",
+            "title" => "name",
+            "code" => "code",
+            _ => "date_created_gmt",
+        };
+        items.sort_by(|a, b| {
+            let ordering = match (a[sort_key].as_i64(), b[sort_key].as_i64()) {
+                (Some(a), Some(b)) => a.cmp(&b),
+                _ => a[sort_key]
+                    .as_str()
+                    .unwrap_or_default()
+                    .cmp(b[sort_key].as_str().unwrap_or_default()),
+            };
+            ordering.then_with(|| a["id"].as_i64().cmp(&b["id"].as_i64()))
+        });
+        if param("order").unwrap_or("desc") == "desc" {
+            items.reverse();
+        }
+
+        let total = items.len();
+        let total_pages = total.div_ceil(per_page);
+        let page_items: Vec<Value> = items
+            .into_iter()
+            .skip((page - 1) * per_page)
+            .take(per_page)
+            .cloned()
+            .collect();
+
+        let mut response = FakeResponse::ok(200, Value::Array(page_items));
+        response
+            .headers
+            .push(("X-WP-Total".to_string(), total.to_string()));
+        response
+            .headers
+            .push(("X-WP-TotalPages".to_string(), total_pages.to_string()));
+        response
+    }
+
+    fn create(
+        &self,
+        data: &mut StoreData,
+        resource: FakeResource,
+        body: &Value,
+    ) -> Result<Value, FakeError> {
+        let item = match resource {
+            FakeResource::Products => self.build_product(data, body),
+            FakeResource::Coupons => self.build_coupon(data, body)?,
+            FakeResource::Orders => self.build_order(data, body)?,
+        };
+
+        let id = item["id"].as_i64().unwrap();
+        data.items_mut(resource).insert(id, item.clone());
+        Ok(item)
+    }
+
+    fn update(
+        &self,
+        data: &mut StoreData,
+        resource: FakeResource,
+        id: i64,
+        body: &Value,
+    ) -> Result<Value, FakeError> {
+        let mut item = data
+            .items(resource)
+            .get(&id)
+            .cloned()
+            .ok_or_else(|| FakeError::invalid_id(resource))?;
+
+        if resource == FakeResource::Coupons {
+            if let Some(code) = body.get("code").and_then(Value::as_str) {
+                let code = code.to_lowercase();
+                if code != item["code"] && find_coupon(data, &code).is_some() {
+                    return Err(coupon_exists(&code));
+                }
+            }
+        }
+
+        let previous_status = item["status"].clone();
+        let Some(fields) = body.as_object() else {
+            return Ok(item);
+        };
+        for (key, value) in fields {
+            if is_read_only(key) {
+                continue;
+            }
+            if key == "meta_data" {
+                merge_meta(data, &mut item, value);
+                continue;
+            }
+            write_field(&mut item, key, value);
+        }
+
+        let now = now_gmt();
+        item["date_modified"] = json!(now);
+        item["date_modified_gmt"] = json!(now);
+        match resource {
+            FakeResource::Products => refresh_product(&mut item),
+            FakeResource::Coupons => {
+                let code = item["code"].as_str().unwrap_or_default().to_lowercase();
+                item["code"] = json!(code);
+            }
+            FakeResource::Orders => {
+                if item["status"] != previous_status {
+                    refresh_order_status(&mut item, &now);
+                }
+            }
+        }
+
+        data.items_mut(resource).insert(id, item.clone());
+        Ok(item)
+    }
+
+    fn delete(
+        &self,
+        data: &mut StoreData,
+        resource: FakeResource,
+        id: i64,
+        force: bool,
+    ) -> Result<Value, FakeError> {
+        let items = data.items_mut(resource);
+        if !items.contains_key(&id) {
+            return Err(FakeError::invalid_id(resource));
+        }
+
+        if force || resource == FakeResource::Coupons {
+            return Ok(items.remove(&id).unwrap());
+        }
+
+        let item = items.get_mut(&id).unwrap();
+        item["status"] = json!("trash");
+        Ok(item.clone())
+    }
+
+    fn batch(&self, data: &mut StoreData, resource: FakeResource, body: &Value) -> FakeResponse {
+        let entries = |name: &str| body[name].as_array().cloned().unwrap_or_default();
+
+        let create: Vec<Value> = entries("create")
+            .iter()
+            .map(|entry| {
+                self.create(data, resource, entry)
+                    .unwrap_or_else(|err| err.into_batch_item(0))
+            })
+            .collect();
+
+        let update: Vec<Value> = entries("update")
+            .iter()
+            .map(|entry| {
+                let id = entry["id"].as_i64().unwrap_or_default();
+                self.update(data, resource, id, entry)
+                    .unwrap_or_else(|err| err.into_batch_item(id))
+            })
+            .collect();
+
+        let delete: Vec<Value> = entries("delete")
+            .iter()
+            .map(|entry| {
+                let id = entry.as_i64().unwrap_or_default();
+                self.delete(data, resource, id, true)
+                    .unwrap_or_else(|err| err.into_batch_item(id))
+            })
+            .collect();
+
+        FakeResponse::ok(
+            200,
+            json!({ "create": create, "update": update, "delete": delete }),
+        )
+    }
+
+    fn links(&self, resource: FakeResource, id: i64) -> Value {
+        let collection = format!("{}/wc/v3/{}", self.base_url, resource.segment());
+        json!({
+            "self": [{ "href": format!("{}/{}", collection, id) }],
+            "collection": [{ "href": collection }],
+        })
+    }
+
+    fn build_product(&self, data: &mut StoreData, body: &Value) -> Value {
+        let id = data.next_id();
+        let now = now_gmt();
+        let name = body["name"].as_str().unwrap_or("Product").to_string();
+        let slug = name.to_lowercase().replace(' ', "-");
+
+        let mut product = json!({
+            "id": id,
+            "name": name,
+            "slug": slug,
+            "permalink": format!("{}/product/{}/", self.site_url(), slug),
+            "date_created": now,
+            "date_created_gmt": now,
+            "date_modified": now,
+            "date_modified_gmt": now,
+            "type": "simple",
+            "status": "publish",
+            "featured": false,
+            "catalog_visibility": "visible",
+            "description": "",
+            "short_description": "",
+            "sku": "",
+            "price": "",
+            "regular_price": "",
+            "sale_price": "",
+            "date_on_sale_from": null,
+            "date_on_sale_from_gmt": null,
+            "date_on_sale_to": null,
+            "date_on_sale_to_gmt": null,
+            "price_html": "",
+            "on_sale": false,
+            "purchasable": false,
+            "total_sales": 0,
+            "virtual": false,
+            "downloadable": false,
+            "downloads": [],
+            "download_limit": -1,
+            "download_expiry": -1,
+            "external_url": "",
+            "button_text": "",
+            "tax_status": "taxable",
+            "tax_class": "",
+            "manage_stock": false,
+            "stock_quantity": null,
+            "stock_status": "instock",
+            "backorders": "no",
+            "backorders_allowed": false,
+            "backordered": false,
+            "sold_individually": false,
+            "weight": "",
+            "dimensions": { "length": "", "width": "", "height": "" },
+            "shipping_required": true,
+            "shipping_taxable": true,
+            "shipping_class": "",
+            "shipping_class_id": 0,
+            "reviews_allowed": true,
+            "average_rating": "0.00",
+            "rating_count": 0,
+            "related_ids": [],
+            "upsell_ids": [],
+            "cross_sell_ids": [],
+            "parent_id": 0,
+            "purchase_note": "",
+            "categories": [],
+            "tags": [],
+            "images": [],
+            "attributes": [],
+            "default_attributes": [],
+            "variations": [],
+            "grouped_products": [],
+            "menu_order": 0,
+            "meta_data": [],
+            "_links": self.links(FakeResource::Products, id),
+        });
+
+        overlay(data, &mut product, body);
+        for category in product["categories"].as_array_mut().into_iter().flatten() {
+            fill_defaults(category, &[("name", json!("")), ("slug", json!(""))]);
+        }
+        for image in product["images"].as_array_mut().into_iter().flatten() {
+            let image_id = image["id"].as_i64().unwrap_or_default();
+            fill_defaults(
+                image,
+                &[
+                    ("id", json!(image_id)),
+                    ("date_created", json!(now)),
+                    ("date_created_gmt", json!(now)),
+                    ("date_modified", json!(now)),
+                    ("date_modified_gmt", json!(now)),
+                    ("src", json!("")),
+                    ("name", json!("")),
+                    ("alt", json!("")),
+                ],
+            );
+        }
+        refresh_product(&mut product);
+        product
+    }
+
+    fn build_coupon(&self, data: &mut StoreData, body: &Value) -> Result<Value, FakeError> {
+        let code = body["code"].as_str().unwrap_or_default().to_lowercase();
+        if code.is_empty() {
+            return Err(FakeError::new(
+                400,
+                "rest_missing_callback_param",
+                "Missing parameter(s): code",
+            ));
+        }
+        if find_coupon(data, &code).is_some() {
+            return Err(coupon_exists(&code));
+        }
+
+        let id = data.next_id();
+        let now = now_gmt();
+        let mut coupon = json!({
+            "id": id,
+            "code": code,
+            "amount": "0",
+            "date_created": now,
+            "date_created_gmt": now,
+            "date_modified": now,
+            "date_modified_gmt": now,
+            "discount_type": "fixed_cart",
+            "description": "",
+            "date_expires": null,
+            "date_expires_gmt": null,
+            "usage_count": 0,
+            "individual_use": false,
+            "product_ids": [],
+            "excluded_product_ids": [],
+            "usage_limit": null,
+            "usage_limit_per_user": null,
+            "limit_usage_to_x_items": null,
+            "free_shipping": false,
+            "product_categories": [],
+            "excluded_product_categories": [],
+            "exclude_sale_items": false,
+            "minimum_amount": "0.00",
+            "maximum_amount": "0.00",
+            "email_restrictions": [],
+            "used_by": [],
+            "meta_data": [],
+            "_links": self.links(FakeResource::Coupons, id),
+        });
+
+        overlay(data, &mut coupon, body);
+        coupon["code"] = json!(code);
+        if let Some(expires) = coupon["date_expires_gmt"].as_str().map(normalize_date) {
+            coupon["date_expires_gmt"] = json!(expires);
+            coupon["date_expires"] = json!(expires);
+        }
+        Ok(coupon)
+    }
+
+    fn build_order(&self, data: &mut StoreData, body: &Value) -> Result<Value, FakeError> {
+        let mut line_items = Vec::new();
+        for line in body["line_items"].as_array().into_iter().flatten() {
+            let product_id = match line["sku"].as_str() {
+                Some(sku) if line.get("product_id").is_none() => data
+                    .products
+                    .iter()
+                    .find(|(_, product)| product["sku"] == sku)
+                    .map(|(id, _)| *id)
+                    .unwrap_or_default(),
+                _ => line["product_id"].as_i64().unwrap_or_default(),
+            };
+            let Some(product) = data.products.get(&product_id).cloned() else {
+                return Err(FakeError::new(
+                    400,
+                    "woocommerce_rest_invalid_product_id",
+                    "Product ID provided is invalid.",
+                ));
+            };
+
+            let quantity = line["quantity"].as_i64().unwrap_or(1).max(1);
+            let price = amount(&product["price"]);
+            let subtotal = line
+                .get("subtotal")
+                .map(amount)
+                .unwrap_or(price * quantity as f64);
+            let total = line.get("total").map(amount).unwrap_or(subtotal);
+            line_items.push(json!({
+                "id": data.next_id(),
+                "name": product["name"],
+                "product_id": product_id,
+                "variation_id": line["variation_id"].as_i64().unwrap_or_default(),
+                "quantity": quantity,
+                "tax_class": "",
+                "subtotal": format_amount(subtotal),
+                "subtotal_tax": "0.00",
+                "total": format_amount(total),
+                "total_tax": "0.00",
+                "taxes": [],
+                "meta_data": build_meta(data, &line["meta_data"]),
+                "sku": product["sku"],
+                "price": total / quantity as f64,
+            }));
+        }
+
+        let billing_email = body["billing"]["email"].as_str().unwrap_or_default();
+        let customer_id = body["customer_id"].as_i64().unwrap_or_default();
+        let mut coupon_lines = Vec::new();
+        for coupon_line in body["coupon_lines"].as_array().into_iter().flatten() {
+            let code = coupon_line["code"]
+                .as_str()
+                .unwrap_or_default()
+                .to_lowercase();
+            let Some(coupon_id) = find_coupon(data, &code) else {
+                return Err(FakeError::new(
+                    400,
+                    "woocommerce_rest_invalid_coupon",
+                    format!("Coupon \"{}\" does not exist!", code),
+                ));
+            };
+
+            let coupon = data.coupons.get_mut(&coupon_id).unwrap();
+            let discount = apply_coupon(coupon, &mut line_items);
+            coupon["usage_count"] = json!(coupon["usage_count"].as_i64().unwrap_or_default() + 1);
+            let used_by = if customer_id > 0 {
+                customer_id.to_string()
+            } else {
+                billing_email.to_string()
+            };
+            if let Some(list) = coupon["used_by"].as_array_mut() {
+                list.push(json!(used_by));
+            }
+            let coupon = coupon.clone();
+
+            coupon_lines.push(json!({
+                "id": data.next_id(),
+                "code": code,
+                "discount": format_amount(discount),
+                "discount_tax": "0.00",
+                "discount_type": coupon["discount_type"],
+                "nominal_amount": amount(&coupon["amount"]),
+                "meta_data": [],
+            }));
+        }
+
+        let shipping_lines: Vec<Value> = body["shipping_lines"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|line| {
+                json!({
+                    "id": data.next_id(),
+                    "method_title": line["method_title"].as_str().unwrap_or_default(),
+                    "method_id": line["method_id"].as_str().unwrap_or_default(),
+                    "total": format_amount(amount(&line["total"])),
+                    "total_tax": "0.00",
+                    "taxes": [],
+                    "meta_data": [],
+                })
+            })
+            .collect();
+
+        let fee_lines: Vec<Value> = body["fee_lines"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|line| {
+                json!({
+                    "id": data.next_id(),
+                    "name": line["name"].as_str().unwrap_or_default(),
+                    "tax_class": line["tax_class"].as_str().unwrap_or_default(),
+                    "tax_status": line["tax_status"].as_str().unwrap_or("taxable"),
+                    "total": format_amount(amount(&line["total"])),
+                    "total_tax": "0.00",
+                    "taxes": [],
+                    "meta_data": [],
+                })
+            })
+            .collect();
+
+        let sum = |lines: &[Value], field: &str| -> f64 {
+            lines.iter().map(|line| amount(&line[field])).sum()
+        };
+        let lines_total = sum(&line_items, "total");
+        let discount_total = sum(&line_items, "subtotal") - lines_total;
+        let shipping_total = sum(&shipping_lines, "total");
+        let total = lines_total + shipping_total + sum(&fee_lines, "total");
+
+        let set_paid = body["set_paid"].as_bool().unwrap_or(false);
+        let status = body["status"]
+            .as_str()
+            .unwrap_or(if set_paid { "processing" } else { "pending" })
+            .to_string();
+
+        let id = data.next_id();
+        let now = now_gmt();
+        let order_key = format!("wc_order_{:x}", id * 7919);
+        let mut order = json!({
+            "id": id,
+            "parent_id": 0,
+            "number": id.to_string(),
+            "order_key": order_key,
+            "created_via": "rest-api",
+            "version": "8.0.0",
+            "status": status,
+            "currency": body["currency"].as_str().unwrap_or("USD"),
+            "date_created": now,
+            "date_created_gmt": now,
+            "date_modified": now,
+            "date_modified_gmt": now,
+            "discount_total": format_amount(discount_total),
+            "discount_tax": "0.00",
+            "shipping_total": format_amount(shipping_total),
+            "shipping_tax": "0.00",
+            "cart_tax": "0.00",
+            "total": format_amount(total),
+            "total_tax": "0.00",
+            "prices_include_tax": false,
+            "customer_id": customer_id,
+            "customer_ip_address": "",
+            "customer_user_agent": "",
+            "customer_note": body["customer_note"].as_str().unwrap_or_default(),
+            "billing": contact_details(&body["billing"]),
+            "shipping": contact_details(&body["shipping"]),
+            "payment_method": body["payment_method"].as_str().unwrap_or_default(),
+            "payment_method_title": body["payment_method_title"].as_str().unwrap_or_default(),
+            "transaction_id": body["transaction_id"].as_str().unwrap_or_default(),
+            "date_paid": null,
+            "date_paid_gmt": null,
+            "date_completed": null,
+            "date_completed_gmt": null,
+            "cart_hash": "",
+            "meta_data": build_meta(data, &body["meta_data"]),
+            "line_items": line_items,
+            "tax_lines": [],
+            "shipping_lines": shipping_lines,
+            "fee_lines": fee_lines,
+            "coupon_lines": coupon_lines,
+            "refunds": [],
+            "payment_url": format!(
+                "{}/checkout/order-pay/{}/?pay_for_order=true&key={}",
+                self.site_url(),
+                id,
+                order_key
+            ),
+            "is_editable": false,
+            "needs_payment": false,
+            "needs_processing": true,
+            "currency_symbol": "$",
+            "_links": self.links(FakeResource::Orders, id),
+        });
+
+        refresh_order_status(&mut order, &now);
+        Ok(order)
+    }
+
+    fn site_url(&self) -> &str {
+        self.base_url.trim_end_matches("/wp-json")
+    }
+}
+
+fn no_route() -> FakeResponse {
+    FakeResponse::error(
+        404,
+        "rest_no_route",
+        "No route was found matching the URL and request method.",
+    )
+}
+
+fn coupon_exists(code: &str) -> FakeError {
+    FakeError::new(
+        400,
+        "woocommerce_rest_coupon_code_already_exists",
+        format!("The coupon code {} already exists", code),
+    )
+}
+
+fn find_coupon(data: &StoreData, code: &str) -> Option<i64> {
+    data.coupons
+        .iter()
+        .find(|(_, coupon)| coupon["code"].as_str() == Some(code))
+        .map(|(id, _)| *id)
+}
+
+fn is_read_only(key: &str) -> bool {
+    matches!(
+        key,
+        "id" | "date_created"
+            | "date_created_gmt"
+            | "date_modified"
+            | "date_modified_gmt"
+            | "usage_count"
+            | "used_by"
+            | "_links"
+    )
+}
+
+/// Copies the writable fields of a request body onto a freshly built resource.
+fn overlay(data: &mut StoreData, item: &mut Value, body: &Value) {
+    let Some(fields) = body.as_object() else {
+        return;
+    };
+    for (key, value) in fields {
+        if is_read_only(key) || item.get(key).is_none() {
+            continue;
+        }
+        if key == "meta_data" {
+            item["meta_data"] = build_meta(data, value);
+            continue;
+        }
+        write_field(item, key, value);
+    }
+}
+
+/// Like WooCommerce, `null` leaves a field alone and `""` clears it. A cleared date reads
+/// back as `null`, along with its store local twin.
+fn write_field(item: &mut Value, key: &str, value: &Value) {
+    if value.is_null() {
+        return;
+    }
+    if key.starts_with("date_") && value.as_str() == Some("") {
+        item[key] = Value::Null;
+        if let Some(local) = key.strip_suffix("_gmt") {
+            item[local] = Value::Null;
+        }
+        return;
+    }
+    item[key] = value.clone();
+}
+
+fn fill_defaults(item: &mut Value, defaults: &[(&str, Value)]) {
+    for (key, value) in defaults {
+        if item.get(*key).is_none_or(Value::is_null) {
+            item[*key] = value.clone();
+        }
+    }
+}
+
+fn build_meta(data: &mut StoreData, meta: &Value) -> Value {
+    let entries = meta
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|entry| {
+            json!({
+                "id": data.next_id(),
+                "key": entry["key"],
+                "value": entry["value"],
+            })
+        })
+        .collect();
+    Value::Array(entries)
+}
+
+/// Meta entries with an id replace that entry, the others are added, like WooCommerce does.
+fn merge_meta(data: &mut StoreData, item: &mut Value, meta: &Value) {
+    for entry in meta.as_array().into_iter().flatten() {
+        let existing = item["meta_data"].as_array_mut().and_then(|list| {
+            list.iter_mut().find(|current| {
+                (entry.get("id").is_some() && current["id"] == entry["id"])
+                    || current["key"] == entry["key"]
+            })
+        });
+        match existing {
+            Some(current) => current["value"] = entry["value"].clone(),
+            None => {
+                let id = data.next_id();
+                if let Some(list) = item["meta_data"].as_array_mut() {
+                    list.push(json!({ "id": id, "key": entry["key"], "value": entry["value"] }));
+                }
+            }
+        }
+    }
+}
+
+fn contact_details(details: &Value) -> Value {
+    let mut result = json!({
+        "first_name": "",
+        "last_name": "",
+        "company": "",
+        "address_1": "",
+        "address_2": "",
+        "city": "",
+        "state": "",
+        "postcode": "",
+        "country": "",
+        "email": "",
+        "phone": "",
+    });
+    if let Some(fields) = details.as_object() {
+        for (key, value) in fields {
+            result[key.as_str()] = value.clone();
+        }
+    }
+    result
+}
+
+fn refresh_product(product: &mut Value) {
+    let regular_price = product["regular_price"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string();
+    let sale_price = product["sale_price"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string();
+    let price = if sale_price.is_empty() {
+        regular_price
+    } else {
+        sale_price.clone()
+    };
+
+    product["on_sale"] = json!(!sale_price.is_empty());
+    product["purchasable"] = json!(!price.is_empty());
+    product["price_html"] = json!(if price.is_empty() {
+        String::new()
+    } else {
+        format!("<span class=\"amount\">${}</span>", price)
+    });
+    product["price"] = json!(price);
+}
+
+fn refresh_order_status(order: &mut Value, now: &str) {
+    let status = order["status"].as_str().unwrap_or_default().to_string();
+    let paid = matches!(status.as_str(), "processing" | "completed");
+    if paid && order["date_paid_gmt"].is_null() {
+        order["date_paid"] = json!(now);
+        order["date_paid_gmt"] = json!(now);
+    }
+    if status == "completed" && order["date_completed_gmt"].is_null() {
+        order["date_completed"] = json!(now);
+        order["date_completed_gmt"] = json!(now);
+    }
+
+    let total = amount(&order["total"]);
+    order["is_editable"] = json!(matches!(
+        status.as_str(),
+        "pending" | "on-hold" | "checkout-draft"
+    ));
+    order["needs_payment"] = json!(matches!(status.as_str(), "pending" | "failed") && total > 0.0);
+}
+
+/// Discounts the order lines and returns the discount granted.
+fn apply_coupon(coupon: &Value, line_items: &mut [Value]) -> f64 {
+    let coupon_amount = amount(&coupon["amount"]);
+    let product_ids: Vec<i64> = coupon["product_ids"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_i64)
+        .collect();
+    let eligible = |line: &Value| {
+        product_ids.is_empty() || product_ids.contains(&line["product_id"].as_i64().unwrap_or(0))
+    };
+
+    let eligible_total: f64 = line_items
+        .iter()
+        .filter(|line| eligible(line))
+        .map(|line| amount(&line["total"]))
+        .sum();
+    if eligible_total <= 0.0 {
+        return 0.0;
+    }
+
+    let mut granted = 0.0;
+    for line in line_items.iter_mut().filter(|line| eligible(line)) {
+        let total = amount(&line["total"]);
+        let quantity = line["quantity"].as_i64().unwrap_or(1) as f64;
+        let discount = match coupon["discount_type"].as_str().unwrap_or_default() {
+            "percent" => total * coupon_amount / 100.0,
+            "fixed_product" => (coupon_amount * quantity).min(total),
+            _ => coupon_amount.min(eligible_total) * total / eligible_total,
+        };
+        let discount = (discount * 100.0).round() / 100.0;
+        let new_total = total - discount;
+        line["total"] = json!(format_amount(new_total));
+        line["price"] = json!(new_total / quantity);
+        granted += discount;
+    }
+    granted
+}
+
+fn amount(value: &Value) -> f64 {
+    match value {
+        Value::Number(number) => number.as_f64().unwrap_or_default(),
+        Value::String(text) => text.parse().unwrap_or_default(),
+        _ => 0.0,
+    }
+}
+
+fn format_amount(value: f64) -> String {
+    format!("{:.2}", value)
+}
+
+/// Brings dates like `2024-01-01T02:00:00+02:00` to the stored UTC `YYYY-MM-DDTHH:MM:SS`,
+/// which compares correctly as a string.
+fn normalize_date(date: &str) -> String {
+    match date.parse::<WooDateTimeUtc>() {
+        Ok(date) => date.to_string(),
+        Err(_) => date.chars().take(19).collect(),
+    }
+}
+
+/// Current time in WooCommerce's `YYYY-MM-DDTHH:MM:SS` format, UTC.
+pub(crate) fn now_gmt() -> String {
+    WooDateTimeUtc::now().to_string()
+}
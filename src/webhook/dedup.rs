@@ -0,0 +1,398 @@
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use std::sync::Mutex;
+
+use crate::WooDateTimeUtc;
+
+use super::{WebhookDelivery, WebhookEvent};
+
+/// Storage behind [`WebhookDeduplicator`]. Implement it on top of Redis, a database
+/// table etc. to share dedup state between several instances of a service.
+///
+/// The webhook server handles deliveries concurrently, so `try_insert_delivery` and
+/// `advance_last_modified` must each be a single atomic step (`SET NX`, an upsert with
+/// a `WHERE` clause, ...). A separate read followed by a write lets two retries of the
+/// same delivery both through.
+#[allow(async_fn_in_trait)]
+pub trait DeliveryStore {
+    async fn has_delivery(&self, delivery_id: &str) -> bool;
+
+    /// Stores `delivery_id` unless it is already there. `false` if it was.
+    async fn try_insert_delivery(&self, delivery_id: &str) -> bool;
+
+    /// Last processed `date_modified_gmt` of a resource, keyed as `<resource>:<id>`.
+    async fn last_modified(&self, resource_key: &str) -> Option<WooDateTimeUtc>;
+
+    /// Moves the resource's last processed version forward to `date_modified_gmt`.
+    /// Leaves it alone and returns the stored version if that is newer.
+    async fn advance_last_modified(
+        &self,
+        resource_key: &str,
+        date_modified_gmt: WooDateTimeUtc,
+    ) -> Result<(), WooDateTimeUtc>;
+}
+
+/// Default [`DeliveryStore`]: keeps the most recently used entries in memory.
+pub struct InMemoryDeliveryStore {
+    deliveries: Mutex<Lru<String, ()>>,
+    versions: Mutex<Lru<String, WooDateTimeUtc>>,
+}
+
+impl InMemoryDeliveryStore {
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0);
+
+        Self {
+            deliveries: Mutex::new(Lru::new(capacity)),
+            versions: Mutex::new(Lru::new(capacity)),
+        }
+    }
+}
+
+impl Default for InMemoryDeliveryStore {
+    fn default() -> Self {
+        Self::new(10_000)
+    }
+}
+
+impl DeliveryStore for InMemoryDeliveryStore {
+    async fn has_delivery(&self, delivery_id: &str) -> bool {
+        self.deliveries
+            .lock()
+            .unwrap()
+            .get(&delivery_id.to_string())
+            .is_some()
+    }
+
+    async fn try_insert_delivery(&self, delivery_id: &str) -> bool {
+        let mut deliveries = self.deliveries.lock().unwrap();
+        let delivery_id = delivery_id.to_string();
+        if deliveries.get(&delivery_id).is_some() {
+            return false;
+        }
+        deliveries.insert(delivery_id, ());
+        true
+    }
+
+    async fn last_modified(&self, resource_key: &str) -> Option<WooDateTimeUtc> {
+        self.versions
+            .lock()
+            .unwrap()
+            .get(&resource_key.to_string())
+            .copied()
+    }
+
+    async fn advance_last_modified(
+        &self,
+        resource_key: &str,
+        date_modified_gmt: WooDateTimeUtc,
+    ) -> Result<(), WooDateTimeUtc> {
+        let mut versions = self.versions.lock().unwrap();
+        let resource_key = resource_key.to_string();
+        if let Some(last_seen) = versions.get(&resource_key).copied() {
+            if last_seen > date_modified_gmt {
+                return Err(last_seen);
+            }
+        }
+        versions.insert(resource_key, date_modified_gmt);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeliveryVerdict {
+    /// Not seen before and not older than what was already processed.
+    Fresh,
+    /// Same delivery id was already processed.
+    Duplicate,
+    /// An older version of the resource than the one already processed.
+    Stale { last_seen: WooDateTimeUtc },
+}
+
+/// Drops retried and out of order webhook deliveries.
+///
+/// Deliveries are recognised by `X-WC-Webhook-Delivery-ID`. For orders, products and
+/// coupons a `date_modified_gmt` older than the last processed version of the same
+/// resource is stale. An equal one passes, the timestamp has one second resolution and
+/// two real changes can share it. [`accept`](Self::accept) claims a delivery atomically. The
+/// [`check`](Self::check) then [`record`](Self::record) pair keeps a failed attempt from
+/// swallowing WooCommerce's retry, but it can let concurrent retries through together.
+pub struct WebhookDeduplicator<S: DeliveryStore = InMemoryDeliveryStore> {
+    store: S,
+}
+
+impl WebhookDeduplicator<InMemoryDeliveryStore> {
+    pub fn in_memory(capacity: usize) -> Self {
+        Self::new(InMemoryDeliveryStore::new(capacity))
+    }
+}
+
+impl<S: DeliveryStore> WebhookDeduplicator<S> {
+    pub fn new(store: S) -> Self {
+        Self { store }
+    }
+
+    pub async fn check(&self, delivery: &WebhookDelivery) -> DeliveryVerdict {
+        if let Some(delivery_id) = &delivery.headers.delivery_id {
+            if self.store.has_delivery(delivery_id).await {
+                return DeliveryVerdict::Duplicate;
+            }
+        }
+
+        if let Some((key, modified)) = resource_version(&delivery.event) {
+            if let Some(last_seen) = self.store.last_modified(&key).await {
+                if modified < last_seen {
+                    return DeliveryVerdict::Stale { last_seen };
+                }
+            }
+        }
+
+        DeliveryVerdict::Fresh
+    }
+
+    /// Never moves a resource's last processed version back, so a late retry of an older
+    /// delivery can't make a newer one look fresh again.
+    pub async fn record(&self, delivery: &WebhookDelivery) {
+        if let Some(delivery_id) = &delivery.headers.delivery_id {
+            self.store.try_insert_delivery(delivery_id).await;
+        }
+
+        if let Some((key, modified)) = resource_version(&delivery.event) {
+            let _ = self.store.advance_last_modified(&key, modified).await;
+        }
+    }
+
+    /// Claims the delivery and returns [`Fresh`](DeliveryVerdict::Fresh) to exactly one of
+    /// several concurrent retries. Convenient when handling can't fail, or a lost delivery
+    /// is acceptable.
+    pub async fn accept(&self, delivery: &WebhookDelivery) -> DeliveryVerdict {
+        if let Some(delivery_id) = &delivery.headers.delivery_id {
+            if !self.store.try_insert_delivery(delivery_id).await {
+                return DeliveryVerdict::Duplicate;
+            }
+        }
+
+        if let Some((key, modified)) = resource_version(&delivery.event) {
+            if let Err(last_seen) = self.store.advance_last_modified(&key, modified).await {
+                return DeliveryVerdict::Stale { last_seen };
+            }
+        }
+
+        DeliveryVerdict::Fresh
+    }
+}
+
+fn resource_version(event: &WebhookEvent) -> Option<(String, WooDateTimeUtc)> {
+    match event {
+        WebhookEvent::Order(order) => {
+            Some((format!("order:{}", order.id), order.date_modified_gmt))
+        }
+        WebhookEvent::Product(product) => {
+            Some((format!("product:{}", product.id), product.date_modified_gmt))
+        }
+        WebhookEvent::Coupon(coupon) => {
+            Some((format!("coupon:{}", coupon.id), coupon.date_modified_gmt))
+        }
+        _ => None,
+    }
+}
+
+/// Tiny least-recently-used map; the oldest entry is evicted once `capacity` is reached.
+struct Lru<K, V> {
+    capacity: usize,
+    tick: u64,
+    entries: HashMap<K, (V, u64)>,
+    order: BTreeMap<u64, K>,
+}
+
+impl<K: Eq + Hash + Clone, V> Lru<K, V> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            tick: 0,
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+        }
+    }
+
+    fn get(&mut self, key: &K) -> Option<&V> {
+        self.tick += 1;
+        let tick = self.tick;
+        let (value, used) = self.entries.get_mut(key)?;
+        self.order.remove(used);
+        self.order.insert(tick, key.clone());
+        *used = tick;
+        Some(value)
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        self.tick += 1;
+        if let Some((_, used)) = self.entries.remove(&key) {
+            self.order.remove(&used);
+        }
+
+        while self.entries.len() >= self.capacity {
+            let Some((_, oldest)) = self.order.pop_first() else {
+                break;
+            };
+            self.entries.remove(&oldest);
+        }
+
+        self.order.insert(self.tick, key.clone());
+        self.entries.insert(key, (value, self.tick));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use crate::webhook::WebhookHeaders;
+    use crate::Coupon;
+
+    use super::*;
+
+    fn coupon_delivery(delivery_id: &str, date_modified_gmt: &str) -> WebhookDelivery {
+        let mut coupon: Value =
+            serde_json::from_str(include_str!("../../fixtures/coupon.json")).unwrap();
+        coupon["date_modified_gmt"] = Value::String(date_modified_gmt.to_string());
+        let coupon: Coupon = serde_json::from_value(coupon).unwrap();
+
+        WebhookDelivery {
+            headers: WebhookHeaders {
+                delivery_id: Some(delivery_id.to_string()),
+                ..Default::default()
+            },
+            event: WebhookEvent::Coupon(Box::new(coupon)),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replayed_delivery_is_duplicate() {
+        let dedup = WebhookDeduplicator::in_memory(16);
+        let delivery = coupon_delivery("d1", "2024-01-01T10:00:00");
+
+        assert_eq!(dedup.accept(&delivery).await, DeliveryVerdict::Fresh);
+        assert_eq!(dedup.accept(&delivery).await, DeliveryVerdict::Duplicate);
+
+        // `date_modified_gmt` has one second resolution, so a second change made within
+        // the same second carries the same version under a new delivery id.
+        let changed = coupon_delivery("d2", "2024-01-01T10:00:00");
+        assert_eq!(dedup.accept(&changed).await, DeliveryVerdict::Fresh);
+    }
+
+    #[tokio::test]
+    async fn test_older_version_is_stale() {
+        let dedup = WebhookDeduplicator::in_memory(16);
+
+        let newer = coupon_delivery("d2", "2024-01-01T10:00:05");
+        assert_eq!(dedup.accept(&newer).await, DeliveryVerdict::Fresh);
+
+        let older = coupon_delivery("d1", "2024-01-01T10:00:00");
+        assert_eq!(
+            dedup.accept(&older).await,
+            DeliveryVerdict::Stale {
+                last_seen: "2024-01-01T10:00:05".parse().unwrap()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_check_does_not_record() {
+        let dedup = WebhookDeduplicator::in_memory(16);
+        let delivery = coupon_delivery("d1", "2024-01-01T10:00:00");
+
+        assert_eq!(dedup.check(&delivery).await, DeliveryVerdict::Fresh);
+        assert_eq!(dedup.check(&delivery).await, DeliveryVerdict::Fresh);
+    }
+
+    /// Yields before every call, so concurrent deliveries interleave between store calls.
+    struct YieldingStore(InMemoryDeliveryStore);
+
+    impl DeliveryStore for YieldingStore {
+        async fn has_delivery(&self, delivery_id: &str) -> bool {
+            tokio::task::yield_now().await;
+            self.0.has_delivery(delivery_id).await
+        }
+
+        async fn try_insert_delivery(&self, delivery_id: &str) -> bool {
+            tokio::task::yield_now().await;
+            self.0.try_insert_delivery(delivery_id).await
+        }
+
+        async fn last_modified(&self, resource_key: &str) -> Option<WooDateTimeUtc> {
+            tokio::task::yield_now().await;
+            self.0.last_modified(resource_key).await
+        }
+
+        async fn advance_last_modified(
+            &self,
+            resource_key: &str,
+            date_modified_gmt: WooDateTimeUtc,
+        ) -> Result<(), WooDateTimeUtc> {
+            tokio::task::yield_now().await;
+            self.0
+                .advance_last_modified(resource_key, date_modified_gmt)
+                .await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_retries_accept_once() {
+        let dedup = WebhookDeduplicator::new(YieldingStore(InMemoryDeliveryStore::new(16)));
+        let delivery = coupon_delivery("d1", "2024-01-01T10:00:00");
+        let (first, second) = tokio::join!(dedup.accept(&delivery), dedup.accept(&delivery));
+        let mut verdicts = vec![first, second];
+        verdicts.sort_by_key(|verdict| *verdict != DeliveryVerdict::Fresh);
+        assert_eq!(
+            verdicts,
+            [DeliveryVerdict::Fresh, DeliveryVerdict::Duplicate]
+        );
+
+        // A newer and an older version racing, the older one never wins.
+        let newer = coupon_delivery("d2", "2024-01-01T10:00:05");
+        let older = coupon_delivery("d3", "2024-01-01T10:00:01");
+        let (newer, _) = tokio::join!(dedup.accept(&newer), dedup.accept(&older));
+        assert_eq!(newer, DeliveryVerdict::Fresh);
+        assert_eq!(
+            dedup
+                .check(&coupon_delivery("d4", "2024-01-01T10:00:01"))
+                .await,
+            DeliveryVerdict::Stale {
+                last_seen: "2024-01-01T10:00:05".parse().unwrap()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_record_keeps_newest_version() {
+        let dedup = WebhookDeduplicator::in_memory(16);
+        dedup
+            .record(&coupon_delivery("d2", "2024-01-01T10:00:05"))
+            .await;
+        dedup
+            .record(&coupon_delivery("d1", "2024-01-01T10:00:00"))
+            .await;
+
+        let late = coupon_delivery("d3", "2024-01-01T10:00:03");
+        assert_eq!(
+            dedup.check(&late).await,
+            DeliveryVerdict::Stale {
+                last_seen: "2024-01-01T10:00:05".parse().unwrap()
+            }
+        );
+    }
+
+    #[test]
+    fn test_lru_evicts_least_recently_used() {
+        let mut lru = Lru::new(2);
+        lru.insert("a", 1);
+        lru.insert("b", 2);
+        assert_eq!(lru.get(&"a"), Some(&1));
+        lru.insert("c", 3);
+
+        assert_eq!(lru.get(&"b"), None);
+        assert_eq!(lru.get(&"a"), Some(&1));
+        assert_eq!(lru.get(&"c"), Some(&3));
+    }
+}
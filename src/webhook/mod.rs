@@ -0,0 +1,13 @@
+mod signature;
+pub use signature::*;
+
+mod receiver;
+pub use receiver::*;
+
+mod dedup;
+pub use dedup::*;
+
+#[cfg(feature = "webhook-server")]
+mod server;
+#[cfg(feature = "webhook-server")]
+pub use server::*;
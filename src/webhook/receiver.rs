@@ -0,0 +1,272 @@
+use std::fmt;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{Coupon, Order, Product, WebhookId, WebhookTopic};
+
+use super::verify_signature;
+
+pub const HEADER_SIGNATURE: &str = "x-wc-webhook-signature";
+pub const HEADER_TOPIC: &str = "x-wc-webhook-topic";
+pub const HEADER_RESOURCE: &str = "x-wc-webhook-resource";
+pub const HEADER_EVENT: &str = "x-wc-webhook-event";
+pub const HEADER_WEBHOOK_ID: &str = "x-wc-webhook-id";
+pub const HEADER_DELIVERY_ID: &str = "x-wc-webhook-delivery-id";
+pub const HEADER_SOURCE: &str = "x-wc-webhook-source";
+
+#[derive(Debug)]
+pub enum WebhookError {
+    MissingHeader(&'static str),
+    InvalidHeader(&'static str, String),
+    InvalidSignature,
+    SerdeError(serde_json::Error),
+}
+
+impl fmt::Display for WebhookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WebhookError::MissingHeader(name) => write!(f, "missing header {}", name),
+            WebhookError::InvalidHeader(name, value) => {
+                write!(f, "invalid header {}: {}", name, value)
+            }
+            WebhookError::InvalidSignature => write!(f, "invalid webhook signature"),
+            WebhookError::SerdeError(err) => write!(f, "invalid webhook payload: {}", err),
+        }
+    }
+}
+
+impl From<serde_json::Error> for WebhookError {
+    fn from(err: serde_json::Error) -> Self {
+        WebhookError::SerdeError(err)
+    }
+}
+
+/// The `X-WC-Webhook-*` headers of a delivery.
+#[derive(Debug, Clone, Default)]
+pub struct WebhookHeaders {
+    pub signature: Option<String>,
+    pub topic: Option<WebhookTopic>,
+    pub resource: Option<String>,
+    pub event: Option<String>,
+    pub webhook_id: Option<WebhookId>,
+    pub delivery_id: Option<String>,
+    pub source: Option<String>,
+}
+
+impl WebhookHeaders {
+    /// Picks the WooCommerce headers out of any `(name, value)` list; names are matched
+    /// case-insensitively so this works with whatever the http framework hands over.
+    pub fn from_pairs<I, K, V>(headers: I) -> Result<Self, WebhookError>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let mut result = WebhookHeaders::default();
+        for (name, value) in headers {
+            let value = value.as_ref().trim().to_string();
+            match name.as_ref().to_ascii_lowercase().as_str() {
+                HEADER_SIGNATURE => result.signature = Some(value),
+                HEADER_TOPIC => result.topic = Some(value.parse().unwrap()),
+                HEADER_RESOURCE => result.resource = Some(value),
+                HEADER_EVENT => result.event = Some(value),
+                HEADER_WEBHOOK_ID => {
+                    let id = value
+                        .parse()
+                        .map_err(|_| WebhookError::InvalidHeader(HEADER_WEBHOOK_ID, value))?;
+                    result.webhook_id = Some(id);
+                }
+                HEADER_DELIVERY_ID => result.delivery_id = Some(value),
+                HEADER_SOURCE => result.source = Some(value),
+                _ => {}
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+#[derive(Debug)]
+pub enum WebhookEvent {
+    /// Sent once when a webhook is created or activated, as `webhook_id=<id>` form data.
+    Ping {
+        webhook_id: WebhookId,
+    },
+    Order(Box<Order>),
+    Product(Box<Product>),
+    Coupon(Box<Coupon>),
+    /// `*.deleted` deliveries only carry the id of the removed resource.
+    Deleted {
+        resource: String,
+        id: u64,
+    },
+    /// Action hooks and resources without a typed model.
+    Other(Value),
+}
+
+#[derive(Debug)]
+pub struct WebhookDelivery {
+    pub headers: WebhookHeaders,
+    pub event: WebhookEvent,
+}
+
+#[derive(Deserialize)]
+struct DeletedPayload {
+    id: u64,
+}
+
+/// Verifies and decodes webhook deliveries. Framework agnostic: feed it the raw body
+/// bytes (before any JSON parsing) together with the request headers.
+pub struct WebhookReceiver {
+    secret: String,
+}
+
+impl WebhookReceiver {
+    pub fn new(secret: &str) -> Self {
+        assert!(!secret.is_empty());
+
+        Self {
+            secret: secret.to_string(),
+        }
+    }
+
+    pub fn receive<I, K, V>(&self, headers: I, body: &[u8]) -> Result<WebhookDelivery, WebhookError>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let headers = WebhookHeaders::from_pairs(headers)?;
+
+        // WooCommerce does not sign the activation ping.
+        if let Some(webhook_id) = parse_ping(body) {
+            if let Some(signature) = &headers.signature {
+                if !verify_signature(&self.secret, body, signature) {
+                    return Err(WebhookError::InvalidSignature);
+                }
+            }
+
+            return Ok(WebhookDelivery {
+                headers,
+                event: WebhookEvent::Ping { webhook_id },
+            });
+        }
+
+        let signature = headers
+            .signature
+            .as_ref()
+            .ok_or(WebhookError::MissingHeader(HEADER_SIGNATURE))?;
+        if !verify_signature(&self.secret, body, signature) {
+            return Err(WebhookError::InvalidSignature);
+        }
+
+        let topic = headers
+            .topic
+            .as_ref()
+            .ok_or(WebhookError::MissingHeader(HEADER_TOPIC))?;
+        let resource = headers
+            .resource
+            .clone()
+            .unwrap_or_else(|| topic.resource().to_string());
+        let event = headers.event.as_deref().unwrap_or_else(|| topic.event());
+
+        let event = if event == "deleted" {
+            let payload: DeletedPayload = serde_json::from_slice(body)?;
+            WebhookEvent::Deleted {
+                resource,
+                id: payload.id,
+            }
+        } else {
+            match resource.as_str() {
+                "order" => WebhookEvent::Order(serde_json::from_slice(body)?),
+                "product" => WebhookEvent::Product(serde_json::from_slice(body)?),
+                "coupon" => WebhookEvent::Coupon(serde_json::from_slice(body)?),
+                _ => WebhookEvent::Other(serde_json::from_slice(body)?),
+            }
+        };
+
+        Ok(WebhookDelivery { headers, event })
+    }
+}
+
+fn parse_ping(body: &[u8]) -> Option<WebhookId> {
+    let body = std::str::from_utf8(body).ok()?;
+    body.trim().strip_prefix("webhook_id=")?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::webhook::compute_signature;
+
+    use super::*;
+
+    const COUPON: &str = include_str!("../../fixtures/coupon.json");
+
+    fn headers(topic: &str, signature: &str) -> Vec<(&'static str, String)> {
+        vec![
+            ("X-WC-Webhook-Topic", topic.to_string()),
+            ("X-WC-Webhook-Signature", signature.to_string()),
+            ("X-WC-Webhook-ID", "17".to_string()),
+            ("X-WC-Webhook-Delivery-ID", "a1b2".to_string()),
+        ]
+    }
+
+    #[test]
+    fn test_receive_coupon() {
+        let receiver = WebhookReceiver::new("secret");
+        let signature = compute_signature("secret", COUPON.as_bytes());
+        let delivery = receiver
+            .receive(headers("coupon.updated", &signature), COUPON.as_bytes())
+            .unwrap();
+
+        assert_eq!(delivery.headers.webhook_id, Some(WebhookId(17)));
+        assert_eq!(delivery.headers.delivery_id.as_deref(), Some("a1b2"));
+        match delivery.event {
+            WebhookEvent::Coupon(coupon) => assert_eq!(coupon.code, "10off"),
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_receive_deleted() {
+        let receiver = WebhookReceiver::new("secret");
+        let body = br#"{"id":42}"#;
+        let signature = compute_signature("secret", body);
+        let delivery = receiver
+            .receive(headers("order.deleted", &signature), body)
+            .unwrap();
+
+        match delivery.event {
+            WebhookEvent::Deleted { resource, id } => {
+                assert_eq!(resource, "order");
+                assert_eq!(id, 42);
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_receive_ping() {
+        let receiver = WebhookReceiver::new("secret");
+        let delivery = receiver
+            .receive(Vec::<(&str, &str)>::new(), b"webhook_id=17")
+            .unwrap();
+
+        assert!(matches!(
+            delivery.event,
+            WebhookEvent::Ping {
+                webhook_id: WebhookId(17)
+            }
+        ));
+    }
+
+    #[test]
+    fn test_receive_rejects_bad_signature() {
+        let receiver = WebhookReceiver::new("secret");
+        let signature = compute_signature("other", COUPON.as_bytes());
+        let result = receiver.receive(headers("coupon.updated", &signature), COUPON.as_bytes());
+
+        assert!(matches!(result, Err(WebhookError::InvalidSignature)));
+    }
+}
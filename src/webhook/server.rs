@@ -0,0 +1,99 @@
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use service_sdk::my_logger::{LogEventCtx, LOGGER};
+
+use super::{WebhookDelivery, WebhookError, WebhookReceiver};
+
+/// Minimal listener that accepts `POST`s on any path, verifies them with `receiver`
+/// and hands the decoded delivery to `handler`. A handler error answers with 500 so
+/// WooCommerce retries the delivery later.
+pub async fn serve_webhooks<H, Fut>(
+    addr: SocketAddr,
+    receiver: WebhookReceiver,
+    handler: H,
+) -> Result<(), hyper::Error>
+where
+    H: Fn(WebhookDelivery) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<(), String>> + Send + 'static,
+{
+    let receiver = Arc::new(receiver);
+    let handler = Arc::new(handler);
+
+    let make_service = make_service_fn(move |_| {
+        let receiver = receiver.clone();
+        let handler = handler.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req| {
+                handle(receiver.clone(), handler.clone(), req)
+            }))
+        }
+    });
+
+    Server::bind(&addr).serve(make_service).await
+}
+
+async fn handle<H, Fut>(
+    receiver: Arc<WebhookReceiver>,
+    handler: Arc<H>,
+    req: Request<Body>,
+) -> Result<Response<Body>, Infallible>
+where
+    H: Fn(WebhookDelivery) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<(), String>> + Send + 'static,
+{
+    if req.method() != Method::POST {
+        return Ok(respond(StatusCode::METHOD_NOT_ALLOWED, ""));
+    }
+
+    let headers: Vec<(String, String)> = req
+        .headers()
+        .iter()
+        .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+        .collect();
+
+    let body = match hyper::body::to_bytes(req.into_body()).await {
+        Ok(body) => body,
+        Err(err) => return Ok(respond(StatusCode::BAD_REQUEST, &err.to_string())),
+    };
+
+    let delivery = match receiver.receive(headers, &body) {
+        Ok(delivery) => delivery,
+        Err(err) => {
+            LOGGER.write_warning(
+                "serve_webhooks",
+                format!("Rejected webhook delivery: {}", err),
+                LogEventCtx::new(),
+            );
+            let status = match err {
+                WebhookError::InvalidSignature | WebhookError::MissingHeader(_) => {
+                    StatusCode::UNAUTHORIZED
+                }
+                _ => StatusCode::BAD_REQUEST,
+            };
+            return Ok(respond(status, &err.to_string()));
+        }
+    };
+
+    match handler(delivery).await {
+        Ok(()) => Ok(respond(StatusCode::OK, "")),
+        Err(err) => {
+            LOGGER.write_error(
+                "serve_webhooks",
+                format!("Webhook handler failed: {}", err),
+                LogEventCtx::new(),
+            );
+            Ok(respond(StatusCode::INTERNAL_SERVER_ERROR, &err))
+        }
+    }
+}
+
+fn respond(status: StatusCode, body: &str) -> Response<Body> {
+    let mut response = Response::new(Body::from(body.to_string()));
+    *response.status_mut() = status;
+    response
+}
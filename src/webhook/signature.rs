@@ -0,0 +1,50 @@
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Value WooCommerce puts into `X-WC-Webhook-Signature`: base64 of the
+/// HMAC-SHA256 of the raw request body keyed with the webhook secret.
+pub fn compute_signature(secret: &str, body: &[u8]) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key size");
+    mac.update(body);
+    base64::engine::general_purpose::STANDARD.encode(mac.finalize().into_bytes())
+}
+
+/// Constant time check of a `X-WC-Webhook-Signature` value against the raw body.
+pub fn verify_signature(secret: &str, body: &[u8], signature: &str) -> bool {
+    let Ok(expected) = base64::engine::general_purpose::STANDARD.decode(signature.trim()) else {
+        return false;
+    };
+
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key size");
+    mac.update(body);
+    mac.verify_slice(&expected).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signature_known_vector() {
+        let body = br#"{"id":719,"code":"10off"}"#;
+        let signature = compute_signature("s3cr3t", body);
+        assert_eq!(signature, "kHZSh4H/sO7kfDJySwGIx9B1UhlVxJ1/OIzguoF3Erg=");
+        assert!(verify_signature("s3cr3t", body, &signature));
+    }
+
+    #[test]
+    fn test_signature_rejects_tampering() {
+        let body = br#"{"id":719,"code":"10off"}"#;
+        let signature = compute_signature("s3cr3t", body);
+        assert!(!verify_signature("other", body, &signature));
+        assert!(!verify_signature(
+            "s3cr3t",
+            br#"{"id":720,"code":"10off"}"#,
+            &signature
+        ));
+        assert!(!verify_signature("s3cr3t", body, "not base64!"));
+    }
+}